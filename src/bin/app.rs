@@ -2,9 +2,22 @@ use bevy::prelude::*;
 use stargazer::states::stars::Stars;
 use stargazer::states::menu::Menu;
 use stargazer::states::pause::Pause;
+use stargazer::states::tour::Tour;
 use stargazer::consts::*;
+use stargazer::render::night_vision::NightVisionPlugin;
+use stargazer::controls::bindings::BindingsPlugin;
+use stargazer::controls::navigation::GamepadsPlugin;
+use stargazer::controls::replay::{ReplayPlugin, ReplayMode};
+use std::error;
+use std::process;
+
+const USAGE: &str = "usage: app [--record <file> | --replay <file> [--timestep <seconds>]]";
 
 fn main() {
+    let replay_mode = parse_args().unwrap_or_else(|e| {
+        eprintln!("error: {}\n{}", e, USAGE);
+        process::exit(1);
+    });
     App::build()
         .insert_resource(ClearColor(Color::rgb(0., 0., 0.)))
         .insert_resource(WindowDescriptor { 
@@ -14,9 +27,40 @@ fn main() {
             vsync: false,
             ..Default::default()})
         .add_plugins(DefaultPlugins)
+        .add_plugin(ReplayPlugin{mode: replay_mode})
+        .add_plugin(BindingsPlugin)
+        .add_plugin(GamepadsPlugin)
+        .add_plugin(NightVisionPlugin)
         .add_state(AppState::Menu)
         .add_plugin(Menu)
         .add_plugin(Stars) 
         .add_plugin(Pause) 
+        .add_plugin(Tour)
         .run();
 }
+
+fn parse_args() -> Result<ReplayMode, Box<dyn error::Error>> {
+    let mut record = None;
+    let mut replay = None;
+    let mut timestep = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--record" => record = Some(args.next().ok_or("--record expects a file")?),
+            "--replay" => replay = Some(args.next().ok_or("--replay expects a file")?),
+            "--timestep" => {
+                timestep = Some(args.next().ok_or("--timestep expects a value")?.parse::<f32>()?);
+            }
+            _ => return Err(format!("unexpected argument {}", arg).into()),
+        }
+    }
+    if timestep.map_or(false, |timestep| timestep <= 0.) {
+        return Err("--timestep must be positive".into());
+    }
+    match (record, replay) {
+        (Some(_), Some(_)) => Err("cannot record and replay at the same time".into()),
+        (Some(path), None) => Ok(ReplayMode::Record(path)),
+        (None, Some(path)) => Ok(ReplayMode::Replay{path, timestep}),
+        (None, None) => Ok(ReplayMode::Live),
+    }
+}
@@ -0,0 +1,149 @@
+use bevy::math::Vec2;
+use std::error;
+use std::fs::{self, File};
+use std::process;
+use stargazer::catalog::{legacy, reader::Catalogue};
+use stargazer::render::atlas::{build_chart, ChartLayers, ChartRequest};
+use stargazer::render::projection::ProjectionKind;
+use stargazer::render::vector;
+use stargazer::world::constellation::parse_figures;
+use stargazer::world::deep_sky::{self, DeepSkyObject};
+use stargazer::world::position::Position;
+
+const USAGE: &str = "usage: stargazer-chart --centre <h:m:s,d:m:s | name> [--fov <degrees>] \
+    [--projection gnomonic|stereographic] [--max-mag <mag>] \
+    [--layers stars,constellations,grid,labels,deep-sky,legend] [--size <width>x<height>] \
+    [--catalogue <stars.bin>] [--constellations <file>] [--deep-sky <file>] <chart.svg|chart.pdf>";
+
+const CATALOGUE_PATH: &str = "assets/data/stars.bin";
+const LEGACY_CATALOGUE_PATH: &str = "assets/data/stars.csv";
+const CONSTELLATIONS_PATH: &str = "assets/data/constellations.txt";
+const DEEP_SKY_PATH: &str = "assets/data/deep_sky.csv";
+
+struct Args {
+    centre: String,
+    request: ChartRequest,
+    catalogue: Option<String>,
+    constellations: String,
+    deep_sky: String,
+    output: String,
+}
+
+/// Draw a printable star chart as SVG or PDF, without opening a window.
+fn main() {
+    let args = parse_args().unwrap_or_else(|e| {
+        eprintln!("error: {}\n{}", e, USAGE);
+        process::exit(1);
+    });
+    if let Err(e) = draw(args) {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
+
+fn parse_args() -> Result<Args, Box<dyn error::Error>> {
+    let mut request = ChartRequest::default();
+    let mut centre = None;
+    let mut catalogue = None;
+    let mut constellations = CONSTELLATIONS_PATH.to_string();
+    let mut deep_sky = DEEP_SKY_PATH.to_string();
+    let mut output = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{} expects a value", arg));
+        match arg.as_str() {
+            "--centre" => centre = Some(value()?),
+            "--fov" => request.field = value()?.parse::<f32>()?.to_radians(),
+            "--projection" => request.projection = value()?.parse::<ProjectionKind>()?,
+            "--max-mag" => request.max_mag = value()?.parse()?,
+            "--layers" => request.layers = value()?.parse::<ChartLayers>()?,
+            "--size" => request.size = parse_size(&value()?)?,
+            "--catalogue" => catalogue = Some(value()?),
+            "--constellations" => constellations = value()?,
+            "--deep-sky" => deep_sky = value()?,
+            _ if output.is_none() => output = Some(arg),
+            _ => return Err(format!("unexpected argument {}", arg).into()),
+        }
+    }
+    if !(request.field > 0. && request.field < std::f32::consts::PI) {
+        return Err("--fov expects a field between 0 and 180 degrees".into());
+    }
+    Ok(Args {
+        centre: centre.ok_or("missing --centre")?,
+        request,
+        catalogue,
+        constellations,
+        deep_sky,
+        output: output.ok_or("missing output file")?,
+    })
+}
+
+/// Chart size given as `<width>x<height>`
+fn parse_size(size: &str) -> Result<Vec2, Box<dyn error::Error>> {
+    let (width, height) = size.split_once('x').ok_or("--size expects <width>x<height>")?;
+    Ok(Vec2::new(width.trim().parse()?, height.trim().parse()?))
+}
+
+/// The catalogue given on the command line, or the app's one with the same
+/// fallback to the legacy CSV export
+fn load_catalogue(path: &Option<String>) -> Result<Catalogue, Box<dyn error::Error>> {
+    if let Some(path) = path {
+        return Catalogue::open(path);
+    }
+    Catalogue::open(CATALOGUE_PATH).or_else(|e| {
+        eprintln!("warning: could not open {} ({}), run stargazer-db to generate it", CATALOGUE_PATH, e);
+        legacy::read_csv(File::open(LEGACY_CATALOGUE_PATH)?)
+    })
+}
+
+/// Whether the centre is given as `h:m:s,d:m:s` rather than by name
+fn is_position(centre: &str) -> bool {
+    let parts: Vec<&str> = centre.split(',').collect();
+    parts.len() == 2 && parts.iter().all(|part| part.split(':').count() == 3)
+}
+
+/// Right ascension and declination in radians of a position or of a named
+/// deep sky object or star
+fn find_centre(centre: &str, catalogue: &Catalogue, objects: &[DeepSkyObject]) -> Result<(f64, f64), Box<dyn error::Error>> {
+    if is_position(centre) {
+        let position = centre.parse::<Position>()?;
+        return Ok((
+            position.right_ascension.to_degrees().to_radians(),
+            position.declination.to_degrees().to_radians(),
+        ));
+    }
+    if let Some(object) = deep_sky::find(objects, centre) {
+        return Ok((object.ra, object.dec));
+    }
+    catalogue.find(centre)
+        .map(|(star, _)| (star.ra as f64, star.dec as f64))
+        .ok_or_else(|| format!("no position or known object '{}'", centre).into())
+}
+
+fn draw(args: Args) -> Result<(), Box<dyn error::Error>> {
+    let mut request = args.request;
+    let catalogue = load_catalogue(&args.catalogue)?;
+    let objects = if request.layers.deep_sky || !is_position(&args.centre) {
+        deep_sky::read_deep_sky(File::open(&args.deep_sky)?)?
+    } else {
+        vec![]
+    };
+    let (ra, dec) = find_centre(&args.centre, &catalogue, &objects)?;
+    request.ra = ra;
+    request.dec = dec;
+    let mut figures = vec![];
+    if request.layers.constellations {
+        for figure in parse_figures(&fs::read_to_string(&args.constellations)?)? {
+            match figure.resolve(&catalogue) {
+                Ok(stars) => figures.push(stars),
+                Err(star) => eprintln!("warning: skipping {} figure, {} is not in the catalogue", figure.constellation, star),
+            }
+        }
+    }
+    let objects = if request.layers.deep_sky { objects } else { vec![] };
+    let chart = build_chart(&request, &catalogue, &figures, &objects);
+    vector::save_chart(&chart, &args.output)?;
+    println!("wrote {} stars, {} lines and {} labels to {}",
+        chart.stars.len(), chart.lines.len(), chart.labels.len(), args.output);
+    Ok(())
+}
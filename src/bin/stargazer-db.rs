@@ -0,0 +1,57 @@
+use std::error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::process;
+use stargazer::catalog::hyg;
+
+const USAGE: &str = "usage: stargazer-db [--max-mag <mag>] <hygdata.csv> [output.bin]";
+
+struct Args {
+    input: String,
+    output: String,
+    max_mag: f64,
+}
+
+/// Convert the HYG database CSV to the binary star catalogue read by the app.
+fn main() {
+    let args = parse_args().unwrap_or_else(|e| {
+        eprintln!("error: {}\n{}", e, USAGE);
+        process::exit(1);
+    });
+    match convert(&args) {
+        Ok(count) => println!("wrote {} stars to {}", count, args.output),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn parse_args() -> Result<Args, Box<dyn error::Error>> {
+    let mut max_mag = f64::INFINITY;
+    let mut positional = vec![];
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--max-mag" => {
+                max_mag = args.next().ok_or("--max-mag expects a value")?.parse()?;
+            }
+            _ => positional.push(arg),
+        }
+    }
+    let mut positional = positional.into_iter();
+    Ok(Args {
+        input: positional.next().ok_or("missing input file")?,
+        output: positional.next().unwrap_or_else(|| "assets/data/stars.bin".to_string()),
+        max_mag,
+    })
+}
+
+fn convert(args: &Args) -> Result<usize, Box<dyn error::Error>> {
+    let stars = hyg::read_hyg(File::open(&args.input)?)?;
+    let writer = hyg::to_catalogue(stars, args.max_mag);
+    let mut out = BufWriter::new(File::create(&args.output)?);
+    writer.write(&mut out)?;
+    out.flush()?;
+    Ok(writer.len())
+}
@@ -0,0 +1,167 @@
+use std::error;
+use std::io::Read;
+
+use crate::catalog::record::{pack_constellation, StarRecord};
+use crate::catalog::writer::CatalogueWriter;
+
+/// Distance HYG uses for stars without a known parallax, in parsecs.
+const UNKNOWN_DISTANCE: f64 = 100000.;
+
+/// A row of the HYG database (https://github.com/astronexus/HYG-Database).
+#[derive(Debug, Clone, PartialEq)]
+pub struct HygStar {
+    pub id: u32,
+    pub proper: String,
+    /// Bayer letter abbreviation, e.g. `Alp`.
+    pub bayer: String,
+    /// Flamsteed number.
+    pub flamsteed: String,
+    /// IAU constellation abbreviation.
+    pub constellation: String,
+    /// Right ascension in hours.
+    pub ra: f64,
+    /// Declination in degrees.
+    pub dec: f64,
+    /// Distance in parsecs.
+    pub distance: f64,
+    pub mag: f64,
+    pub abs_mag: f64,
+    pub color_index: f64,
+    pub pm_ra: f64,
+    pub pm_dec: f64,
+}
+
+impl HygStar {
+    /// Bayer designation if any, else Flamsteed designation.
+    pub fn designation(&self) -> Option<String> {
+        if self.constellation.is_empty() {
+            return None;
+        }
+        if !self.bayer.is_empty() {
+            Some(format!("{} {}", self.bayer, self.constellation))
+        } else if !self.flamsteed.is_empty() {
+            Some(format!("{} {}", self.flamsteed, self.constellation))
+        } else {
+            None
+        }
+    }
+
+    pub fn to_record(&self, name: u32, designation: u32) -> StarRecord {
+        let distance = if self.distance >= UNKNOWN_DISTANCE { f64::NAN } else { self.distance };
+        StarRecord {
+            id: self.id,
+            ra: (self.ra * 15.).to_radians() as f32,
+            dec: self.dec.to_radians() as f32,
+            mag: self.mag as f32,
+            abs_mag: self.abs_mag as f32,
+            color_index: self.color_index as f32,
+            pm_ra: self.pm_ra as f32,
+            pm_dec: self.pm_dec as f32,
+            name,
+            distance: distance as f32,
+            designation,
+            constellation: pack_constellation(&self.constellation),
+        }
+    }
+}
+
+struct Columns {
+    id: usize,
+    proper: usize,
+    bayer: usize,
+    flamsteed: usize,
+    constellation: usize,
+    ra: usize,
+    dec: usize,
+    distance: usize,
+    mag: usize,
+    abs_mag: usize,
+    color_index: usize,
+    pm_ra: usize,
+    pm_dec: usize,
+}
+
+impl Columns {
+    fn from_headers(headers: &csv::StringRecord) -> Result<Columns, Box<dyn error::Error>> {
+        let find = |name: &str| {
+            headers.iter().position(|h| h == name)
+                .ok_or_else(|| format!("missing HYG column '{}'", name))
+        };
+        Ok(Columns {
+            id: find("id")?,
+            proper: find("proper")?,
+            bayer: find("bayer")?,
+            flamsteed: find("flam")?,
+            constellation: find("con")?,
+            ra: find("ra")?,
+            dec: find("dec")?,
+            distance: find("dist")?,
+            mag: find("mag")?,
+            abs_mag: find("absmag")?,
+            color_index: find("ci")?,
+            pm_ra: find("pmra")?,
+            pm_dec: find("pmdec")?,
+        })
+    }
+}
+
+fn field<'a>(record: &'a csv::StringRecord, index: usize) -> &'a str {
+    record.get(index).unwrap_or("").trim()
+}
+
+fn required(record: &csv::StringRecord, index: usize) -> Result<f64, Box<dyn error::Error>> {
+    Ok(field(record, index).parse()?)
+}
+
+fn optional(record: &csv::StringRecord, index: usize) -> Result<f64, Box<dyn error::Error>> {
+    match field(record, index) {
+        "" => Ok(f64::NAN),
+        value => Ok(value.parse()?),
+    }
+}
+
+/// Parse every star of a HYG CSV file.
+pub fn read_hyg<R: Read>(reader: R) -> Result<Vec<HygStar>, Box<dyn error::Error>> {
+    let mut rdr = csv::ReaderBuilder::new()
+        .has_headers(true)
+        .from_reader(reader);
+    let columns = Columns::from_headers(rdr.headers()?)?;
+    let mut stars = vec![];
+    for (line, result) in rdr.records().enumerate() {
+        let record = result?;
+        let parse = || -> Result<HygStar, Box<dyn error::Error>> {
+            Ok(HygStar {
+                id: field(&record, columns.id).parse()?,
+                proper: field(&record, columns.proper).to_string(),
+                bayer: field(&record, columns.bayer).to_string(),
+                flamsteed: field(&record, columns.flamsteed).to_string(),
+                constellation: field(&record, columns.constellation).to_string(),
+                ra: required(&record, columns.ra)?,
+                dec: required(&record, columns.dec)?,
+                distance: optional(&record, columns.distance)?,
+                mag: required(&record, columns.mag)?,
+                abs_mag: optional(&record, columns.abs_mag)?,
+                color_index: optional(&record, columns.color_index)?,
+                pm_ra: optional(&record, columns.pm_ra)?,
+                pm_dec: optional(&record, columns.pm_dec)?,
+            })
+        };
+        let star = parse().map_err(|e| format!("HYG row {}: {}", line + 2, e))?;
+        stars.push(star);
+    }
+    Ok(stars)
+}
+
+/// Build a catalogue from HYG stars no fainter than `max_mag`, brightest
+/// first, skipping the Sun.
+pub fn to_catalogue(mut stars: Vec<HygStar>, max_mag: f64) -> CatalogueWriter {
+    stars.retain(|s| s.proper != "Sol" && s.mag <= max_mag);
+    stars.sort_by(|a, b| a.mag.partial_cmp(&b.mag).unwrap_or(std::cmp::Ordering::Equal));
+    let mut writer = CatalogueWriter::new();
+    for star in &stars {
+        let name = writer.add_name(&star.proper);
+        let designation = writer.add_name(&star.designation().unwrap_or_default());
+        writer.push(star.to_record(name, designation));
+    }
+    writer
+}
@@ -0,0 +1,36 @@
+use std::error;
+use std::io::Read;
+
+use crate::catalog::reader::Catalogue;
+use crate::catalog::record::{StarRecord, NO_NAME};
+use crate::catalog::writer::CatalogueWriter;
+
+/// Load the legacy `name,theta,phi,mag` CSV export into an in-memory catalogue.
+pub fn read_csv<R: Read>(reader: R) -> Result<Catalogue, Box<dyn error::Error>> {
+    let mut rdr = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .from_reader(reader);
+    let mut writer = CatalogueWriter::new();
+    for (id, result) in rdr.records().enumerate() {
+        let record = result?;
+        let get = |i: usize| record.get(i).ok_or("missing column in legacy csv");
+        let theta: f32 = get(1)?.parse()?;
+        let phi: f32 = get(2)?.parse()?;
+        let name = writer.add_name(get(0)?);
+        writer.push(StarRecord {
+            id: id as u32,
+            ra: phi,
+            dec: std::f32::consts::FRAC_PI_2 - theta,
+            mag: get(3)?.parse()?,
+            abs_mag: f32::NAN,
+            color_index: f32::NAN,
+            pm_ra: f32::NAN,
+            pm_dec: f32::NAN,
+            name,
+            distance: f32::NAN,
+            designation: NO_NAME,
+            constellation: [0; 4],
+        });
+    }
+    Catalogue::from_bytes(writer.to_bytes())
+}
@@ -0,0 +1,5 @@
+pub mod record;
+pub mod reader;
+pub mod writer;
+pub mod hyg;
+pub mod legacy;
@@ -0,0 +1,131 @@
+use std::error;
+use std::fs::File;
+use std::ops::Deref;
+use std::path::Path;
+use memmap::Mmap;
+
+use crate::catalog::record::{Header, StarRecord, HEADER_SIZE, RECORD_SIZE, NO_NAME, u32_at};
+
+enum Storage {
+    Mapped(Mmap),
+    Owned(Vec<u8>),
+}
+
+impl Deref for Storage {
+    type Target = [u8];
+    fn deref(&self) -> &[u8] {
+        match self {
+            Storage::Mapped(mmap) => &mmap[..],
+            Storage::Owned(bytes) => &bytes[..],
+        }
+    }
+}
+
+/// Read-only view over a binary star catalogue.
+///
+/// Records are decoded on access, so opening a memory-mapped catalogue
+/// costs nothing more than validating its header.
+pub struct Catalogue {
+    storage: Storage,
+    header: Header,
+}
+
+impl Catalogue {
+    /// Memory-map a catalogue file.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Catalogue, Box<dyn error::Error>> {
+        let file = File::open(path)?;
+        // Safety: the catalogue is only ever read, and asset files are not
+        // expected to be modified while the application runs.
+        let mmap = unsafe { Mmap::map(&file)? };
+        Catalogue::from_storage(Storage::Mapped(mmap))
+    }
+
+    /// Build a catalogue from an in-memory buffer.
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Catalogue, Box<dyn error::Error>> {
+        Catalogue::from_storage(Storage::Owned(bytes))
+    }
+
+    fn from_storage(storage: Storage) -> Result<Catalogue, Box<dyn error::Error>> {
+        let header = Header::from_bytes(&storage)?;
+        let names_start = HEADER_SIZE + header.count as usize * RECORD_SIZE;
+        let offsets_end = names_start + (header.name_count as usize + 1) * 4;
+        if storage.len() < offsets_end {
+            return Err("catalogue truncated".into());
+        }
+        let blob_len = u32_at(&storage, offsets_end - 4) as usize;
+        if storage.len() < offsets_end + blob_len {
+            return Err("catalogue name table truncated".into());
+        }
+        // names are sliced without further checks, so every offset must
+        // stay within the blob, which the last one bounds
+        let mut previous = 0;
+        for offset in (names_start..offsets_end).step_by(4) {
+            let offset = u32_at(&storage, offset);
+            if offset < previous {
+                return Err("catalogue name offsets out of order".into());
+            }
+            previous = offset;
+        }
+        Ok(Catalogue { storage, header })
+    }
+
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    pub fn len(&self) -> usize {
+        self.header.count as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, index: usize) -> Option<StarRecord> {
+        if index >= self.len() {
+            return None;
+        }
+        let start = HEADER_SIZE + index * RECORD_SIZE;
+        Some(StarRecord::from_bytes(&self.storage[start..start + RECORD_SIZE]))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = StarRecord> + '_ {
+        (0..self.len()).map(move |i| self.get(i).unwrap())
+    }
+
+    /// Name stored at `index` in the name table.
+    pub fn name_at(&self, index: u32) -> Option<&str> {
+        if index == NO_NAME || index >= self.header.name_count {
+            return None;
+        }
+        let names_start = HEADER_SIZE + self.len() * RECORD_SIZE;
+        let blob_start = names_start + (self.header.name_count as usize + 1) * 4;
+        let offset = names_start + index as usize * 4;
+        let start = u32_at(&self.storage, offset) as usize;
+        let end = u32_at(&self.storage, offset + 4) as usize;
+        std::str::from_utf8(&self.storage[blob_start + start..blob_start + end]).ok()
+    }
+
+    /// Name of a star, if it has one.
+    pub fn name(&self, record: &StarRecord) -> Option<&str> {
+        self.name_at(record.name)
+    }
+
+    /// Bayer or Flamsteed designation of a star, e.g. `Alp Ori` or `58 Ori`.
+    pub fn designation(&self, record: &StarRecord) -> Option<&str> {
+        self.name_at(record.designation)
+    }
+
+    /// First star with a proper name or designation matching `name`,
+    /// ignoring case, with the name it matched as written in the catalogue.
+    pub fn find(&self, name: &str) -> Option<(StarRecord, &str)> {
+        let name = name.trim().to_lowercase();
+        self.iter().find_map(|star| {
+            let matched = [self.name(&star), self.designation(&star)].iter()
+                .flatten()
+                .find(|n| n.to_lowercase() == name)
+                .copied();
+            matched.map(|n| (star, n))
+        })
+    }
+}
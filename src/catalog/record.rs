@@ -0,0 +1,167 @@
+/// Magic bytes at the start of every catalogue file.
+pub const MAGIC: [u8; 4] = *b"SGZC";
+/// Current version of the binary catalogue format.
+pub const VERSION: u16 = 2;
+/// Size in bytes of the file header.
+pub const HEADER_SIZE: usize = 16;
+/// Size in bytes of a single star record.
+pub const RECORD_SIZE: usize = 48;
+/// Name index used for stars without a name.
+pub const NO_NAME: u32 = u32::MAX;
+
+/// Header of a binary catalogue file, all fields little-endian.
+///
+/// | offset | size | field       |
+/// |--------|------|-------------|
+/// | 0      | 4    | magic       |
+/// | 4      | 2    | version     |
+/// | 6      | 2    | record size |
+/// | 8      | 4    | star count  |
+/// | 12     | 4    | name count  |
+///
+/// The header is followed by `star count` fixed-size records, then by the
+/// name table: `name count + 1` u32 offsets into a UTF-8 blob.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Header {
+    pub version: u16,
+    pub record_size: u16,
+    pub count: u32,
+    pub name_count: u32,
+}
+
+impl Header {
+    pub fn new(count: u32, name_count: u32) -> Header {
+        Header {
+            version: VERSION,
+            record_size: RECORD_SIZE as u16,
+            count,
+            name_count,
+        }
+    }
+
+    pub fn to_bytes(&self) -> [u8; HEADER_SIZE] {
+        let mut bytes = [0u8; HEADER_SIZE];
+        bytes[0..4].copy_from_slice(&MAGIC);
+        bytes[4..6].copy_from_slice(&self.version.to_le_bytes());
+        bytes[6..8].copy_from_slice(&self.record_size.to_le_bytes());
+        bytes[8..12].copy_from_slice(&self.count.to_le_bytes());
+        bytes[12..16].copy_from_slice(&self.name_count.to_le_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Header, String> {
+        if bytes.len() < HEADER_SIZE {
+            return Err("catalogue too short for header".to_string());
+        }
+        if bytes[0..4] != MAGIC {
+            return Err("not a stargazer catalogue (bad magic)".to_string());
+        }
+        let header = Header {
+            version: u16::from_le_bytes([bytes[4], bytes[5]]),
+            record_size: u16::from_le_bytes([bytes[6], bytes[7]]),
+            count: u32_at(bytes, 8),
+            name_count: u32_at(bytes, 12),
+        };
+        if header.version != VERSION {
+            return Err(format!("unsupported catalogue version {}", header.version));
+        }
+        if header.record_size as usize != RECORD_SIZE {
+            return Err(format!("unexpected record size {}", header.record_size));
+        }
+        Ok(header)
+    }
+}
+
+/// A single star, as stored in the catalogue.
+///
+/// Angles are in radians, proper motions in milliarcseconds per year and
+/// distances in parsecs. Unknown floating point values are stored as NaN.
+/// `name` and `designation` index into the name table, `constellation` is
+/// the NUL-padded IAU abbreviation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StarRecord {
+    pub id: u32,
+    pub ra: f32,
+    pub dec: f32,
+    pub mag: f32,
+    pub abs_mag: f32,
+    pub color_index: f32,
+    pub pm_ra: f32,
+    pub pm_dec: f32,
+    pub name: u32,
+    pub distance: f32,
+    pub designation: u32,
+    pub constellation: [u8; 4],
+}
+
+impl StarRecord {
+    pub fn to_bytes(&self) -> [u8; RECORD_SIZE] {
+        let mut bytes = [0u8; RECORD_SIZE];
+        bytes[0..4].copy_from_slice(&self.id.to_le_bytes());
+        bytes[4..8].copy_from_slice(&self.ra.to_le_bytes());
+        bytes[8..12].copy_from_slice(&self.dec.to_le_bytes());
+        bytes[12..16].copy_from_slice(&self.mag.to_le_bytes());
+        bytes[16..20].copy_from_slice(&self.abs_mag.to_le_bytes());
+        bytes[20..24].copy_from_slice(&self.color_index.to_le_bytes());
+        bytes[24..28].copy_from_slice(&self.pm_ra.to_le_bytes());
+        bytes[28..32].copy_from_slice(&self.pm_dec.to_le_bytes());
+        bytes[32..36].copy_from_slice(&self.name.to_le_bytes());
+        bytes[36..40].copy_from_slice(&self.distance.to_le_bytes());
+        bytes[40..44].copy_from_slice(&self.designation.to_le_bytes());
+        bytes[44..48].copy_from_slice(&self.constellation);
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> StarRecord {
+        StarRecord {
+            id: u32_at(bytes, 0),
+            ra: f32_at(bytes, 4),
+            dec: f32_at(bytes, 8),
+            mag: f32_at(bytes, 12),
+            abs_mag: f32_at(bytes, 16),
+            color_index: f32_at(bytes, 20),
+            pm_ra: f32_at(bytes, 24),
+            pm_dec: f32_at(bytes, 28),
+            name: u32_at(bytes, 32),
+            distance: f32_at(bytes, 36),
+            designation: u32_at(bytes, 40),
+            constellation: [bytes[44], bytes[45], bytes[46], bytes[47]],
+        }
+    }
+
+    /// IAU constellation abbreviation, e.g. `Ori`.
+    pub fn constellation(&self) -> Option<&str> {
+        std::str::from_utf8(&self.constellation).ok()
+            .map(|s| s.trim_end_matches('\0'))
+            .filter(|s| !s.is_empty())
+    }
+
+    /// Spherical theta (polar angle from the north celestial pole).
+    pub fn theta(&self) -> f32 {
+        std::f32::consts::FRAC_PI_2 - self.dec
+    }
+
+    /// Spherical phi (azimuthal angle, equal to right ascension).
+    pub fn phi(&self) -> f32 {
+        self.ra
+    }
+}
+
+/// Pack a constellation abbreviation into a record field.
+pub fn pack_constellation(con: &str) -> [u8; 4] {
+    let mut packed = [0u8; 4];
+    for (i, b) in con.bytes().take(4).enumerate() {
+        packed[i] = b;
+    }
+    packed
+}
+
+pub(crate) fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    let mut buf = [0u8; 4];
+    buf.copy_from_slice(&bytes[offset..offset + 4]);
+    u32::from_le_bytes(buf)
+}
+
+fn f32_at(bytes: &[u8], offset: usize) -> f32 {
+    f32::from_bits(u32_at(bytes, offset))
+}
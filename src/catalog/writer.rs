@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use crate::catalog::record::{Header, StarRecord, NO_NAME};
+
+/// Accumulates stars and names before serialising them as a catalogue.
+#[derive(Default)]
+pub struct CatalogueWriter {
+    records: Vec<StarRecord>,
+    names: Vec<String>,
+    name_lookup: HashMap<String, u32>,
+}
+
+impl CatalogueWriter {
+    pub fn new() -> CatalogueWriter {
+        Default::default()
+    }
+
+    /// Intern a name and return its index in the name table.
+    pub fn add_name(&mut self, name: &str) -> u32 {
+        if name.is_empty() {
+            return NO_NAME;
+        }
+        if let Some(index) = self.name_lookup.get(name) {
+            return *index;
+        }
+        let index = self.names.len() as u32;
+        self.names.push(name.to_string());
+        self.name_lookup.insert(name.to_string(), index);
+        index
+    }
+
+    pub fn push(&mut self, record: StarRecord) {
+        self.records.push(record);
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    pub fn write<W: Write>(&self, mut w: W) -> io::Result<()> {
+        let header = Header::new(self.records.len() as u32, self.names.len() as u32);
+        w.write_all(&header.to_bytes())?;
+        for record in &self.records {
+            w.write_all(&record.to_bytes())?;
+        }
+        let mut offset: u32 = 0;
+        w.write_all(&offset.to_le_bytes())?;
+        for name in &self.names {
+            offset += name.len() as u32;
+            w.write_all(&offset.to_le_bytes())?;
+        }
+        for name in &self.names {
+            w.write_all(name.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        self.write(&mut bytes).unwrap();
+        bytes
+    }
+}
@@ -4,4 +4,6 @@ pub enum AppState {
     Menu,
     Stars,
     Pause,
+    /// Guided tour over the sky view, pushed on top of `Stars`
+    Tour,
 }
@@ -0,0 +1,415 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use std::error;
+use std::fs;
+use std::path::Path;
+
+/// Named input actions, bound to keys, mouse buttons or gamepad buttons.
+///
+/// Bindings are read from a user config file at startup, falling back to
+/// the defaults, and can be edited and saved from the key bindings window.
+pub struct BindingsPlugin;
+
+/// User config file of the key bindings, relative to the working directory
+pub const BINDINGS_PATH: &str = "config/bindings.cfg";
+
+impl Plugin for BindingsPlugin {
+    fn build(&self, app: &mut AppBuilder){
+        app.insert_resource(load_bindings(BINDINGS_PATH));
+    }
+}
+
+/// Bindings of the config file, or the defaults when it is missing or invalid
+fn load_bindings<P: AsRef<Path>>(path: P) -> Bindings {
+    if !path.as_ref().exists() {
+        return Bindings::default();
+    }
+    match Bindings::load(&path) {
+        Ok(bindings) => bindings,
+        Err(e) => {
+            warn!("could not read key bindings from {} ({}), using the defaults", path.as_ref().display(), e);
+            Bindings::default()
+        }
+    }
+}
+
+/// Where an action is available, actions available in the same state, or
+/// in states following each other within a frame, must not share a binding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    /// Every state
+    Global,
+    /// Start menu
+    Menu,
+    /// Sky view
+    Sky,
+    /// Guided tour, played over the sky view
+    Tour,
+    /// Key bindings window waiting for the press to bind
+    Capture,
+}
+
+impl Scope {
+    /// Whether actions of both scopes can see the same press. Leaving the
+    /// menu runs the sky view in the same frame, so a press starting the sky
+    /// view would also trigger a sky action. Tours consume the press that
+    /// ends them and are started from a button, so they stay apart, and a
+    /// captured press is consumed before the sky actions read it.
+    pub fn overlaps(&self, other: Scope) -> bool {
+        let pair = |a, b| (*self == a && other == b) || (*self == b && other == a);
+        *self == other || *self == Scope::Global || other == Scope::Global || pair(Scope::Menu, Scope::Sky)
+    }
+}
+
+macro_rules! actions {
+    ($($action:ident: $name:expr, $label:expr, $scope:ident;)*) => {
+        /// Something the user can do from a key or button
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum Action {
+            $($action,)*
+        }
+
+        impl Action {
+            pub const ALL: &'static [Action] = &[$(Action::$action,)*];
+
+            /// Identifier used in the config file
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(Action::$action => $name,)*
+                }
+            }
+
+            /// Human readable description
+            pub fn label(&self) -> &'static str {
+                match self {
+                    $(Action::$action => $label,)*
+                }
+            }
+
+            pub fn scope(&self) -> Scope {
+                match self {
+                    $(Action::$action => Scope::$scope,)*
+                }
+            }
+        }
+    };
+}
+
+actions! {
+    Start: "start", "Start", Menu;
+    Pause: "pause", "Pause", Sky;
+    NightVision: "night_vision", "Night vision", Global;
+    ToggleGrid: "toggle_grid", "Show grids", Sky;
+    ToggleLabels: "toggle_labels", "Show grid labels", Sky;
+    TimeFaster: "time_faster", "Time 10x faster", Sky;
+    TimeSlower: "time_slower", "Time 10x slower", Sky;
+    TimeNormal: "time_normal", "Real time", Sky;
+    Search: "search", "Search a star", Sky;
+    Pick: "pick", "Pick a star", Sky;
+    Settings: "settings", "Key bindings", Sky;
+    Screenshot: "screenshot", "Save a screenshot", Sky;
+    PanLeft: "pan_left", "Pan left", Sky;
+    PanRight: "pan_right", "Pan right", Sky;
+    PanUp: "pan_up", "Pan up", Sky;
+    PanDown: "pan_down", "Pan down", Sky;
+    ZoomIn: "zoom_in", "Zoom in", Sky;
+    ZoomOut: "zoom_out", "Zoom out", Sky;
+    RollLeft: "roll_left", "Roll left", Sky;
+    RollRight: "roll_right", "Roll right", Sky;
+    SnapNorth: "snap_north", "Look north", Sky;
+    SnapEast: "snap_east", "Look east", Sky;
+    SnapSouth: "snap_south", "Look south", Sky;
+    SnapWest: "snap_west", "Look west", Sky;
+    SnapZenith: "snap_zenith", "Look at the zenith", Sky;
+    SnapPole: "snap_pole", "Look at the celestial pole", Sky;
+    TourPlay: "tour_play", "Play or pause the tour", Tour;
+    TourStep: "tour_step", "Next tour step", Tour;
+    TourStop: "tour_stop", "Leave the tour", Tour;
+    CancelCapture: "cancel_capture", "Cancel adding a binding", Capture;
+}
+
+impl Action {
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL.iter().copied().find(|action| action.name() == name)
+    }
+}
+
+macro_rules! names {
+    ($table:ident, $type:ident, $($variant:ident),*) => {
+        const $table: &[($type, &str)] = &[$(($type::$variant, stringify!($variant))),*];
+    };
+}
+
+names!(KEY_NAMES, KeyCode,
+    Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9, Key0, A, B, C, D, E, F, G, H, I, J,
+    K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z, Escape, F1, F2, F3, F4, F5, F6, F7, F8, F9,
+    F10, F11, F12, F13, F14, F15, F16, F17, F18, F19, F20, F21, F22, F23, F24, Snapshot,
+    Scroll, Pause, Insert, Home, Delete, End, PageDown, PageUp, Left, Up, Right, Down, Back,
+    Return, Space, Compose, Caret, Numlock, Numpad0, Numpad1, Numpad2, Numpad3, Numpad4,
+    Numpad5, Numpad6, Numpad7, Numpad8, Numpad9, AbntC1, AbntC2, NumpadAdd, Apostrophe, Apps,
+    Asterisk, Plus, At, Ax, Backslash, Calculator, Capital, Colon, Comma, Convert,
+    NumpadDecimal, NumpadDivide, Equals, Grave, Kana, Kanji, LAlt, LBracket, LControl, LShift,
+    LWin, Mail, MediaSelect, MediaStop, Minus, NumpadMultiply, Mute, MyComputer,
+    NavigateForward, NavigateBackward, NextTrack, NoConvert, NumpadComma, NumpadEnter,
+    NumpadEquals, Oem102, Period, PlayPause, Power, PrevTrack, RAlt, RBracket, RControl,
+    RShift, RWin, Semicolon, Slash, Sleep, Stop, NumpadSubtract, Sysrq, Tab, Underline,
+    Unlabeled, VolumeDown, VolumeUp, Wake, WebBack, WebFavorites, WebForward, WebHome,
+    WebRefresh, WebSearch, WebStop, Yen, Copy, Paste, Cut);
+
+names!(GAMEPAD_NAMES, GamepadButtonType,
+    South, East, North, West, C, Z, LeftTrigger, LeftTrigger2, RightTrigger, RightTrigger2,
+    Select, Start, Mode, LeftThumb, RightThumb, DPadUp, DPadDown, DPadLeft, DPadRight);
+
+/// Current state of every input device an action can be bound to
+pub struct ActionInput<'a> {
+    pub keys: &'a Input<KeyCode>,
+    pub mouse: &'a Input<MouseButton>,
+    pub gamepad: &'a Input<GamepadButton>,
+}
+
+/// A key, mouse button, or button of any gamepad
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButtonType),
+}
+
+impl Binding {
+    /// Name used in the config file and the UI, e.g. `Escape`, `Mouse:Right`
+    /// or `Gamepad:Start`
+    pub fn name(&self) -> String {
+        match self {
+            Binding::Key(code) => lookup_name(KEY_NAMES, code).to_string(),
+            Binding::Mouse(MouseButton::Other(n)) => format!("Mouse:{}", n),
+            Binding::Mouse(button) => format!("Mouse:{:?}", button),
+            Binding::Gamepad(button) => format!("Gamepad:{}", lookup_name(GAMEPAD_NAMES, button)),
+        }
+    }
+
+    pub fn parse(name: &str) -> Result<Binding, Box<dyn error::Error>> {
+        let name = name.trim();
+        let binding = if let Some(button) = name.strip_prefix("Mouse:") {
+            Binding::Mouse(match button {
+                "Left" => MouseButton::Left,
+                "Right" => MouseButton::Right,
+                "Middle" => MouseButton::Middle,
+                other => MouseButton::Other(other.parse()?),
+            })
+        } else if let Some(button) = name.strip_prefix("Gamepad:") {
+            Binding::Gamepad(lookup_value(GAMEPAD_NAMES, button).ok_or(format!("unknown gamepad button {}", button))?)
+        } else {
+            Binding::Key(lookup_value(KEY_NAMES, name).ok_or(format!("unknown key {}", name))?)
+        };
+        Ok(binding)
+    }
+
+    pub fn pressed(&self, input: &ActionInput) -> bool {
+        match self {
+            Binding::Key(code) => input.keys.pressed(*code),
+            Binding::Mouse(button) => input.mouse.pressed(*button),
+            Binding::Gamepad(button) => input.gamepad.get_pressed().any(|pressed| pressed.1 == *button),
+        }
+    }
+
+    pub fn just_pressed(&self, input: &ActionInput) -> bool {
+        match self {
+            Binding::Key(code) => input.keys.just_pressed(*code),
+            Binding::Mouse(button) => input.mouse.just_pressed(*button),
+            Binding::Gamepad(button) => input.gamepad.get_just_pressed().any(|pressed| pressed.1 == *button),
+        }
+    }
+
+    /// First key, mouse button other than the left one, or gamepad button
+    /// pressed this frame, used to capture a new binding
+    pub fn first_pressed(input: &ActionInput) -> Option<Binding> {
+        input.keys.get_just_pressed().next().map(|code| Binding::Key(*code))
+            .or_else(|| input.mouse.get_just_pressed()
+                .find(|button| **button != MouseButton::Left)
+                .map(|button| Binding::Mouse(*button)))
+            .or_else(|| input.gamepad.get_just_pressed().next().map(|button| Binding::Gamepad(button.1)))
+    }
+}
+
+fn lookup_name<T: PartialEq>(table: &[(T, &'static str)], value: &T) -> &'static str {
+    table.iter().find(|(v, _)| v == value).map(|(_, name)| *name).unwrap_or("?")
+}
+
+fn lookup_value<T: Copy>(table: &[(T, &str)], name: &str) -> Option<T> {
+    table.iter().find(|(_, n)| *n == name).map(|(v, _)| *v)
+}
+
+/// A binding shared by actions available at the same time
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    pub binding: Binding,
+    pub actions: Vec<Action>,
+}
+
+/// Bindings of every action
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bindings(pub HashMap<Action, Vec<Binding>>);
+
+impl Default for Bindings {
+    fn default() -> Self {
+        use Binding::{Key, Mouse, Gamepad};
+        let defaults = vec![
+            (Action::Start, vec![Key(KeyCode::Return), Gamepad(GamepadButtonType::Start)]),
+            (Action::Pause, vec![Key(KeyCode::Escape), Gamepad(GamepadButtonType::Select)]),
+            (Action::NightVision, vec![Key(KeyCode::N)]),
+            (Action::ToggleGrid, vec![Key(KeyCode::G)]),
+            (Action::ToggleLabels, vec![Key(KeyCode::L)]),
+            (Action::TimeFaster, vec![Key(KeyCode::Period)]),
+            (Action::TimeSlower, vec![Key(KeyCode::Comma)]),
+            (Action::TimeNormal, vec![Key(KeyCode::Key0)]),
+            (Action::Search, vec![Key(KeyCode::F)]),
+            (Action::Pick, vec![Mouse(MouseButton::Right), Gamepad(GamepadButtonType::South)]),
+            (Action::Settings, vec![Key(KeyCode::F1)]),
+            (Action::Screenshot, vec![Key(KeyCode::F12), Key(KeyCode::Snapshot)]),
+            (Action::PanLeft, vec![Key(KeyCode::Left), Key(KeyCode::A), Gamepad(GamepadButtonType::DPadLeft)]),
+            (Action::PanRight, vec![Key(KeyCode::Right), Key(KeyCode::D), Gamepad(GamepadButtonType::DPadRight)]),
+            (Action::PanUp, vec![Key(KeyCode::Up), Key(KeyCode::W), Gamepad(GamepadButtonType::DPadUp)]),
+            (Action::PanDown, vec![Key(KeyCode::Down), Key(KeyCode::S), Gamepad(GamepadButtonType::DPadDown)]),
+            (Action::ZoomIn, vec![Key(KeyCode::Equals), Key(KeyCode::Plus), Key(KeyCode::NumpadAdd)]),
+            (Action::ZoomOut, vec![Key(KeyCode::Minus), Key(KeyCode::NumpadSubtract)]),
+            (Action::RollLeft, vec![Key(KeyCode::Q), Gamepad(GamepadButtonType::LeftTrigger)]),
+            (Action::RollRight, vec![Key(KeyCode::E), Gamepad(GamepadButtonType::RightTrigger)]),
+            (Action::SnapNorth, vec![Key(KeyCode::Key1)]),
+            (Action::SnapEast, vec![Key(KeyCode::Key2)]),
+            (Action::SnapSouth, vec![Key(KeyCode::Key3)]),
+            (Action::SnapWest, vec![Key(KeyCode::Key4)]),
+            (Action::SnapZenith, vec![Key(KeyCode::Z)]),
+            (Action::SnapPole, vec![Key(KeyCode::P)]),
+            (Action::TourPlay, vec![Key(KeyCode::Space), Gamepad(GamepadButtonType::Start)]),
+            (Action::TourStep, vec![Key(KeyCode::Right), Gamepad(GamepadButtonType::South)]),
+            (Action::TourStop, vec![Key(KeyCode::Escape), Gamepad(GamepadButtonType::East)]),
+            (Action::CancelCapture, vec![Key(KeyCode::Escape)]),
+        ];
+        Bindings(defaults.into_iter().collect())
+    }
+}
+
+impl Bindings {
+    pub fn get(&self, action: Action) -> &[Binding] {
+        self.0.get(&action).map(|bindings| bindings.as_slice()).unwrap_or(&[])
+    }
+
+    /// Name of the first binding of the action, for hints in the UI
+    pub fn hint(&self, action: Action) -> Option<String> {
+        self.get(action).first().map(|binding| binding.name())
+    }
+
+    /// Whether any binding of the action is held
+    pub fn pressed(&self, action: Action, input: &ActionInput) -> bool {
+        self.get(action).iter().any(|binding| binding.pressed(input))
+    }
+
+    /// Whether any binding of the action was pressed this frame
+    pub fn just_pressed(&self, action: Action, input: &ActionInput) -> bool {
+        self.get(action).iter().any(|binding| binding.just_pressed(input))
+    }
+
+    /// Release every binding of an action, so that systems running later in
+    /// the frame do not see it pressed
+    pub fn consume(
+        &self,
+        action: Action,
+        keys: &mut Input<KeyCode>,
+        mouse: &mut Input<MouseButton>,
+        gamepad: &mut Input<GamepadButton>,
+    ) {
+        for binding in self.get(action) {
+            match binding {
+                Binding::Key(code) => keys.reset(*code),
+                Binding::Mouse(button) => mouse.reset(*button),
+                Binding::Gamepad(button) => {
+                    let pressed: Vec<GamepadButton> = gamepad.get_pressed()
+                        .filter(|pressed| pressed.1 == *button)
+                        .copied()
+                        .collect();
+                    for pressed in pressed {
+                        gamepad.reset(pressed);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Bind one more input to an action, ignoring duplicates
+    pub fn add(&mut self, action: Action, binding: Binding) {
+        let bindings = self.0.entry(action).or_insert_with(Vec::new);
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    pub fn remove(&mut self, action: Action, binding: Binding) {
+        if let Some(bindings) = self.0.get_mut(&action) {
+            bindings.retain(|b| *b != binding);
+        }
+    }
+
+    /// Bindings shared by actions available in the same state, in action
+    /// order
+    pub fn conflicts(&self) -> Vec<Conflict> {
+        let mut conflicts: Vec<Conflict> = vec![];
+        for action in Action::ALL {
+            for binding in self.get(*action) {
+                for scope in [Scope::Menu, Scope::Sky, Scope::Tour, Scope::Capture].iter() {
+                    let actions: Vec<Action> = Action::ALL.iter().copied()
+                        .filter(|other| other.scope().overlaps(*scope) && self.get(*other).contains(binding))
+                        .collect();
+                    let conflict = Conflict{binding: *binding, actions};
+                    if conflict.actions.len() > 1 && !conflicts.contains(&conflict) {
+                        conflicts.push(conflict);
+                    }
+                }
+            }
+        }
+        conflicts
+    }
+
+    /// Config file contents, one `action = binding, binding` line per action
+    pub fn to_config(&self) -> String {
+        let mut config = String::from("# Stargazer key bindings: action = binding, binding\n");
+        for action in Action::ALL {
+            let names: Vec<String> = self.get(*action).iter().map(|binding| binding.name()).collect();
+            config.push_str(&format!("{} = {}\n", action.name(), names.join(", ")));
+        }
+        config
+    }
+
+    /// Parse a config file, actions it does not mention keep their defaults
+    pub fn from_config(config: &str) -> Result<Bindings, Box<dyn error::Error>> {
+        let mut bindings = Bindings::default();
+        for (number, line) in config.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut parts = line.splitn(2, '=');
+            let name = parts.next().unwrap_or("").trim();
+            let values = parts.next().ok_or(format!("line {}: expected action = bindings", number + 1))?;
+            let action = Action::from_name(name).ok_or(format!("line {}: unknown action {}", number + 1, name))?;
+            let parsed = values.split(',')
+                .filter(|value| !value.trim().is_empty())
+                .map(Binding::parse)
+                .collect::<Result<Vec<Binding>, _>>()
+                .map_err(|e| format!("line {}: {}", number + 1, e))?;
+            bindings.0.insert(action, parsed);
+        }
+        Ok(bindings)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Bindings, Box<dyn error::Error>> {
+        Bindings::from_config(&fs::read_to_string(path)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn error::Error>> {
+        if let Some(parent) = path.as_ref().parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, self.to_config())?;
+        Ok(())
+    }
+}
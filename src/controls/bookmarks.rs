@@ -0,0 +1,174 @@
+use cgmath::Quaternion;
+use std::error;
+use std::fs;
+use std::path::Path;
+use crate::render::camera::{CameraFrame, CameraMode};
+use crate::render::projection::ProjectionKind;
+use crate::world::observer::Observer;
+
+/// User file of the saved views, relative to the working directory
+pub const BOOKMARKS_PATH: &str = "config/bookmarks.cfg";
+
+/// A named view: where the camera looks, from where and when, and what is
+/// shown
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bookmark {
+    pub name: String,
+    /// Camera orientation in its reference frame
+    pub orientation: Quaternion<f64>,
+    pub frame: CameraFrame,
+    pub mode: CameraMode,
+    /// Vertical field of view in radians
+    pub fov: f32,
+    pub projection: ProjectionKind,
+    pub observer: Observer,
+    /// Sky quality meter reading of the site (mag/arcsec²)
+    pub sky_quality: f32,
+    /// Simulated julian date and time rate
+    pub jd: f64,
+    pub rate: f64,
+    /// Names of the layers shown
+    pub layers: Vec<String>,
+}
+
+/// Saved views, in the order they were added
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Bookmarks(pub Vec<Bookmark>);
+
+impl Bookmarks {
+    /// File contents: a `[name]` header per view followed by `key = value`
+    /// lines
+    pub fn to_config(&self) -> String {
+        let mut config = String::from("# Stargazer saved views\n");
+        for bookmark in &self.0 {
+            let q = bookmark.orientation;
+            let observer = &bookmark.observer;
+            config.push_str(&format!("\n[{}]\n", bookmark.name));
+            config.push_str(&format!("orientation = {} {} {} {}\n", q.s, q.v.x, q.v.y, q.v.z));
+            config.push_str(&format!("frame = {}\n", match bookmark.frame {
+                CameraFrame::Equatorial => "equatorial",
+                CameraFrame::Horizon => "horizon",
+            }));
+            config.push_str(&format!("mode = {}\n", match bookmark.mode {
+                CameraMode::KeepLevel => "level",
+                CameraMode::Free => "free",
+            }));
+            config.push_str(&format!("fov = {}\n", bookmark.fov));
+            config.push_str(&format!("projection = {}\n", bookmark.projection.name()));
+            config.push_str(&format!("observer = {}; {}; {}; {}\n",
+                observer.name, observer.latitude, observer.longitude, observer.bortle));
+            config.push_str(&format!("sky_quality = {}\n", bookmark.sky_quality));
+            config.push_str(&format!("time = {} {}\n", bookmark.jd, bookmark.rate));
+            config.push_str(&format!("layers = {}\n", bookmark.layers.join(", ")));
+        }
+        config
+    }
+
+    pub fn from_config(config: &str) -> Result<Bookmarks, Box<dyn error::Error>> {
+        let mut bookmarks = vec![];
+        let mut current: Option<Bookmark> = None;
+        for (number, line) in config.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line.starts_with('[') && line.ends_with(']') {
+                if let Some(bookmark) = current.take() {
+                    bookmarks.push(bookmark);
+                }
+                current = Some(Bookmark::named(&line[1..line.len() - 1]));
+                continue;
+            }
+            let bookmark = current.as_mut().ok_or(format!("line {}: expected a [name] header", number + 1))?;
+            bookmark.set(line).map_err(|e| format!("line {}: {}", number + 1, e))?;
+        }
+        if let Some(bookmark) = current {
+            bookmarks.push(bookmark);
+        }
+        Ok(Bookmarks(bookmarks))
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Bookmarks, Box<dyn error::Error>> {
+        Bookmarks::from_config(&fs::read_to_string(path)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn error::Error>> {
+        if let Some(parent) = path.as_ref().parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, self.to_config())?;
+        Ok(())
+    }
+}
+
+impl Bookmark {
+    /// Default view under a name, before its settings are read
+    fn named(name: &str) -> Bookmark {
+        Bookmark {
+            name: name.to_string(),
+            orientation: Quaternion::new(1., 0., 0., 0.),
+            frame: CameraFrame::Equatorial,
+            mode: CameraMode::KeepLevel,
+            fov: 1.6,
+            projection: ProjectionKind::Gnomonic,
+            observer: Observer::default(),
+            sky_quality: 21.,
+            jd: 2451545.,
+            rate: 1.,
+            layers: vec![],
+        }
+    }
+
+    /// Read one `key = value` line
+    fn set(&mut self, line: &str) -> Result<(), Box<dyn error::Error>> {
+        let mut parts = line.splitn(2, '=');
+        let key = parts.next().unwrap_or("").trim();
+        let value = parts.next().ok_or("expected key = value")?.trim();
+        let numbers = || value.split_whitespace().map(|n| n.parse::<f64>()).collect::<Result<Vec<f64>, _>>();
+        match key {
+            "orientation" => match numbers()?.as_slice() {
+                [s, x, y, z] => self.orientation = Quaternion::new(*s, *x, *y, *z),
+                _ => return Err("orientation needs 4 numbers".into()),
+            },
+            "frame" => self.frame = match value {
+                "equatorial" => CameraFrame::Equatorial,
+                "horizon" => CameraFrame::Horizon,
+                _ => return Err(format!("unknown frame {}", value).into()),
+            },
+            "mode" => self.mode = match value {
+                "level" => CameraMode::KeepLevel,
+                "free" => CameraMode::Free,
+                _ => return Err(format!("unknown mode {}", value).into()),
+            },
+            "fov" => self.fov = value.parse()?,
+            "projection" => self.projection = value.parse()?,
+            "observer" => {
+                // split from the end, site names may contain `;`
+                let mut fields: Vec<&str> = value.rsplitn(4, ';').map(|field| field.trim()).collect();
+                fields.reverse();
+                match fields.as_slice() {
+                    [name, latitude, longitude, bortle] => {
+                        self.observer = Observer::new(name, latitude.parse()?, longitude.parse()?, bortle.parse()?);
+                    }
+                    _ => return Err("observer needs name; latitude; longitude; bortle".into()),
+                }
+            }
+            "sky_quality" => self.sky_quality = value.parse()?,
+            "time" => match numbers()?.as_slice() {
+                [jd, rate] => {
+                    self.jd = *jd;
+                    self.rate = *rate;
+                }
+                _ => return Err("time needs a julian date and a rate".into()),
+            },
+            "layers" => {
+                self.layers = value.split(',')
+                    .map(|layer| layer.trim().to_string())
+                    .filter(|layer| !layer.is_empty())
+                    .collect();
+            }
+            _ => return Err(format!("unknown setting {}", key).into()),
+        }
+        Ok(())
+    }
+}
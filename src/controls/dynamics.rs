@@ -0,0 +1,73 @@
+use bevy::math::Vec3;
+
+/// Damping and sensitivity of the camera motion.
+///
+/// Pan velocities are in radians per second at a field of view of π, the
+/// camera turning slower as it zooms in. Zoom velocity is in radians of
+/// field of view per second.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraDynamics {
+    /// Rate at which the motion dies out, per second
+    pub damping: f32,
+    /// Pan velocity thrown by a pixel of mouse drag
+    pub drag_sensitivity: f32,
+    /// Zoom velocity given by a line of mouse wheel
+    pub scroll_sensitivity: f32,
+}
+
+impl Default for CameraDynamics {
+    fn default() -> Self {
+        CameraDynamics {
+            damping: 1.5,
+            drag_sensitivity: 0.012,
+            scroll_sensitivity: 0.3,
+        }
+    }
+}
+
+/// Pixels of a touchpad scroll worth a line of mouse wheel
+pub const PIXELS_PER_LINE: f32 = 50.;
+
+/// Camera motion: x pitches up, y yaws left and z zooms in
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct CameraInertia {
+    pub velocity: Vec3,
+    /// Acceleration held until the next step, from keys and sticks
+    pub acceleration: Vec3,
+}
+
+impl CameraInertia {
+    /// Sudden change of velocity, from a mouse throw or a scroll
+    pub fn push(&mut self, velocity: Vec3) {
+        self.velocity += velocity;
+    }
+
+    /// Acceleration for the coming step, added by every held control
+    pub fn accelerate(&mut self, acceleration: Vec3) {
+        self.acceleration += acceleration;
+    }
+
+    pub fn stop(&mut self) {
+        *self = CameraInertia::default();
+    }
+
+    /// Advance the motion by `seconds` and return how far it went.
+    ///
+    /// The damped motion `dv/dt = a - k v` is integrated exactly over the
+    /// step, so that a trajectory does not depend on the frame rate.
+    pub fn step(&mut self, dynamics: &CameraDynamics, seconds: f32) -> Vec3 {
+        let acceleration = self.acceleration;
+        self.acceleration = Vec3::ZERO;
+        let k = dynamics.damping;
+        if k <= 0. {
+            let distance = self.velocity * seconds + acceleration * seconds * seconds / 2.;
+            self.velocity += acceleration * seconds;
+            return distance;
+        }
+        let terminal = acceleration / k;
+        let decay = (-k * seconds).exp();
+        let distance = terminal * seconds + (self.velocity - terminal) * (1. - decay) / k;
+        self.velocity = terminal + (self.velocity - terminal) * decay;
+        distance
+    }
+}
@@ -0,0 +1,5 @@
+pub mod navigation;
+pub mod dynamics;
+pub mod bindings;
+pub mod bookmarks;
+pub mod replay;
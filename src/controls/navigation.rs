@@ -0,0 +1,131 @@
+use bevy::prelude::*;
+use crate::controls::bindings::{Action, ActionInput, Bindings};
+
+/// Keeps track of connected gamepads in every app state, pads being usually
+/// connected at launch while the menu shows
+pub struct GamepadsPlugin;
+
+impl Plugin for GamepadsPlugin {
+    fn build(&self, app: &mut AppBuilder){
+        app.init_resource::<Gamepads>()
+            .add_system(track_gamepads.system());
+    }
+}
+
+/// Gamepads currently connected
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Gamepads(pub Vec<Gamepad>);
+
+impl Gamepads {
+    /// Follow a connection or disconnection
+    pub fn update(&mut self, event: &GamepadEvent) {
+        let GamepadEvent(gamepad, event_type) = event;
+        match event_type {
+            GamepadEventType::Connected if !self.0.contains(gamepad) => self.0.push(*gamepad),
+            GamepadEventType::Disconnected => self.0.retain(|g| g != gamepad),
+            _ => {}
+        }
+    }
+}
+
+fn track_gamepads(mut gamepads: ResMut<Gamepads>, mut gamepad_evr: EventReader<GamepadEvent>) {
+    for event in gamepad_evr.iter() {
+        gamepads.update(event);
+    }
+}
+
+/// Gamepad navigation: left stick pans, right stick zooms
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StickSettings {
+    /// Stick deflection below which input is ignored
+    pub dead_zone: f32,
+    /// Pan acceleration at full deflection
+    pub pan_speed: f32,
+    /// Zoom acceleration at full deflection
+    pub zoom_speed: f32,
+}
+
+impl Default for StickSettings {
+    fn default() -> Self {
+        StickSettings {
+            dead_zone: 0.15,
+            pan_speed: KEY_PAN_ACCELERATION,
+            zoom_speed: KEY_ZOOM_ACCELERATION,
+        }
+    }
+}
+
+/// Pan acceleration while a pan binding is held, see `CameraDynamics` for
+/// the units
+pub const KEY_PAN_ACCELERATION: f32 = 1.44;
+/// Zoom acceleration while a zoom binding is held
+pub const KEY_ZOOM_ACCELERATION: f32 = 3.6;
+/// Roll rate while a roll binding is held, in radians per second
+pub const KEY_ROLL_SPEED: f64 = 0.6;
+
+/// Where a snap key points the camera
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapTarget {
+    North,
+    East,
+    South,
+    West,
+    Zenith,
+    /// Celestial pole above the observer's horizon
+    Pole,
+}
+
+impl SnapTarget {
+    /// Horizon azimuth and altitude of the target in radians, `None` for
+    /// the celestial pole
+    pub fn horizon(&self) -> Option<(f64, f64)> {
+        use std::f64::consts::{FRAC_PI_2, PI};
+        // look a little above the horizon so that the ground stays in view
+        let altitude = 15f64.to_radians();
+        match self {
+            SnapTarget::North => Some((0., altitude)),
+            SnapTarget::East => Some((FRAC_PI_2, altitude)),
+            SnapTarget::South => Some((PI, altitude)),
+            SnapTarget::West => Some((3. * FRAC_PI_2, altitude)),
+            SnapTarget::Zenith => Some((0., FRAC_PI_2)),
+            SnapTarget::Pole => None,
+        }
+    }
+}
+
+/// Camera acceleration from held bindings: x pitches, y yaws and z zooms
+pub fn key_acceleration(bindings: &Bindings, input: &ActionInput) -> Vec3 {
+    let held = |action| if bindings.pressed(action, input) {1.} else {0.};
+    Vec3::new(
+        (held(Action::PanUp) - held(Action::PanDown)) * KEY_PAN_ACCELERATION,
+        // positive yaw turns the view left
+        (held(Action::PanLeft) - held(Action::PanRight)) * KEY_PAN_ACCELERATION,
+        (held(Action::ZoomIn) - held(Action::ZoomOut)) * KEY_ZOOM_ACCELERATION,
+    )
+}
+
+/// Snap target of a binding pressed this frame
+pub fn key_snap(bindings: &Bindings, input: &ActionInput) -> Option<SnapTarget> {
+    let targets = [
+        (Action::SnapNorth, SnapTarget::North),
+        (Action::SnapEast, SnapTarget::East),
+        (Action::SnapSouth, SnapTarget::South),
+        (Action::SnapWest, SnapTarget::West),
+        (Action::SnapZenith, SnapTarget::Zenith),
+        (Action::SnapPole, SnapTarget::Pole),
+    ];
+    targets.iter()
+        .find(|(action, _)| bindings.just_pressed(*action, input))
+        .map(|(_, target)| *target)
+}
+
+/// Camera acceleration from stick deflections in [-1, 1], pushed up and
+/// right being positive
+pub fn stick_acceleration(settings: &StickSettings, pan: Vec2, zoom: f32) -> Vec3 {
+    let live = |value: f32| if value.abs() < settings.dead_zone {0.} else {value};
+    Vec3::new(
+        live(pan.y) * settings.pan_speed,
+        -live(pan.x) * settings.pan_speed,
+        live(zoom) * settings.zoom_speed,
+    )
+}
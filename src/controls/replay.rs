@@ -0,0 +1,442 @@
+use bevy::app::Events;
+use bevy::input::{ElementState, InputSystem};
+use bevy::input::gamepad::GamepadEventRaw;
+use bevy::input::keyboard::KeyboardInput;
+use bevy::input::mouse::{MouseButtonInput, MouseMotion, MouseScrollUnit, MouseWheel};
+use bevy::input::touch::TouchInput;
+use bevy::prelude::*;
+use bevy::window::{CursorLeft, CursorMoved, ReceivedCharacter};
+use std::error;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use crate::controls::bindings::Binding;
+use crate::world::clock::SimulationClock;
+
+/// Input recording and deterministic replay, to reproduce rendering bugs.
+///
+/// Recording writes the mouse, keyboard, text and gamepad events of every
+/// frame, with the simulated time and the frame time, to a text file.
+/// Replaying feeds them back frame by frame, live input from every device
+/// being dropped, and advances the simulation by the recorded frame times
+/// so that a session renders the same on every run.
+pub struct ReplayPlugin {
+    pub mode: ReplayMode,
+}
+
+/// What happens to the input, chosen on the command line
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplayMode {
+    Live,
+    /// Write the input to a file
+    Record(String),
+    /// Read the input from a file, stepping by the recorded frame times
+    /// unless a fixed timestep in seconds is given
+    Replay{path: String, timestep: Option<f32>},
+}
+
+/// Frame time of recordings made before frame times were recorded
+pub const REPLAY_TIMESTEP: f32 = 1. / 60.;
+
+/// Seconds of simulation in the current frame: the real frame time, or the
+/// recorded one during a replay. Systems that animate or advance the clock
+/// read it instead of `Time`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FrameTime {
+    pub delta: f32,
+}
+
+impl FrameTime {
+    pub fn delta_seconds(&self) -> f32 {
+        self.delta
+    }
+
+    pub fn delta_seconds_f64(&self) -> f64 {
+        self.delta as f64
+    }
+}
+
+/// One recorded input event
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputEvent {
+    /// Key or mouse button pressed (`true`) or released
+    Button(Binding, bool),
+    /// Raw mouse motion in pixels
+    Motion(Vec2),
+    /// Scroll, in lines or in pixels
+    Wheel{lines: bool, x: f32, y: f32},
+    /// Cursor position in the window, in pixels from the bottom left corner
+    Cursor(Vec2),
+    /// Cursor out of the window
+    CursorLeft,
+    /// Character typed, for text fields
+    Character(char),
+    /// Gamepad connection, button or stick, as read from the device
+    Gamepad(GamepadEventRaw),
+}
+
+/// Input of one frame, the simulated time it started at and how many
+/// seconds it lasted
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputFrame {
+    pub jd: f64,
+    pub delta: f32,
+    pub events: Vec<InputEvent>,
+}
+
+/// Recorded frames, in order
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct InputRecording(pub Vec<InputFrame>);
+
+impl InputFrame {
+    /// A `frame <jd> <delta>` line followed by a line per event
+    pub fn to_text(&self) -> String {
+        let mut text = format!("frame {} {}\n", self.jd, self.delta);
+        for event in &self.events {
+            let line = match event {
+                InputEvent::Button(binding, true) => format!("press {}", binding.name()),
+                InputEvent::Button(binding, false) => format!("release {}", binding.name()),
+                InputEvent::Motion(delta) => format!("motion {} {}", delta.x, delta.y),
+                InputEvent::Wheel{lines, x, y} => format!("wheel {} {} {}", if *lines {"line"} else {"pixel"}, x, y),
+                InputEvent::Cursor(position) => format!("cursor {} {}", position.x, position.y),
+                InputEvent::CursorLeft => String::from("leave"),
+                // as a code point, spaces and new lines would not survive
+                InputEvent::Character(c) => format!("char {}", *c as u32),
+                InputEvent::Gamepad(GamepadEventRaw(gamepad, event)) => {
+                    let action = match event {
+                        GamepadEventType::Connected => String::from("connected"),
+                        GamepadEventType::Disconnected => String::from("disconnected"),
+                        GamepadEventType::ButtonChanged(button, value) => {
+                            format!("button {} {}", Binding::Gamepad(*button).name(), value)
+                        }
+                        GamepadEventType::AxisChanged(axis, value) => {
+                            format!("axis {} {}", axis_name(axis), value)
+                        }
+                    };
+                    format!("gamepad {} {}", gamepad.0, action)
+                }
+            };
+            text.push_str(&line);
+            text.push('\n');
+        }
+        text
+    }
+}
+
+impl InputRecording {
+    pub fn to_text(&self) -> String {
+        let mut text = String::from(RECORDING_HEADER);
+        for frame in &self.0 {
+            text.push_str(&frame.to_text());
+        }
+        text
+    }
+
+    pub fn from_text(text: &str) -> Result<InputRecording, Box<dyn error::Error>> {
+        let mut frames: Vec<InputFrame> = vec![];
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let at_line = |e: Box<dyn error::Error>| format!("line {}: {}", number + 1, e);
+            let words: Vec<&str> = line.split_whitespace().collect();
+            if let ["frame", jd, delta @ ..] = words.as_slice() {
+                let jd = jd.parse::<f64>().map_err(|e| at_line(e.into()))?;
+                // older recordings were made at the fixed timestep
+                let delta = match delta {
+                    [] => REPLAY_TIMESTEP,
+                    [delta] => delta.parse::<f32>().map_err(|e| at_line(e.into()))?,
+                    _ => return Err(at_line(format!("cannot read '{}'", line).into()).into()),
+                };
+                frames.push(InputFrame{jd, delta, events: vec![]});
+                continue;
+            }
+            let frame = frames.last_mut().ok_or(format!("line {}: expected a frame line", number + 1))?;
+            frame.events.push(parse_event(&words).map_err(at_line)?);
+        }
+        Ok(InputRecording(frames))
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<InputRecording, Box<dyn error::Error>> {
+        InputRecording::from_text(&fs::read_to_string(path)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn error::Error>> {
+        if let Some(parent) = path.as_ref().parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, self.to_text())?;
+        Ok(())
+    }
+}
+
+const RECORDING_HEADER: &str = "# Stargazer input recording\n";
+
+fn parse_event(words: &[&str]) -> Result<InputEvent, Box<dyn error::Error>> {
+    let number = |word: &str| word.parse::<f32>();
+    let event = match words {
+        ["press", binding] => InputEvent::Button(Binding::parse(binding)?, true),
+        ["release", binding] => InputEvent::Button(Binding::parse(binding)?, false),
+        ["motion", x, y] => InputEvent::Motion(Vec2::new(number(x)?, number(y)?)),
+        ["wheel", unit, x, y] => InputEvent::Wheel{
+            lines: match *unit {
+                "line" => true,
+                "pixel" => false,
+                _ => return Err(format!("unknown scroll unit {}", unit).into()),
+            },
+            x: number(x)?,
+            y: number(y)?,
+        },
+        ["cursor", x, y] => InputEvent::Cursor(Vec2::new(number(x)?, number(y)?)),
+        ["leave"] => InputEvent::CursorLeft,
+        ["char", code] => InputEvent::Character(
+            std::char::from_u32(code.parse()?).ok_or(format!("invalid character {}", code))?
+        ),
+        ["gamepad", id, action @ ..] => {
+            let event = match action {
+                ["connected"] => GamepadEventType::Connected,
+                ["disconnected"] => GamepadEventType::Disconnected,
+                ["button", button, value] => match Binding::parse(button)? {
+                    Binding::Gamepad(button) => GamepadEventType::ButtonChanged(button, number(value)?),
+                    _ => return Err(format!("{} is not a gamepad button", button).into()),
+                },
+                ["axis", axis, value] => GamepadEventType::AxisChanged(
+                    lookup_axis(axis).ok_or(format!("unknown gamepad axis {}", axis))?,
+                    number(value)?,
+                ),
+                _ => return Err(format!("cannot read '{}'", words.join(" ")).into()),
+            };
+            InputEvent::Gamepad(GamepadEventRaw(Gamepad(id.parse()?), event))
+        }
+        _ => return Err(format!("cannot read '{}'", words.join(" ")).into()),
+    };
+    Ok(event)
+}
+
+const AXIS_NAMES: &[(GamepadAxisType, &str)] = &[
+    (GamepadAxisType::LeftStickX, "LeftStickX"),
+    (GamepadAxisType::LeftStickY, "LeftStickY"),
+    (GamepadAxisType::LeftZ, "LeftZ"),
+    (GamepadAxisType::RightStickX, "RightStickX"),
+    (GamepadAxisType::RightStickY, "RightStickY"),
+    (GamepadAxisType::RightZ, "RightZ"),
+    (GamepadAxisType::DPadX, "DPadX"),
+    (GamepadAxisType::DPadY, "DPadY"),
+];
+
+fn axis_name(axis: &GamepadAxisType) -> &'static str {
+    AXIS_NAMES.iter().find(|(a, _)| a == axis).map(|(_, name)| *name).unwrap_or("?")
+}
+
+fn lookup_axis(name: &str) -> Option<GamepadAxisType> {
+    AXIS_NAMES.iter().find(|(_, n)| *n == name).map(|(a, _)| *a)
+}
+
+/// Recording written as it goes, so that a crash keeps what led to it
+struct Recorder {
+    writer: BufWriter<File>,
+}
+
+impl Recorder {
+    fn create<P: AsRef<Path>>(path: P) -> Result<Recorder, Box<dyn error::Error>> {
+        if let Some(parent) = path.as_ref().parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(RECORDING_HEADER.as_bytes())?;
+        Ok(Recorder { writer })
+    }
+}
+
+/// Frames left to replay
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replay {
+    pub recording: InputRecording,
+    /// Index of the next frame
+    pub next: usize,
+    /// Fixed timestep replacing the recorded frame times
+    pub timestep: Option<f32>,
+    /// Last recorded cursor position, `None` out of the window
+    pub cursor: Option<Vec2>,
+}
+
+impl Replay {
+    pub fn new(recording: InputRecording, timestep: Option<f32>) -> Replay {
+        Replay { recording, next: 0, timestep, cursor: None }
+    }
+
+    /// Input of the next frame, `None` once the recording is over
+    pub fn next_frame(&mut self) -> Option<&InputFrame> {
+        let index = self.next;
+        if index >= self.recording.0.len() {
+            return None;
+        }
+        self.next += 1;
+        self.recording.0.get(index)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.recording.0.len()
+    }
+}
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut AppBuilder){
+        app.init_resource::<FrameTime>();
+        let replaying = match &self.mode {
+            ReplayMode::Record(path) => {
+                match Recorder::create(path) {
+                    Ok(recorder) => {
+                        app.insert_resource(recorder)
+                        .add_system_to_stage(CoreStage::PreUpdate, record_frame.system().before(InputSystem));
+                        info!("recording the input to {}", path);
+                    }
+                    Err(e) => error!("cannot record the input to {}: {}", path, e),
+                }
+                false
+            }
+            ReplayMode::Replay{path, timestep} => match InputRecording::load(path) {
+                Ok(recording) => {
+                    app.insert_resource(Replay::new(recording, *timestep))
+                    .add_system_to_stage(CoreStage::PreUpdate, replay_frame.system().before(InputSystem));
+                    info!("replaying the input of {}", path);
+                    true
+                }
+                Err(e) => {
+                    error!("cannot replay {}: {}", path, e);
+                    false
+                }
+            },
+            ReplayMode::Live => false,
+        };
+        if !replaying {
+            app.add_system_to_stage(CoreStage::PreUpdate, live_frame_time.system());
+        }
+    }
+}
+
+fn live_frame_time(time: Res<Time>, mut frame_time: ResMut<FrameTime>) {
+    frame_time.delta = time.delta_seconds();
+}
+
+/// Append the input received since the last frame to the recording
+fn record_frame(
+    time: Res<Time>,
+    clock: Res<SimulationClock>,
+    mut recorder: ResMut<Recorder>,
+    mut keyboard_evr: EventReader<KeyboardInput>,
+    mut mousebtn_evr: EventReader<MouseButtonInput>,
+    mut motion_evr: EventReader<MouseMotion>,
+    mut scroll_evr: EventReader<MouseWheel>,
+    mut cursor_evr: EventReader<CursorMoved>,
+    mut cursor_left_evr: EventReader<CursorLeft>,
+    mut character_evr: EventReader<ReceivedCharacter>,
+    mut gamepad_evr: EventReader<GamepadEventRaw>,
+) {
+    let pressed = |state: &ElementState| *state == ElementState::Pressed;
+    let mut events = vec![];
+    // keys known by scan code only never reach `Input<KeyCode>`
+    events.extend(keyboard_evr.iter()
+        .filter_map(|ev| ev.key_code.map(|code| InputEvent::Button(Binding::Key(code), pressed(&ev.state)))));
+    events.extend(mousebtn_evr.iter()
+        .map(|ev| InputEvent::Button(Binding::Mouse(ev.button), pressed(&ev.state))));
+    events.extend(motion_evr.iter().map(|ev| InputEvent::Motion(ev.delta)));
+    events.extend(scroll_evr.iter().map(|ev| InputEvent::Wheel{
+        lines: matches!(ev.unit, MouseScrollUnit::Line),
+        x: ev.x,
+        y: ev.y,
+    }));
+    events.extend(cursor_evr.iter().map(|ev| InputEvent::Cursor(ev.position)));
+    events.extend(cursor_left_evr.iter().map(|_| InputEvent::CursorLeft));
+    events.extend(character_evr.iter().map(|ev| InputEvent::Character(ev.char)));
+    events.extend(gamepad_evr.iter().map(|ev| InputEvent::Gamepad(ev.clone())));
+    let frame = InputFrame{jd: clock.jd, delta: time.delta_seconds(), events};
+    let written = recorder.writer.write_all(frame.to_text().as_bytes()).and_then(|_| recorder.writer.flush());
+    if let Err(e) = written {
+        error!("cannot write the input recording: {}", e);
+    }
+}
+
+/// Replace the live input with the next recorded frame, and step the
+/// simulation by the recorded frame time
+fn replay_frame(
+    mut replay: ResMut<Replay>,
+    time: Res<Time>,
+    mut frame_time: ResMut<FrameTime>,
+    mut clock: ResMut<SimulationClock>,
+    mut windows: ResMut<Windows>,
+    mut keyboard_events: ResMut<Events<KeyboardInput>>,
+    mut mousebtn_events: ResMut<Events<MouseButtonInput>>,
+    mut motion_events: ResMut<Events<MouseMotion>>,
+    mut scroll_events: ResMut<Events<MouseWheel>>,
+    mut cursor_events: ResMut<Events<CursorMoved>>,
+    mut cursor_left_events: ResMut<Events<CursorLeft>>,
+    mut character_events: ResMut<Events<ReceivedCharacter>>,
+    mut gamepad_events: ResMut<Events<GamepadEventRaw>>,
+    mut touch_events: ResMut<Events<TouchInput>>,
+) {
+    let frame = match replay.next_frame() {
+        Some(frame) => frame.clone(),
+        None => {
+            // the recording is over, hand over to the live input
+            frame_time.delta = time.delta_seconds();
+            return;
+        }
+    };
+    keyboard_events.clear();
+    mousebtn_events.clear();
+    motion_events.clear();
+    scroll_events.clear();
+    cursor_events.clear();
+    cursor_left_events.clear();
+    character_events.clear();
+    gamepad_events.clear();
+    // never recorded, nothing reads touches
+    touch_events.clear();
+    frame_time.delta = replay.timestep.unwrap_or(frame.delta);
+    clock.jd = frame.jd;
+    let window_id = windows.get_primary().map(|window| window.id());
+    let state = |pressed| if pressed {ElementState::Pressed} else {ElementState::Released};
+    for event in frame.events {
+        match event {
+            InputEvent::Button(Binding::Key(key_code), pressed) => {
+                keyboard_events.send(KeyboardInput{scan_code: 0, key_code: Some(key_code), state: state(pressed)});
+            }
+            InputEvent::Button(Binding::Mouse(button), pressed) => {
+                mousebtn_events.send(MouseButtonInput{button, state: state(pressed)});
+            }
+            InputEvent::Button(Binding::Gamepad(_), _) => {}
+            InputEvent::Motion(delta) => motion_events.send(MouseMotion{delta}),
+            InputEvent::Wheel{lines, x, y} => {
+                let unit = if lines {MouseScrollUnit::Line} else {MouseScrollUnit::Pixel};
+                scroll_events.send(MouseWheel{unit, x, y});
+            }
+            InputEvent::Cursor(position) => {
+                replay.cursor = Some(position);
+                if let Some(id) = window_id {
+                    cursor_events.send(CursorMoved{id, position});
+                }
+            }
+            InputEvent::CursorLeft => {
+                replay.cursor = None;
+                if let Some(id) = window_id {
+                    cursor_left_events.send(CursorLeft{id});
+                }
+            }
+            InputEvent::Character(c) => {
+                if let Some(id) = window_id {
+                    character_events.send(ReceivedCharacter{id, char: c});
+                }
+            }
+            InputEvent::Gamepad(event) => gamepad_events.send(event),
+        }
+    }
+    // the live cursor moved the window cursor before this frame
+    if let Some(window) = windows.get_primary_mut() {
+        window.update_cursor_position_from_backend(replay.cursor);
+    }
+    if replay.is_finished() {
+        info!("end of the input replay");
+    }
+}
@@ -1,4 +1,7 @@
 pub mod world;
 pub mod units;
 pub mod states;
-pub mod consts;
\ No newline at end of file
+pub mod consts;
+pub mod catalog;
+pub mod render;
+pub mod controls;
\ No newline at end of file
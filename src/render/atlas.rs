@@ -0,0 +1,347 @@
+//! Printable star charts built straight from the catalogues, without a
+//! window: stars sized by magnitude, constellation figures, an equatorial
+//! grid labelled in hours and degrees, deep sky objects and a legend.
+use bevy::math::Vec2;
+use bevy::render::color::Color;
+use cgmath::{InnerSpace, Vector3, Vector4};
+use std::error;
+use std::str::FromStr;
+use crate::catalog::reader::Catalogue;
+use crate::render::camera::SkyCamera;
+use crate::render::chart::{Chart, SymbolShape};
+use crate::render::paths::{border_crossings, Border, PathStyle};
+use crate::render::projection::{ProjectionKind, ViewProjection, CLIP_HALF};
+use crate::units::{dms::DMS, hms::HMS};
+use crate::world::deep_sky::{DeepSkyKind, DeepSkyObject};
+use crate::world::frames::{to_cartesian, to_world};
+use crate::world::photometry::BrightnessModel;
+
+/// Parts of a chart that can be left out
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChartLayers {
+    pub stars: bool,
+    pub constellations: bool,
+    pub grid: bool,
+    /// Grid and deep sky object labels
+    pub labels: bool,
+    pub deep_sky: bool,
+    pub legend: bool,
+}
+
+impl Default for ChartLayers {
+    fn default() -> Self {
+        ChartLayers {
+            stars: true,
+            constellations: true,
+            grid: true,
+            labels: true,
+            deep_sky: true,
+            legend: true,
+        }
+    }
+}
+
+impl ChartLayers {
+    pub const NAMES: [&'static str; 6] = ["stars", "constellations", "grid", "labels", "deep-sky", "legend"];
+
+    fn toggle(&mut self, name: &str) -> Option<&mut bool> {
+        match name {
+            "stars" => Some(&mut self.stars),
+            "constellations" => Some(&mut self.constellations),
+            "grid" => Some(&mut self.grid),
+            "labels" => Some(&mut self.labels),
+            "deep-sky" => Some(&mut self.deep_sky),
+            "legend" => Some(&mut self.legend),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for ChartLayers {
+    type Err = Box<dyn error::Error>;
+    /// Comma separated names of the layers to draw, e.g. `stars,grid`
+    fn from_str(s: &str) -> Result<Self, Box<dyn error::Error>> {
+        let mut layers = ChartLayers {
+            stars: false,
+            constellations: false,
+            grid: false,
+            labels: false,
+            deep_sky: false,
+            legend: false,
+        };
+        for name in s.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+            let toggle = layers.toggle(name)
+                .ok_or_else(|| format!("unknown layer {}, expected some of {}", name, ChartLayers::NAMES.join(",")))?;
+            *toggle = true;
+        }
+        Ok(layers)
+    }
+}
+
+/// What a chart shows
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChartRequest {
+    /// J2000 right ascension and declination of the centre, in radians
+    pub ra: f64,
+    pub dec: f64,
+    /// Sky shown across the chart height, in radians
+    pub field: f32,
+    pub projection: ProjectionKind,
+    /// Faintest star drawn
+    pub max_mag: f32,
+    pub layers: ChartLayers,
+    /// Chart size in pixels for SVG, in points for PDF
+    pub size: Vec2,
+}
+
+impl Default for ChartRequest {
+    fn default() -> Self {
+        ChartRequest {
+            ra: 0.,
+            dec: 0.,
+            field: 20_f32.to_radians(),
+            projection: ProjectionKind::Stereographic,
+            max_mag: 6.5,
+            layers: ChartLayers::default(),
+            size: Vec2::new(800., 800.),
+        }
+    }
+}
+
+/// Chart height at which lines and texts have their window sizes
+const REFERENCE_HEIGHT: f32 = 800.;
+
+const PAPER: Color = Color::WHITE;
+const INK: Color = Color::BLACK;
+const GRID_COLOR: Color = Color::rgba(0.3, 0.3, 0.3, 0.5);
+const CONSTELLATION_COLOR: Color = Color::rgb(0.2, 0.4, 0.8);
+const LABEL_SIZE: f32 = 11.;
+
+/// Symbol and colour of a kind of deep sky object
+pub fn deep_sky_symbol(kind: DeepSkyKind) -> (SymbolShape, Color) {
+    match kind {
+        DeepSkyKind::Galaxy => (SymbolShape::Ellipse, Color::rgb(0.8, 0., 0.)),
+        DeepSkyKind::OpenCluster => (SymbolShape::DashedCircle, Color::rgb(0.75, 0.55, 0.)),
+        DeepSkyKind::GlobularCluster => (SymbolShape::CrossedCircle, Color::rgb(0.6, 0.4, 0.)),
+        DeepSkyKind::Nebula => (SymbolShape::Square, Color::rgb(0., 0.55, 0.)),
+        DeepSkyKind::PlanetaryNebula => (SymbolShape::TickedCircle, Color::rgb(0., 0.5, 0.7)),
+    }
+}
+
+/// Grid spacing in degrees, in right ascension and declination, giving
+/// about six lines across a `field` (radians) centred at `dec`
+pub fn grid_steps(field: f32, dec: f64) -> (f64, f64) {
+    // right ascension steps in minutes of time, declination steps in arcminutes
+    let ra_steps = [1., 2., 5., 10., 15., 20., 30., 60., 120., 180.];
+    let dec_steps = [1., 2., 5., 10., 15., 30., 60., 120., 300., 600., 900., 1200., 1800.];
+    let target = (field as f64).to_degrees() / 6.;
+    let dec_step = dec_steps.iter().map(|m| m / 60.).find(|step| *step >= target).unwrap_or(30.);
+    // meridians converge towards the poles
+    let ra_target = target / dec.abs().min(80_f64.to_radians()).cos();
+    let ra_step = ra_steps.iter().map(|m| m / 4.).find(|step| *step >= ra_target).unwrap_or(45.);
+    (ra_step, dec_step)
+}
+
+/// Scene direction of a right ascension and declination in radians
+fn scene_direction(ra: f64, dec: f64) -> Vector3<f64> {
+    let v = to_cartesian(ra, dec);
+    Vector3::new(v.x, v.z, v.y)
+}
+
+/// Great circle arc between two positions, split into steps of at most
+/// `step` radians, the end point excluded
+fn arc(from: Vector3<f64>, to: Vector3<f64>, step: f64) -> Vec<Vector4<f32>> {
+    let angle = from.angle(to).0;
+    let segments = (angle / step).ceil().clamp(1., 256.) as usize;
+    (0..segments)
+        .map(|i| {
+            let t = i as f64 / segments as f64;
+            let (a, b) = (((1. - t) * angle).sin(), (t * angle).sin());
+            let v = if angle > 1e-9 { (from * a + to * b) / angle.sin() } else { from };
+            to_world(v.normalize())
+        })
+        .collect()
+}
+
+/// Lay a chart out from the star catalogue, the resolved constellation
+/// figures (right ascension and declination of their stars) and deep sky
+/// objects
+pub fn build_chart(
+    request: &ChartRequest,
+    catalogue: &Catalogue,
+    figures: &[Vec<(f64, f64)>],
+    deep_sky: &[DeepSkyObject],
+) -> Chart {
+    let mut camera = SkyCamera::default();
+    camera.look_to_scene(scene_direction(request.ra, request.dec));
+    let fov = request.projection.fov_for_field(request.field);
+    let projection = ViewProjection::with_kind(request.projection, fov, request.size, camera.view_matrix());
+    let scale = request.size.y / REFERENCE_HEIGHT;
+    let mut chart = Chart::new(projection, scale, PAPER);
+    // sky within reach of the chart corners, and a sampling step keeping
+    // curves smooth
+    let reach = projection.screen_to_world(Vec2::ZERO)
+        .angle(projection.screen_to_world(request.size / 2.)).0 as f64 * 1.05;
+    let step = (request.field as f64 / 60.).min(1_f64.to_radians());
+
+    if request.layers.grid {
+        add_grid(&mut chart, request, reach, step);
+    }
+    let mut constellations_shown = false;
+    if request.layers.constellations {
+        let lines = chart.lines.len();
+        let style = PathStyle::solid(CONSTELLATION_COLOR, 1.2);
+        for figure in figures {
+            let stars: Vec<Vector3<f64>> = figure.iter().map(|(ra, dec)| to_cartesian(*ra, *dec)).collect();
+            let mut path: Vec<Vector4<f32>> = stars.windows(2).flat_map(|pair| arc(pair[0], pair[1], step)).collect();
+            if let Some(last) = stars.last() {
+                path.push(to_world(*last));
+            }
+            chart.add_path(&path, &style);
+        }
+        constellations_shown = chart.lines.len() > lines;
+    }
+    let mut kinds = vec![];
+    if request.layers.deep_sky {
+        for object in deep_sky {
+            let (shape, color) = deep_sky_symbol(object.kind);
+            let world = to_world(to_cartesian(object.ra, object.dec));
+            let radius = match chart.add_symbol(world, shape, (object.size / 2.) as f32, color) {
+                Some(radius) => radius,
+                None => continue,
+            };
+            if !kinds.contains(&object.kind) {
+                kinds.push(object.kind);
+            }
+            if request.layers.labels {
+                if let Some(center) = chart.projection.world_to_screen(world) {
+                    let position = center + Vec2::new(radius + 3. * scale, 0.);
+                    chart.add_label(position, &object.name, color, LABEL_SIZE, Vec2::new(0., 0.5));
+                }
+            }
+        }
+    }
+    if request.layers.stars {
+        let model = BrightnessModel {
+            limiting_mag: request.max_mag + 1.,
+            sky_limit: f32::INFINITY,
+            contrast: 0.6,
+            min_radius: 0.8,
+            max_radius: 7.,
+            reference_fov: fov,
+            zoom_gain: 0.,
+        };
+        for star in catalogue.iter().filter(|star| star.mag <= request.max_mag) {
+            let world = to_world(to_cartesian(star.ra as f64, star.dec as f64));
+            chart.add_star(world, star.mag, INK, &model);
+        }
+    }
+    if request.layers.legend {
+        chart.add_legend(&format!("Centre {} {}, field {:.1}°",
+            HMS::from_degrees(request.ra.to_degrees()).label(),
+            DMS::from_degrees(request.dec.to_degrees()).label(),
+            request.field.to_degrees()), None);
+        let projection_name = request.projection.name();
+        chart.add_legend(&format!("{}{} projection, stars to mag {:.1}",
+            projection_name[..1].to_uppercase(), &projection_name[1..], request.max_mag), None);
+        if request.layers.grid {
+            let (ra_step, dec_step) = grid_steps(request.field, request.dec);
+            chart.add_legend(&format!("Grid every {} of RA, {} of Dec",
+                step_label(ra_step * 4., 'h', 'm'), step_label(dec_step * 60., '°', '\'')), Some(with_alpha(GRID_COLOR, 1.)));
+        }
+        if constellations_shown {
+            chart.add_legend("Constellation lines", Some(CONSTELLATION_COLOR));
+        }
+        for kind in DeepSkyKind::ALL.iter().filter(|kind| kinds.contains(kind)) {
+            chart.add_legend(kind.name(), Some(deep_sky_symbol(*kind).1));
+        }
+    }
+    chart
+}
+
+/// Grid step of `minutes` in whole units of sixty when possible, e.g. `2h`
+/// or `30m`
+fn step_label(minutes: f64, unit: char, minute: char) -> String {
+    if minutes >= 60. && minutes % 60. == 0. {
+        format!("{}{}", minutes / 60., unit)
+    } else {
+        format!("{}{}", minutes, minute)
+    }
+}
+
+fn with_alpha(color: Color, alpha: f32) -> Color {
+    let mut color = color;
+    color.set_a(alpha);
+    color
+}
+
+/// Add the parts of the equatorial grid within `reach` radians of the
+/// centre, labelled where they leave the chart
+fn add_grid(chart: &mut Chart, request: &ChartRequest, reach: f64, step: f64) {
+    let (ra_step, dec_step) = grid_steps(request.field, request.dec);
+    let style = PathStyle::solid(GRID_COLOR, 0.8);
+    let label_color = with_alpha(GRID_COLOR, 1.);
+    let half_pi = std::f64::consts::FRAC_PI_2;
+    let dec_range = ((request.dec - reach).max(-half_pi), (request.dec + reach).min(half_pi));
+    // a pole in view brings in every meridian
+    let ra_half = if request.dec.abs() + reach >= half_pi {
+        std::f64::consts::PI
+    } else {
+        (reach / dec_range.0.abs().max(dec_range.1.abs()).cos()).min(std::f64::consts::PI)
+    };
+    let ra_range = (request.ra - ra_half, request.ra + ra_half);
+    let samples = |from: f64, to: f64| -> Vec<f64> {
+        let count = ((to - from) / step).ceil().max(1.) as usize;
+        (0..count + 1).map(|i| from + (to - from) * i as f64 / count as f64).collect()
+    };
+    let first = |from: f64, spacing: f64| (from.to_degrees() / spacing).ceil() as i64;
+    let last = |to: f64, spacing: f64| (to.to_degrees() / spacing).floor() as i64;
+
+    // meridians, labelled on the top and bottom sides
+    for index in first(ra_range.0, ra_step)..=last(ra_range.1, ra_step) {
+        let ra = (index as f64 * ra_step).to_radians();
+        if ra_half >= std::f64::consts::PI && index as f64 * ra_step >= request.ra.to_degrees() + 180. {
+            // full turn, each meridian once
+            continue;
+        }
+        let path: Vec<Vector4<f32>> = samples(dec_range.0, dec_range.1).iter()
+            .map(|dec| to_world(to_cartesian(ra, *dec)))
+            .collect();
+        let label = HMS::from_degrees(ra.to_degrees()).label();
+        add_grid_line(chart, &path, &style, &label, label_color, request.layers.labels, [Border::Bottom, Border::Top]);
+    }
+    // parallels, labelled on the left and right sides
+    for index in first(dec_range.0, dec_step)..=last(dec_range.1, dec_step) {
+        let dec = (index as f64 * dec_step).to_radians();
+        if dec.abs() >= half_pi - 1e-9 {
+            continue;
+        }
+        let path: Vec<Vector4<f32>> = samples(ra_range.0, ra_range.1).iter()
+            .map(|ra| to_world(to_cartesian(*ra, dec)))
+            .collect();
+        let label = DMS::from_degrees(dec.to_degrees()).label();
+        add_grid_line(chart, &path, &style, &label, label_color, request.layers.labels, [Border::Left, Border::Right]);
+    }
+}
+
+fn add_grid_line(
+    chart: &mut Chart,
+    path: &[Vector4<f32>],
+    style: &PathStyle,
+    label: &str,
+    color: Color,
+    labelled: bool,
+    sides: [Border; 2],
+) {
+    chart.add_path(path, style);
+    if !labelled {
+        return;
+    }
+    let clip: Vec<Vector4<f32>> = path.iter().map(|v| chart.projection.world_to_clip(*v)).collect();
+    for crossing in border_crossings(&clip, Vec2::splat(CLIP_HALF)) {
+        if sides.contains(&crossing.border) {
+            let screen = chart.projection.ndc_to_screen(crossing.position);
+            chart.add_border_label(crossing.border, screen, label, color, LABEL_SIZE);
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use cgmath::{InnerSpace, Matrix3, Quaternion, Rad, Rotation, Rotation3, Vector3};
+
+/// How yaw and pitch behave
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CameraMode {
+    /// Yaw turns around the pole of the reference frame and roll is locked,
+    /// so that its equator or the horizon stays level on screen
+    KeepLevel,
+    /// Yaw, pitch and roll turn around the camera's own axes
+    Free,
+}
+
+/// Reference frame the camera orientation is expressed in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CameraFrame {
+    /// Fixed on the stars
+    Equatorial,
+    /// Fixed on the observer's horizon, the sky turns as time passes
+    Horizon,
+}
+
+/// Sky camera orientation, stored as a quaternion to avoid gimbal lock.
+///
+/// Scene axes follow `frames::to_world`: y points to the frame's pole, and
+/// the camera looks down its local -z axis with +y up, like cgmath's
+/// `perspective`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SkyCamera {
+    /// Rotation from camera axes to the reference frame axes
+    pub orientation: Quaternion<f64>,
+    /// Rotation from the reference frame axes to the scene axes
+    pub frame_rotation: Quaternion<f64>,
+    pub mode: CameraMode,
+    pub frame: CameraFrame,
+}
+
+/// Closest the view direction gets to the pole when keeping level
+const POLE_MARGIN: f64 = 1e-4;
+
+impl Default for SkyCamera {
+    fn default() -> Self {
+        SkyCamera {
+            orientation: Quaternion::new(1., 0., 0., 0.),
+            frame_rotation: Quaternion::new(1., 0., 0., 0.),
+            mode: CameraMode::KeepLevel,
+            frame: CameraFrame::Equatorial,
+        }
+    }
+}
+
+impl SkyCamera {
+    /// Rotation from camera axes to scene axes
+    pub fn rotation(&self) -> Quaternion<f64> {
+        self.frame_rotation * self.orientation
+    }
+
+    /// Scene to camera rotation, the view part of the view-projection
+    pub fn view_matrix(&self) -> Matrix3<f64> {
+        Matrix3::from(self.rotation().invert())
+    }
+
+    /// Unit view direction in scene axes
+    pub fn forward(&self) -> Vector3<f64> {
+        self.rotation().rotate_vector(-Vector3::unit_z())
+    }
+
+    /// Unit up direction of the screen in scene axes
+    pub fn up(&self) -> Vector3<f64> {
+        self.rotation().rotate_vector(Vector3::unit_y())
+    }
+
+    /// Turn left or right by `angle` radians
+    pub fn yaw(&mut self, angle: f64) {
+        let turn = Quaternion::from_angle_y(Rad(angle));
+        self.orientation = match self.mode {
+            CameraMode::KeepLevel => turn * self.orientation,
+            CameraMode::Free => self.orientation * turn,
+        };
+    }
+
+    /// Tilt up or down by `angle` radians, stopping short of the pole when
+    /// keeping level
+    pub fn pitch(&mut self, angle: f64) {
+        let turned = self.orientation * Quaternion::from_angle_x(Rad(angle));
+        if self.mode == CameraMode::KeepLevel {
+            let forward = turned.rotate_vector(-Vector3::unit_z());
+            let up = turned.rotate_vector(Vector3::unit_y());
+            // going over the pole would turn the view upside down
+            if up.y < 0. || forward.y.abs() > 1. - POLE_MARGIN {
+                return;
+            }
+        }
+        self.orientation = turned;
+    }
+
+    /// Rotate the view around its direction by `angle` radians, only in
+    /// free mode
+    pub fn roll(&mut self, angle: f64) {
+        if self.mode == CameraMode::Free {
+            self.orientation = self.orientation * Quaternion::from_angle_z(Rad(angle));
+        }
+    }
+
+    /// Look along `forward` (frame axes) with the frame's pole up
+    pub fn look_to(&mut self, forward: Vector3<f64>) {
+        let forward = forward.normalize();
+        let pole = Vector3::unit_y();
+        let right = if forward.cross(pole).magnitude2() < 1e-12 {
+            // looking at the pole, keep the current right direction
+            self.orientation.rotate_vector(Vector3::unit_x())
+        } else {
+            forward.cross(pole).normalize()
+        };
+        let up = right.cross(forward).normalize();
+        self.orientation = Quaternion::from(Matrix3::from_cols(right, up, -forward)).normalize();
+    }
+
+    /// Look along `direction` given in scene axes
+    pub fn look_to_scene(&mut self, direction: Vector3<f64>) {
+        let forward = self.frame_rotation.invert().rotate_vector(direction);
+        self.look_to(forward);
+    }
+
+    /// Remove any roll, keeping the view direction
+    pub fn level(&mut self) {
+        let forward = self.orientation.rotate_vector(-Vector3::unit_z());
+        self.look_to(forward);
+    }
+
+    pub fn set_mode(&mut self, mode: CameraMode) {
+        self.mode = mode;
+        if mode == CameraMode::KeepLevel {
+            self.level();
+        }
+    }
+
+    /// Change the reference frame without moving the view
+    pub fn set_frame(&mut self, frame: CameraFrame, frame_rotation: Quaternion<f64>) {
+        let rotation = self.rotation();
+        self.frame = frame;
+        self.frame_rotation = frame_rotation;
+        self.orientation = (frame_rotation.invert() * rotation).normalize();
+        if self.mode == CameraMode::KeepLevel {
+            self.level();
+        }
+    }
+}
+
+/// Smooth move of the camera orientation and field of view, eased in and out
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraTransition {
+    pub from: Quaternion<f64>,
+    pub to: Quaternion<f64>,
+    pub from_fov: f32,
+    pub to_fov: f32,
+    /// Seconds elapsed and total
+    pub elapsed: f32,
+    pub duration: f32,
+}
+
+impl CameraTransition {
+    pub fn new(from: Quaternion<f64>, to: Quaternion<f64>, from_fov: f32, to_fov: f32, duration: f32) -> CameraTransition {
+        CameraTransition { from, to, from_fov, to_fov, elapsed: 0., duration }
+    }
+
+    /// Orientation and field of view after `seconds` more
+    pub fn advance(&mut self, seconds: f32) -> (Quaternion<f64>, f32) {
+        self.elapsed = (self.elapsed + seconds).min(self.duration);
+        let t = if self.duration > 0. { self.elapsed / self.duration } else { 1. };
+        // smoothstep easing
+        let s = t * t * (3. - 2. * t);
+        let orientation = self.from.slerp(self.to, s as f64).normalize();
+        // zooming feels even in log scale
+        let fov = self.from_fov * (self.to_fov / self.from_fov).powf(s);
+        (orientation, fov)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+}
@@ -0,0 +1,484 @@
+use ab_glyph::{point, Font, PxScale, ScaleFont};
+use bevy::math::Vec2;
+use bevy::render::color::Color;
+use cgmath::Vector4;
+use image::{Rgba, RgbaImage};
+use std::error;
+use std::fs;
+use std::path::Path;
+use crate::render::paths::{clip_segment, Border, PathStyle};
+use crate::render::projection::ViewProjection;
+use crate::world::photometry::BrightnessModel;
+
+/// Sky view laid out in pixels, away from the window and the GPU, to be
+/// saved as an image of any size.
+///
+/// A chart is filled through its own `ViewProjection`, so that positions
+/// are in pixels from the bottom left corner like screen positions. Line
+/// widths, star radii and text sizes are given at window resolution and
+/// multiplied by `scale`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chart {
+    pub projection: ViewProjection,
+    pub scale: f32,
+    pub background: Color,
+    pub stars: Vec<ChartStar>,
+    /// Visible runs of the paths
+    pub lines: Vec<ChartLine>,
+    pub labels: Vec<ChartLabel>,
+    /// Lines of the legend box in the top left corner, with an optional
+    /// colour swatch
+    pub legend: Vec<(String, Option<Color>)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChartStar {
+    pub position: Vec2,
+    pub radius: f32,
+    /// Colour, faded through its alpha
+    pub color: Color,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChartLine {
+    pub points: Vec<Vec2>,
+    /// Style scaled to the chart
+    pub style: PathStyle,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChartLabel {
+    pub position: Vec2,
+    pub text: String,
+    pub color: Color,
+    /// Text height in pixels, scaled to the chart
+    pub size: f32,
+    /// Point of the text box put at `position`, from (0, 0) at its bottom
+    /// left corner to (1, 1) at its top right corner
+    pub anchor: Vec2,
+}
+
+/// Outline drawn around a position, sized in pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolShape {
+    /// Flat ellipse, like a galaxy seen at an angle
+    Ellipse,
+    DashedCircle,
+    /// Circle crossed by two diameters
+    CrossedCircle,
+    Square,
+    /// Circle with four ticks pointing out
+    TickedCircle,
+}
+
+/// Text size of the legend at window resolution
+pub const LEGEND_TEXT_SIZE: f32 = 14.;
+
+/// Smallest symbol radius at window resolution
+pub const SYMBOL_MIN_RADIUS: f32 = 5.;
+
+/// Filled rectangle of the legend, between two chart positions
+pub type LegendBox = (Vec2, Vec2, Color);
+
+impl Chart {
+    pub fn new(projection: ViewProjection, scale: f32, background: Color) -> Chart {
+        Chart {
+            projection,
+            scale,
+            background,
+            stars: vec![],
+            lines: vec![],
+            labels: vec![],
+            legend: vec![],
+        }
+    }
+
+    /// Width and height in pixels
+    pub fn size(&self) -> (u32, u32) {
+        (self.projection.viewport.x.round() as u32, self.projection.viewport.y.round() as u32)
+    }
+
+    /// Add a star sized and faded by the brightness model, if it shows
+    pub fn add_star(&mut self, world: Vector4<f32>, mag: f32, color: Color, model: &BrightnessModel) {
+        let source = model.point_source(mag, self.projection.fov);
+        if source.alpha <= 0. {
+            return;
+        }
+        let radius = source.radius * self.scale;
+        if !self.projection.is_visible_within(world, radius) {
+            return;
+        }
+        if let Some(position) = self.projection.world_to_screen(world) {
+            let mut color = color;
+            color.set_a(color.a() * source.alpha);
+            self.stars.push(ChartStar{position, radius, color});
+        }
+    }
+
+    /// Add a path given in world positions, split where it leaves the view
+    pub fn add_path(&mut self, world: &[Vector4<f32>], style: &PathStyle) {
+        let style = PathStyle {
+            color: style.color,
+            width: style.width * self.scale,
+            dash: style.dash * self.scale,
+            gap: style.gap * self.scale,
+        };
+        let clip: Vec<Vector4<f32>> = world.iter().map(|v| self.projection.world_to_clip(*v)).collect();
+        let to_screen = |v: Vector4<f32>| self.projection.ndc_to_screen(Vec2::new(v[0] / v[3], v[1] / v[3]));
+        let mut runs = vec![];
+        let mut points: Vec<Vec2> = vec![];
+        for pair in clip.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            let (t0, t1) = match clip_segment(a, b) {
+                Some(range) => range,
+                None => {
+                    runs.push(std::mem::take(&mut points));
+                    continue;
+                }
+            };
+            if t0 > 0. || points.is_empty() {
+                runs.push(std::mem::take(&mut points));
+                points.push(to_screen(a + (b - a) * t0));
+            }
+            points.push(to_screen(a + (b - a) * t1));
+            if t1 < 1. {
+                runs.push(std::mem::take(&mut points));
+            }
+        }
+        runs.push(points);
+        for points in runs.into_iter().filter(|points| points.len() >= 2) {
+            self.lines.push(ChartLine{points, style});
+        }
+    }
+
+    /// Add a symbol of `angular_radius` radians on the sky, never smaller
+    /// than `SYMBOL_MIN_RADIUS`. Returns its radius in chart pixels when it
+    /// is in view.
+    pub fn add_symbol(&mut self, world: Vector4<f32>, shape: SymbolShape, angular_radius: f32, color: Color) -> Option<f32> {
+        let radius = (angular_radius * self.projection.pixels_per_radian()).max(SYMBOL_MIN_RADIUS * self.scale);
+        if !self.projection.is_visible_within(world, radius) {
+            return None;
+        }
+        let center = self.projection.world_to_screen(world)?;
+        let solid = PathStyle::solid(color, 1.2 * self.scale);
+        let ring = |rx: f32, ry: f32| -> Vec<Vec2> {
+            (0..49)
+                .map(|i| {
+                    let t = i as f32 * 2. * std::f32::consts::PI / 48.;
+                    center + Vec2::new(rx * t.cos(), ry * t.sin())
+                })
+                .collect()
+        };
+        let mut outlines = vec![];
+        match shape {
+            SymbolShape::Ellipse => outlines.push((ring(radius, radius / 2.), solid)),
+            SymbolShape::DashedCircle => {
+                let dashed = PathStyle::dashed(color, solid.width, 3. * self.scale, 2. * self.scale);
+                outlines.push((ring(radius, radius), dashed));
+            }
+            SymbolShape::CrossedCircle => {
+                outlines.push((ring(radius, radius), solid));
+                outlines.push((vec![center - Vec2::new(radius, 0.), center + Vec2::new(radius, 0.)], solid));
+                outlines.push((vec![center - Vec2::new(0., radius), center + Vec2::new(0., radius)], solid));
+            }
+            SymbolShape::Square => {
+                let corners = [(-1., -1.), (1., -1.), (1., 1.), (-1., 1.), (-1., -1.)];
+                let points: Vec<Vec2> = corners.iter().map(|(x, y)| center + Vec2::new(*x, *y) * radius).collect();
+                outlines.push((points, solid));
+            }
+            SymbolShape::TickedCircle => {
+                let inner = radius * 0.6;
+                outlines.push((ring(inner, inner), solid));
+                for direction in [Vec2::X, Vec2::Y, -Vec2::X, -Vec2::Y].iter() {
+                    outlines.push((vec![center + *direction * inner, center + *direction * radius], solid));
+                }
+            }
+        }
+        for (points, style) in outlines {
+            self.lines.push(ChartLine{points, style});
+        }
+        Some(radius)
+    }
+
+    /// Add a text, `size` being its height at window resolution
+    pub fn add_label(&mut self, position: Vec2, text: &str, color: Color, size: f32, anchor: Vec2) {
+        self.labels.push(ChartLabel {
+            position,
+            text: text.to_string(),
+            color,
+            size: size * self.scale,
+            anchor,
+        });
+    }
+
+    /// Add a grid label where a line crosses `border` at screen position
+    /// `crossing`, inside the chart and reading along the border
+    pub fn add_border_label(&mut self, border: Border, crossing: Vec2, text: &str, color: Color, size: f32) {
+        let (width, height) = (self.projection.viewport.x, self.projection.viewport.y);
+        let inset = 4. * self.scale;
+        let (position, anchor) = match border {
+            Border::Left => (Vec2::new(inset, crossing.y + 2. * self.scale), Vec2::new(0., 0.)),
+            Border::Right => (Vec2::new(width - inset, crossing.y + 2. * self.scale), Vec2::new(1., 0.)),
+            Border::Bottom => (Vec2::new(crossing.x + inset, inset), Vec2::new(0., 0.)),
+            Border::Top => (Vec2::new(crossing.x + inset, height - inset), Vec2::new(0., 1.)),
+        };
+        self.add_label(position, text, color, size, anchor);
+    }
+
+    /// Add the name of a path where it crosses a viewport border, below or
+    /// left of the path so that it clears `add_border_label` texts
+    pub fn add_border_name(&mut self, border: Border, crossing: Vec2, text: &str, color: Color, size: f32) {
+        let (width, height) = (self.projection.viewport.x, self.projection.viewport.y);
+        let inset = 4. * self.scale;
+        let (position, anchor) = match border {
+            Border::Left => (Vec2::new(inset, crossing.y - 2. * self.scale), Vec2::new(0., 1.)),
+            Border::Right => (Vec2::new(width - inset, crossing.y - 2. * self.scale), Vec2::new(1., 1.)),
+            Border::Bottom => (Vec2::new(crossing.x - inset, inset), Vec2::new(1., 0.)),
+            Border::Top => (Vec2::new(crossing.x - inset, height - inset), Vec2::new(1., 1.)),
+        };
+        self.add_label(position, text, color, size, anchor);
+    }
+
+    pub fn add_legend(&mut self, text: &str, color: Option<Color>) {
+        self.legend.push((text.to_string(), color));
+    }
+
+    /// Rasterize the chart, writing text with `font`
+    pub fn to_image<F: Font>(&self, font: &F) -> RgbaImage {
+        let (width, height) = self.size();
+        let mut canvas = Canvas {
+            image: RgbaImage::from_pixel(width, height, to_pixel(self.background)),
+            height: height as f32,
+        };
+        for line in &self.lines {
+            canvas.stroke(&line.points, &line.style);
+        }
+        for star in &self.stars {
+            canvas.disc(star.position, star.radius, star.color);
+        }
+        for label in &self.labels {
+            canvas.text(font, label);
+        }
+        self.draw_legend(&mut canvas, font);
+        canvas.image
+    }
+
+    pub fn save_png<P: AsRef<Path>, F: Font>(&self, path: P, font: &F) -> Result<(), Box<dyn error::Error>> {
+        if let Some(parent) = path.as_ref().parent() {
+            fs::create_dir_all(parent)?;
+        }
+        self.to_image(font).save(path)?;
+        Ok(())
+    }
+
+    fn draw_legend<F: Font>(&self, canvas: &mut Canvas, font: &F) {
+        let (boxes, labels) = self.legend_layout(|text, size| text_width(&font.as_scaled(PxScale::from(size)), text));
+        for (min, max, color) in boxes {
+            canvas.rectangle(min, max, color);
+        }
+        for label in &labels {
+            canvas.text(font, label);
+        }
+    }
+
+    /// Boxes and texts of the legend, in the top left corner, given the
+    /// width of a text at a size
+    pub fn legend_layout<W: Fn(&str, f32) -> f32>(&self, text_width: W) -> (Vec<LegendBox>, Vec<ChartLabel>) {
+        let (mut boxes, mut labels) = (vec![], vec![]);
+        if self.legend.is_empty() {
+            return (boxes, labels);
+        }
+        let size = LEGEND_TEXT_SIZE * self.scale;
+        let margin = 10. * self.scale;
+        let line_height = size * 1.4;
+        let swatch = size * 0.7;
+        let text_left = margin * 2. + swatch + size * 0.5;
+        let width = self.legend.iter()
+            .map(|(text, _)| text_width(text, size))
+            .fold(0., f32::max) + text_left;
+        let top = self.projection.viewport.y - margin;
+        let bottom = top - line_height * self.legend.len() as f32 - margin;
+        // dark box on dark skies, light box on paper
+        let light = self.background.r() + self.background.g() + self.background.b() > 1.5;
+        let (fill, ink) = if light {
+            (Color::rgba(1., 1., 1., 0.85), Color::BLACK)
+        } else {
+            (Color::rgba(0., 0., 0., 0.6), Color::WHITE)
+        };
+        boxes.push((Vec2::new(margin, bottom), Vec2::new(margin + width, top), fill));
+        for (row, (text, color)) in self.legend.iter().enumerate() {
+            let baseline = top - margin / 2. - line_height * (row as f32 + 1.);
+            if let Some(color) = color {
+                let corner = Vec2::new(margin * 2., baseline + size * 0.1);
+                boxes.push((corner, corner + Vec2::splat(swatch), *color));
+            }
+            labels.push(ChartLabel {
+                position: Vec2::new(text_left, baseline),
+                text: text.clone(),
+                color: ink,
+                size,
+                anchor: Vec2::ZERO,
+            });
+        }
+        (boxes, labels)
+    }
+}
+
+/// Width in pixels of a line of text
+pub fn text_width<F: Font, S: ScaleFont<F>>(font: &S, text: &str) -> f32 {
+    let mut width = 0.;
+    let mut previous = None;
+    for c in text.chars() {
+        let id = font.glyph_id(c);
+        if let Some(previous) = previous {
+            width += font.kern(previous, id);
+        }
+        width += font.h_advance(id);
+        previous = Some(id);
+    }
+    width
+}
+
+fn to_pixel(color: Color) -> Rgba<u8> {
+    let channel = |value: f32| (value.max(0.).min(1.) * 255.).round() as u8;
+    Rgba([channel(color.r()), channel(color.g()), channel(color.b()), 255])
+}
+
+/// Image being drawn, with chart positions flipped to rows from the top
+struct Canvas {
+    image: RgbaImage,
+    height: f32,
+}
+
+/// Longest stretch of a segment scanned at once, in pixels
+const SCAN_LENGTH: f32 = 64.;
+
+impl Canvas {
+    /// Image position of a chart position
+    fn flip(&self, position: Vec2) -> Vec2 {
+        Vec2::new(position.x, self.height - position.y)
+    }
+
+    /// Blend `color` over the pixel at column `x` and row `y`
+    fn blend(&mut self, x: i64, y: i64, color: Color, coverage: f32) {
+        if x < 0 || y < 0 || x >= self.image.width() as i64 || y >= self.image.height() as i64 {
+            return;
+        }
+        let alpha = color.a() * coverage;
+        if alpha <= 0. {
+            return;
+        }
+        let pixel = self.image.get_pixel_mut(x as u32, y as u32);
+        let source = [color.r(), color.g(), color.b()];
+        for (channel, value) in pixel.0.iter_mut().zip(source.iter()) {
+            let blended = *channel as f32 / 255. * (1. - alpha) + value.max(0.).min(1.) * alpha;
+            *channel = (blended * 255.).round() as u8;
+        }
+    }
+
+    /// Pixels within `reach` of the box spanned by two image positions
+    fn pixels(&self, a: Vec2, b: Vec2, reach: f32) -> impl Iterator<Item = (i64, i64)> {
+        let min = a.min(b) - Vec2::splat(reach);
+        let max = a.max(b) + Vec2::splat(reach);
+        let clamp = |value: f32, limit: u32| (value.floor() as i64).max(0).min(limit as i64);
+        let (x0, x1) = (clamp(min.x, self.image.width()), clamp(max.x + 1., self.image.width()));
+        let (y0, y1) = (clamp(min.y, self.image.height()), clamp(max.y + 1., self.image.height()));
+        (y0..y1).flat_map(move |y| (x0..x1).map(move |x| (x, y)))
+    }
+
+    /// Stroke a polyline with anti-aliased, round joined segments
+    fn stroke(&mut self, points: &[Vec2], style: &PathStyle) {
+        let mut along = 0.;
+        for (index, pair) in points.windows(2).enumerate() {
+            let (a, b) = (self.flip(pair[0]), self.flip(pair[1]));
+            self.segment(a, b, along, style, index == 0);
+            along += (b - a).length();
+        }
+    }
+
+    /// Stroke a segment in image positions, `along` being the path length
+    /// before it. Joints are drawn by the end of the previous segment.
+    fn segment(&mut self, a: Vec2, b: Vec2, along: f32, style: &PathStyle, start_cap: bool) {
+        let length = (b - a).length();
+        if length <= f32::EPSILON {
+            return;
+        }
+        let half = style.width / 2.;
+        let period = style.dash + style.gap;
+        // scan long segments piece by piece, each pixel belongs to the piece
+        // holding its nearest point
+        let pieces = (length / SCAN_LENGTH).ceil().max(1.);
+        for piece in 0..pieces as usize {
+            let (s0, s1) = (piece as f32 / pieces, (piece + 1) as f32 / pieces);
+            let last = piece + 1 == pieces as usize;
+            let pixels: Vec<(i64, i64)> = self.pixels(a + (b - a) * s0, a + (b - a) * s1, half + 1.).collect();
+            for (x, y) in pixels {
+                let p = Vec2::new(x as f32 + 0.5, y as f32 + 0.5);
+                let raw = (p - a).dot(b - a) / (length * length);
+                if raw < 0. && !start_cap {
+                    continue;
+                }
+                let t = raw.max(0.).min(1.);
+                if t < s0 || (t >= s1 && !last) {
+                    continue;
+                }
+                if style.dash > 0. && (along + t * length) % period > style.dash {
+                    continue;
+                }
+                let distance = (p - (a + (b - a) * t)).length();
+                let coverage = (half + 0.5 - distance).max(0.).min(1.);
+                self.blend(x, y, style.color, coverage);
+            }
+        }
+    }
+
+    /// Anti-aliased disc at a chart position
+    fn disc(&mut self, center: Vec2, radius: f32, color: Color) {
+        let center = self.flip(center);
+        let pixels: Vec<(i64, i64)> = self.pixels(center, center, radius + 1.).collect();
+        for (x, y) in pixels {
+            let distance = (Vec2::new(x as f32 + 0.5, y as f32 + 0.5) - center).length();
+            self.blend(x, y, color, (radius + 0.5 - distance).max(0.).min(1.));
+        }
+    }
+
+    /// Filled rectangle between two chart positions
+    fn rectangle(&mut self, min: Vec2, max: Vec2, color: Color) {
+        let pixels: Vec<(i64, i64)> = self.pixels(self.flip(min), self.flip(max), 0.).collect();
+        let (top, bottom) = (self.height - max.y, self.height - min.y);
+        for (x, y) in pixels {
+            let (column, row) = (x as f32 + 0.5, y as f32 + 0.5);
+            if column >= min.x && column <= max.x && row >= top && row <= bottom {
+                self.blend(x, y, color, 1.);
+            }
+        }
+    }
+
+    fn text<F: Font>(&mut self, font: &F, label: &ChartLabel) {
+        let scaled = font.as_scaled(PxScale::from(label.size));
+        let size = Vec2::new(text_width(&scaled, &label.text), scaled.ascent() - scaled.descent());
+        let corner = label.position - size * label.anchor;
+        // descent is negative, the baseline sits above the bottom of the box
+        let baseline = self.height - (corner.y - scaled.descent());
+        let mut x = corner.x;
+        let mut previous = None;
+        for c in label.text.chars() {
+            let id = scaled.glyph_id(c);
+            if let Some(previous) = previous {
+                x += scaled.kern(previous, id);
+            }
+            let glyph = id.with_scale_and_position(label.size, point(x, baseline));
+            if let Some(outlined) = font.outline_glyph(glyph) {
+                let bounds = outlined.px_bounds();
+                let (left, top) = (bounds.min.x as i64, bounds.min.y as i64);
+                let mut coverage = vec![];
+                outlined.draw(|gx, gy, c| coverage.push((left + gx as i64, top + gy as i64, c)));
+                for (gx, gy, c) in coverage {
+                    self.blend(gx, gy, label.color, c);
+                }
+            }
+            x += scaled.h_advance(id);
+            previous = Some(id);
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::render::{
+    mesh::Indices,
+    pipeline::{CompareFunction, CullMode, PipelineDescriptor, PrimitiveTopology, RenderPipeline},
+    render_graph::{base, AssetRenderResourcesNode, RenderGraph},
+    renderer::RenderResources,
+    shader::{ShaderStage, ShaderStages},
+    texture::{Extent3d, TextureDimension, TextureFormat},
+};
+use cgmath::Matrix3;
+use crate::world::frames::{to_cartesian, to_world};
+
+/// All-sky panorama drawn behind everything else: an equirectangular texture
+/// in galactic coordinates mapped on the inside of the celestial sphere.
+///
+/// The texture follows the usual convention of galactic panoramas, longitude
+/// 0 at the centre increasing to the left and the north galactic pole at the
+/// top.
+pub struct MilkyWayPlugin;
+
+/// Marker for the entity holding the panorama sphere
+pub struct MilkyWay;
+
+/// Pipeline used to draw the panorama
+pub struct MilkyWayPipeline(pub Handle<PipelineDescriptor>);
+
+#[derive(RenderResources, TypeUuid)]
+#[uuid = "0f8b2d61-93c4-4a57-b1e6-5c2a7e9d4f18"]
+pub struct MilkyWayMaterial {
+    /// Sky to clip space projection
+    pub view_proj: Mat4,
+    /// Opacity of the panorama over the black sky
+    pub brightness: f32,
+    pub texture: Handle<Texture>,
+}
+
+impl Plugin for MilkyWayPlugin {
+    fn build(&self, app: &mut AppBuilder){
+        app
+        .add_asset::<MilkyWayMaterial>()
+        .add_startup_system(setup_pipeline.system());
+    }
+}
+
+fn setup_pipeline(
+    mut commands: Commands,
+    mut pipelines: ResMut<Assets<PipelineDescriptor>>,
+    mut shaders: ResMut<Assets<Shader>>,
+    mut render_graph: ResMut<RenderGraph>,
+){
+    let mut descriptor = PipelineDescriptor::default_config(ShaderStages {
+        vertex: shaders.add(Shader::from_glsl(ShaderStage::Vertex, include_str!("shaders/milky_way.vert"))),
+        fragment: Some(shaders.add(Shader::from_glsl(ShaderStage::Fragment, include_str!("shaders/milky_way.frag")))),
+    });
+    // seen from the inside, and always behind stars and paths
+    descriptor.primitive.cull_mode = CullMode::None;
+    if let Some(depth_stencil) = descriptor.depth_stencil.as_mut() {
+        depth_stencil.depth_write_enabled = false;
+        depth_stencil.depth_compare = CompareFunction::Always;
+    }
+    commands.insert_resource(MilkyWayPipeline(pipelines.add(descriptor)));
+
+    render_graph.add_system_node(
+        "milky_way_material",
+        AssetRenderResourcesNode::<MilkyWayMaterial>::new(true),
+    );
+    render_graph
+        .add_node_edge("milky_way_material", base::node::MAIN_PASS)
+        .unwrap();
+}
+
+/// Build a sphere in scene coordinates with texture coordinates following
+/// galactic longitude and latitude. `to_equatorial` rotates galactic
+/// cartesian coordinates to the equatorial frame.
+pub fn milky_way_mesh(to_equatorial: Matrix3<f64>, lon_segments: u32, lat_segments: u32) -> Mesh {
+    let pi = std::f64::consts::PI;
+    let mut positions = vec![];
+    let mut uvs = vec![];
+    for j in 0..lat_segments+1 {
+        let v = j as f64 / lat_segments as f64;
+        let lat = pi / 2. - v * pi;
+        for i in 0..lon_segments+1 {
+            let u = i as f64 / lon_segments as f64;
+            // longitude decreases from +180° at the left edge to -180° at the right one
+            let lon = pi - u * 2. * pi;
+            let p = to_world(to_equatorial * to_cartesian(lon, lat));
+            positions.push([p[0], p[1], p[2]]);
+            uvs.push([u as f32, v as f32]);
+        }
+    }
+    let row = lon_segments + 1;
+    let mut indices = vec![];
+    for j in 0..lat_segments {
+        for i in 0..lon_segments {
+            let a = j * row + i;
+            let b = a + row;
+            indices.extend_from_slice(&[a, b, a + 1, a + 1, b, b + 1]);
+        }
+    }
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh
+}
+
+/// Brightness, from 0 to 1, of a smooth Milky Way at galactic longitude and
+/// latitude in radians, drawn when the panorama cannot be loaded
+pub fn procedural_brightness(lon: f64, lat: f64) -> f32 {
+    let lon = (lon.to_degrees() + 180.).rem_euclid(360.) - 180.;
+    let lat = lat.to_degrees();
+    // the disc is thicker and brighter towards the galactic centre
+    let towards_centre = 0.5 + 0.5 * lon.to_radians().cos();
+    let thickness = 6. + 4. * towards_centre;
+    let disc = (0.25 + 0.45 * towards_centre) * (-0.5 * (lat / thickness).powi(2)).exp();
+    let bulge = 0.3 * (-(lon * lon + lat * lat) / (2. * 12_f64.powi(2))).exp();
+    (disc + bulge).min(1.) as f32
+}
+
+/// Equirectangular texture of `procedural_brightness`, laid out like the
+/// panorama
+pub fn procedural_texture(width: u32, height: u32) -> Texture {
+    let pi = std::f64::consts::PI;
+    let mut data = Vec::with_capacity((width * height * 4) as usize);
+    for y in 0..height {
+        let lat = pi / 2. - (y as f64 + 0.5) / height as f64 * pi;
+        for x in 0..width {
+            let lon = pi - (x as f64 + 0.5) / width as f64 * 2. * pi;
+            let b = procedural_brightness(lon, lat);
+            // slightly warm, like the old stars of the disc
+            data.extend_from_slice(&[(b * 255.) as u8, (b * 240.) as u8, (b * 220.) as u8, 255]);
+        }
+    }
+    Texture::new(Extent3d::new(width, height, 1), TextureDimension::D2, data, TextureFormat::Rgba8UnormSrgb)
+}
+
+/// Spawn the panorama entity
+pub fn spawn_milky_way(
+    commands: &mut Commands,
+    pipeline: &MilkyWayPipeline,
+    mesh: Handle<Mesh>,
+    material: Handle<MilkyWayMaterial>,
+){
+    commands.spawn_bundle(MeshBundle {
+        mesh,
+        render_pipelines: RenderPipelines::from_pipelines(vec![RenderPipeline::new(pipeline.0.clone())]),
+        // opaque entities are drawn before the transparent stars and paths
+        visible: Visible {
+            is_transparent: false,
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+    .insert(material)
+    .insert(MilkyWay);
+}
@@ -0,0 +1,10 @@
+pub mod star_field;
+pub mod paths;
+pub mod milky_way;
+pub mod night_vision;
+pub mod camera;
+pub mod projection;
+pub mod chart;
+pub mod vector;
+pub mod atlas;
+pub mod screenshot;
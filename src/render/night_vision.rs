@@ -0,0 +1,243 @@
+use bevy::prelude::*;
+use bevy::render::{
+    pass::{LoadOp, Operations, PassDescriptor, RenderPassColorAttachmentDescriptor, TextureAttachment},
+    pipeline::{BindGroupDescriptorId, BlendState, ColorTargetState, ColorWrite, CullMode, PipelineCompiler, PipelineDescriptor, PipelineSpecialization},
+    render_graph::{base, Edge, Node, RenderGraph, RenderGraphError, ResourceSlotInfo, ResourceSlots, SlotLabel, WindowSwapChainNode, WindowTextureNode},
+    renderer::{BindGroup, RenderContext, RenderResourceBindings, RenderResourceContext, RenderResourceType, SamplerId},
+    shader::{ShaderStage, ShaderStages},
+    texture::{Extent3d, FilterMode, SamplerDescriptor, TextureDescriptor, TextureDimension, TextureFormat, TextureUsage},
+};
+use bevy::window::WindowId;
+use crate::controls::bindings::{Action, ActionInput, Bindings};
+
+/// Dim red monochrome display that preserves dark adaptation at the
+/// telescope.
+///
+/// While it is on, every pass drawing to the window (sky, bevy UI, egui)
+/// draws to a window sized texture instead, which a last full screen pass
+/// copies to the window in the night palette. Toggled by the night vision
+/// binding (`N` by default) in every state.
+pub struct NightVisionPlugin;
+
+/// Whether the night palette is active
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct NightVision {
+    pub enabled: bool,
+}
+
+/// Brightness of the red channel for a white input
+pub const NIGHT_BRIGHTNESS: f32 = 0.6;
+
+pub mod node {
+    pub const NIGHT_VISION_TEXTURE: &str = "night_vision_texture";
+    pub const NIGHT_VISION_PASS: &str = "night_vision_pass";
+}
+
+impl Plugin for NightVisionPlugin {
+    fn build(&self, app: &mut AppBuilder){
+        app
+        .init_resource::<NightVision>()
+        .add_startup_system(setup_pipeline.system())
+        .add_system(toggle_night_vision.system())
+        .add_system_to_stage(CoreStage::PostUpdate, route_window_passes.system());
+    }
+}
+
+/// Night palette applied to a color, as the night vision pass draws it: its
+/// brightest channel, in red
+pub fn night_color(color: Color) -> Color {
+    let [r, g, b, a] = color.as_linear_rgba_f32();
+    Color::rgba_linear(r.max(g).max(b) * NIGHT_BRIGHTNESS, 0., 0., a)
+}
+
+fn setup_pipeline(
+    mut pipelines: ResMut<Assets<PipelineDescriptor>>,
+    mut shaders: ResMut<Assets<Shader>>,
+    mut render_graph: ResMut<RenderGraph>,
+) {
+    let mut descriptor = PipelineDescriptor::new(ShaderStages {
+        vertex: shaders.add(Shader::from_glsl(ShaderStage::Vertex, include_str!("shaders/night_vision.vert"))),
+        fragment: Some(shaders.add(Shader::from_glsl(ShaderStage::Fragment, include_str!("shaders/night_vision.frag")))),
+    });
+    descriptor.primitive.cull_mode = CullMode::None;
+    descriptor.color_target_states = vec![ColorTargetState {
+        format: TextureFormat::default(),
+        color_blend: BlendState::REPLACE,
+        alpha_blend: BlendState::REPLACE,
+        write_mask: ColorWrite::ALL,
+    }];
+    setup_graph(&mut render_graph, pipelines.add(descriptor));
+}
+
+/// Add the night vision texture and pass to a graph holding the window
+/// passes, after all of them; they keep drawing to the window until
+/// [`route_to_night_vision`] says otherwise
+pub fn setup_graph(render_graph: &mut RenderGraph, pipeline: Handle<PipelineDescriptor>) {
+    render_graph.add_node(node::NIGHT_VISION_TEXTURE, WindowTextureNode::new(WindowId::primary(), TextureDescriptor {
+        // resized to the window before the first frame
+        size: Extent3d{width: 1, height: 1, depth: 1},
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: TextureDimension::D2,
+        format: TextureFormat::default(),
+        usage: TextureUsage::OUTPUT_ATTACHMENT | TextureUsage::SAMPLED,
+    }));
+    render_graph.add_node(node::NIGHT_VISION_PASS, NightVisionNode::new(pipeline));
+    render_graph.add_slot_edge(base::node::PRIMARY_SWAP_CHAIN, WindowSwapChainNode::OUT_TEXTURE, node::NIGHT_VISION_PASS, NightVisionNode::IN_WINDOW).unwrap();
+    render_graph.add_slot_edge(node::NIGHT_VISION_TEXTURE, WindowTextureNode::OUT_TEXTURE, node::NIGHT_VISION_PASS, NightVisionNode::IN_TEXTURE).unwrap();
+    for pass in [base::node::MAIN_PASS, bevy::ui::node::UI_PASS, bevy_egui::node::EGUI_PASS].iter() {
+        if render_graph.get_node_id(*pass).is_ok() {
+            render_graph.add_node_edge(*pass, node::NIGHT_VISION_PASS).unwrap();
+        }
+    }
+}
+
+/// Make the passes drawing to the window draw to the night vision texture
+/// when `enabled`, and back to the window otherwise
+pub fn route_to_night_vision(render_graph: &mut RenderGraph, enabled: bool) -> Result<(), RenderGraphError> {
+    if enabled {
+        move_slot_edges(render_graph, base::node::PRIMARY_SWAP_CHAIN, node::NIGHT_VISION_TEXTURE)
+    } else {
+        move_slot_edges(render_graph, node::NIGHT_VISION_TEXTURE, base::node::PRIMARY_SWAP_CHAIN)
+    }
+}
+
+/// Feed the inputs taken from an output of `from` with the output of `to`
+/// of the same name instead, except for the night vision pass which needs
+/// both
+fn move_slot_edges(render_graph: &mut RenderGraph, from: &'static str, to: &'static str) -> Result<(), RenderGraphError> {
+    let night_pass = render_graph.get_node_id(node::NIGHT_VISION_PASS)?;
+    let from_state = render_graph.get_node_state(from)?;
+    let to_state = render_graph.get_node_state(to)?;
+    let mut moved = vec![];
+    for edge in from_state.edges.output_edges.iter() {
+        if let Edge::SlotEdge{input_node, input_index, output_index, ..} = *edge {
+            if input_node == night_pass {
+                continue;
+            }
+            let name = from_state.output_slots.get_slot(output_index)?.info.name.clone();
+            let output_index = to_state.output_slots.get_slot_index(SlotLabel::Name(name))?;
+            moved.push((edge.clone(), Edge::SlotEdge{input_node, input_index, output_node: to_state.id, output_index}));
+        }
+    }
+    for (old, new) in moved {
+        render_graph.get_node_state_mut(from)?.edges.output_edges.retain(|edge| *edge != old);
+        render_graph.get_node_state_mut(to)?.edges.output_edges.push(new.clone());
+        let input_node = render_graph.get_node_state_mut(new.get_input_node())?;
+        for edge in input_node.edges.input_edges.iter_mut().filter(|edge| **edge == old) {
+            *edge = new.clone();
+        }
+    }
+    Ok(())
+}
+
+fn toggle_night_vision(
+    bindings: Res<Bindings>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mouse_input: Res<Input<MouseButton>>,
+    gamepad_input: Res<Input<GamepadButton>>,
+    mut night_vision: ResMut<NightVision>,
+) {
+    let input = ActionInput{keys: &keyboard_input, mouse: &mouse_input, gamepad: &gamepad_input};
+    if bindings.just_pressed(Action::NightVision, &input) {
+        night_vision.enabled = !night_vision.enabled;
+    }
+}
+
+/// The graph is staged again every frame, rerouted edges apply to the next
+fn route_window_passes(night_vision: Res<NightVision>, mut render_graph: ResMut<RenderGraph>) {
+    if night_vision.is_changed() {
+        route_to_night_vision(&mut render_graph, night_vision.enabled).unwrap();
+    }
+}
+
+/// Full screen pass drawing the night vision texture to the window in the
+/// night palette, run only while night vision is on
+struct NightVisionNode {
+    inputs: Vec<ResourceSlotInfo>,
+    pipeline: Handle<PipelineDescriptor>,
+    /// Compiled pipeline, the layout of its texture bind group and the
+    /// sampler bound with it
+    compiled: Option<(Handle<PipelineDescriptor>, BindGroupDescriptorId, SamplerId)>,
+    enabled: bool,
+}
+
+impl NightVisionNode {
+    const IN_WINDOW: &'static str = "window";
+    const IN_TEXTURE: &'static str = "texture";
+
+    fn new(pipeline: Handle<PipelineDescriptor>) -> NightVisionNode {
+        NightVisionNode {
+            inputs: vec![
+                ResourceSlotInfo::new(Self::IN_WINDOW, RenderResourceType::Texture),
+                ResourceSlotInfo::new(Self::IN_TEXTURE, RenderResourceType::Texture),
+            ],
+            pipeline,
+            compiled: None,
+            enabled: false,
+        }
+    }
+}
+
+impl Node for NightVisionNode {
+    fn input(&self) -> &[ResourceSlotInfo] {
+        &self.inputs
+    }
+
+    fn prepare(&mut self, world: &mut World) {
+        self.enabled = world.get_resource::<NightVision>().unwrap().enabled;
+        if !self.enabled || self.compiled.is_some() {
+            return;
+        }
+        let world = world.cell();
+        let render_resource_context = world.get_resource::<Box<dyn RenderResourceContext>>().unwrap();
+        let mut pipelines = world.get_resource_mut::<Assets<PipelineDescriptor>>().unwrap();
+        let mut shaders = world.get_resource_mut::<Assets<Shader>>().unwrap();
+        let mut pipeline_compiler = world.get_resource_mut::<PipelineCompiler>().unwrap();
+        let pipeline = pipeline_compiler.compile_pipeline(
+            &**render_resource_context,
+            &mut pipelines,
+            &mut shaders,
+            &self.pipeline,
+            &PipelineSpecialization::default(),
+        );
+        let layout = pipelines.get(&pipeline).and_then(|pipeline| pipeline.get_layout()).unwrap();
+        let bind_group_descriptor = layout.bind_groups[0].id;
+        // pixel for pixel copy
+        let sampler = render_resource_context.create_sampler(&SamplerDescriptor{min_filter: FilterMode::Nearest, ..Default::default()});
+        self.compiled = Some((pipeline, bind_group_descriptor, sampler));
+    }
+
+    fn update(
+        &mut self,
+        world: &World,
+        render_context: &mut dyn RenderContext,
+        input: &ResourceSlots,
+        _output: &mut ResourceSlots,
+    ) {
+        let (pipeline, bind_group_descriptor, sampler) = match &self.compiled {
+            Some(compiled) if self.enabled => compiled,
+            _ => return,
+        };
+        let window = input.get(Self::IN_WINDOW).and_then(|resource| resource.get_texture()).unwrap();
+        let texture = input.get(Self::IN_TEXTURE).and_then(|resource| resource.get_texture()).unwrap();
+        // same id for the same texture, created once
+        let bind_group = BindGroup::build().add_texture(0, texture).add_sampler(1, *sampler).finish();
+        render_context.resources().create_bind_group(*bind_group_descriptor, &bind_group);
+        let pass = PassDescriptor {
+            color_attachments: vec![RenderPassColorAttachmentDescriptor {
+                attachment: TextureAttachment::Id(window),
+                resolve_target: None,
+                ops: Operations{load: LoadOp::Clear(Color::BLACK), store: true},
+            }],
+            depth_stencil_attachment: None,
+            sample_count: 1,
+        };
+        let render_resource_bindings = world.get_resource::<RenderResourceBindings>().unwrap();
+        render_context.begin_pass(&pass, render_resource_bindings, &mut |render_pass| {
+            render_pass.set_pipeline(pipeline);
+            render_pass.set_bind_group(0, *bind_group_descriptor, bind_group.id, None);
+            render_pass.draw(0..3, 0..1);
+        });
+    }
+}
@@ -0,0 +1,274 @@
+use bevy::prelude::*;
+use bevy::render::{
+    mesh::Indices,
+    pipeline::{CompareFunction, CullMode, PipelineDescriptor, PrimitiveTopology, RenderPipeline},
+    shader::{ShaderStage, ShaderStages},
+};
+use cgmath::Vector4;
+use crate::render::projection::CLIP_HALF;
+
+/// Batched, frustum clipped and anti-aliased rendering of projected paths.
+///
+/// Paths are given in homogeneous clip coordinates, clipped segment by
+/// segment against the view frustum, expanded into screen space quads and
+/// accumulated into one `LineBatch` mesh per layer.
+pub struct PathRendererPlugin;
+
+/// Pipeline used to draw line batches
+pub struct PathPipeline(pub Handle<PipelineDescriptor>);
+
+/// Marker for the entity holding a line batch mesh
+pub struct LineBatchMesh;
+
+/// How a path is stroked
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PathStyle {
+    pub color: Color,
+    /// Width in pixels
+    pub width: f32,
+    /// Dash and gap lengths in pixels, no dashing when `dash` is zero
+    pub dash: f32,
+    pub gap: f32,
+}
+
+impl PathStyle {
+    pub fn solid(color: Color, width: f32) -> PathStyle {
+        PathStyle { color, width, dash: 0., gap: 0. }
+    }
+
+    pub fn dashed(color: Color, width: f32, dash: f32, gap: f32) -> PathStyle {
+        PathStyle { color, width, dash, gap }
+    }
+}
+
+impl Plugin for PathRendererPlugin {
+    fn build(&self, app: &mut AppBuilder){
+        app.add_startup_system(setup_pipeline.system());
+    }
+}
+
+fn setup_pipeline(
+    mut commands: Commands,
+    mut pipelines: ResMut<Assets<PipelineDescriptor>>,
+    mut shaders: ResMut<Assets<Shader>>,
+){
+    let mut descriptor = PipelineDescriptor::default_config(ShaderStages {
+        vertex: shaders.add(Shader::from_glsl(ShaderStage::Vertex, include_str!("shaders/path.vert"))),
+        fragment: Some(shaders.add(Shader::from_glsl(ShaderStage::Fragment, include_str!("shaders/path.frag")))),
+    });
+    descriptor.primitive.cull_mode = CullMode::None;
+    if let Some(depth_stencil) = descriptor.depth_stencil.as_mut() {
+        depth_stencil.depth_write_enabled = false;
+        depth_stencil.depth_compare = CompareFunction::Always;
+    }
+    commands.insert_resource(PathPipeline(pipelines.add(descriptor)));
+}
+
+/// Clip a segment in homogeneous coordinates against the visible part of
+/// the view frustum (`-CLIP_HALF * w <= x, y <= CLIP_HALF * w` and
+/// `-w <= z <= w`), returning the kept parameter range along it.
+pub fn clip_segment(a: Vector4<f32>, b: Vector4<f32>) -> Option<(f32, f32)> {
+    clip_segment_within(a, b, CLIP_HALF)
+}
+
+/// Clip a segment to `-half * w <= x, y <= half * w` and `-w <= z <= w`
+fn clip_segment_within(a: Vector4<f32>, b: Vector4<f32>, half: f32) -> Option<(f32, f32)> {
+    let planes = [
+        (half * a[3] + a[0], half * b[3] + b[0]),
+        (half * a[3] - a[0], half * b[3] - b[0]),
+        (half * a[3] + a[1], half * b[3] + b[1]),
+        (half * a[3] - a[1], half * b[3] - b[1]),
+        (a[3] + a[2], b[3] + b[2]),
+        (a[3] - a[2], b[3] - b[2]),
+    ];
+    let mut t0: f32 = 0.;
+    let mut t1: f32 = 1.;
+    for (da, db) in planes.iter() {
+        if *da < 0. && *db < 0. {
+            return None;
+        }
+        if *da < 0. {
+            t0 = f32::max(t0, da / (da - db));
+        } else if *db < 0. {
+            t1 = f32::min(t1, da / (da - db));
+        }
+    }
+    if t0 < t1 {
+        Some((t0, t1))
+    } else {
+        None
+    }
+}
+
+/// Side of the viewport
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Border {
+    Left,
+    Right,
+    Bottom,
+    Top,
+}
+
+/// Point where a path leaves or enters the viewport
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BorderCrossing {
+    pub border: Border,
+    /// Crossing point in normalized device coordinates
+    pub position: Vec2,
+}
+
+/// Find where a path given in clip coordinates crosses the border of the
+/// viewport spanning `-half..half` in normalized device coordinates.
+pub fn border_crossings(clip: &[Vector4<f32>], half: Vec2) -> Vec<BorderCrossing> {
+    let mut crossings = vec![];
+    for pair in clip.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        // clip with a margin so that the segment ends stay clear of the border
+        let (t0, t1) = match clip_segment_within(a, b, 1.) {
+            Some(range) => range,
+            None => continue,
+        };
+        let to_ndc = |v: Vector4<f32>| Vec2::new(v[0] / v[3], v[1] / v[3]);
+        let start = to_ndc(a + (b - a) * t0);
+        let end = to_ndc(a + (b - a) * t1);
+        let borders = [
+            (Border::Left, start.x, end.x, -half.x),
+            (Border::Right, start.x, end.x, half.x),
+            (Border::Bottom, start.y, end.y, -half.y),
+            (Border::Top, start.y, end.y, half.y),
+        ];
+        for (border, from, to, edge) in borders.iter() {
+            // half-open test so that a vertex lying on the border is counted once
+            if (from < edge) == (to < edge) {
+                continue;
+            }
+            let t = (edge - from) / (to - from);
+            let position = start + (end - start) * t;
+            let inside = match border {
+                Border::Left | Border::Right => position.y.abs() <= half.y,
+                Border::Bottom | Border::Top => position.x.abs() <= half.x,
+            };
+            if inside {
+                crossings.push(BorderCrossing{border: *border, position});
+            }
+        }
+    }
+    crossings
+}
+
+/// Vertex data of many stroked segments, uploaded as a single mesh
+#[derive(Default)]
+pub struct LineBatch {
+    positions: Vec<[f32; 3]>,
+    uvs: Vec<[f32; 2]>,
+    colors: Vec<[f32; 4]>,
+    styles: Vec<[f32; 4]>,
+    indices: Vec<u32>,
+}
+
+impl LineBatch {
+    pub fn new() -> LineBatch {
+        Default::default()
+    }
+
+    pub fn clear(&mut self) {
+        self.positions.clear();
+        self.uvs.clear();
+        self.colors.clear();
+        self.styles.clear();
+        self.indices.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    /// Number of segments in the batch
+    pub fn len(&self) -> usize {
+        self.indices.len() / 6
+    }
+
+    /// Add a path given in clip coordinates. `scale` maps normalized device
+    /// coordinates to pixels.
+    pub fn add_path(&mut self, clip: &[Vector4<f32>], style: &PathStyle, scale: Vec2) {
+        let mut along = 0.;
+        for pair in clip.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            let (t0, t1) = match clip_segment(a, b) {
+                Some(range) => range,
+                None => continue,
+            };
+            let to_screen = |v: Vector4<f32>| Vec2::new(v[0] / v[3] * scale.x, v[1] / v[3] * scale.y);
+            let start = to_screen(a + (b - a) * t0);
+            let end = to_screen(a + (b - a) * t1);
+            if t0 > 0. {
+                // the path re-enters the view, restart the dash pattern
+                along = 0.;
+            }
+            self.add_segment(start, end, along, style);
+            along += (end - start).length();
+        }
+    }
+
+    /// Add a screen space segment, `along` being the path length before it
+    pub fn add_segment(&mut self, start: Vec2, end: Vec2, along: f32, style: &PathStyle) {
+        let length = (end - start).length();
+        if length <= f32::EPSILON {
+            return;
+        }
+        let direction = (end - start) / length;
+        let normal = Vec2::new(-direction.y, direction.x);
+        // half width plus one pixel of feathering for anti-aliasing
+        let half = style.width / 2. + 1.;
+        let color = style.color.as_linear_rgba_f32();
+        let params = [style.width / 2., style.dash, style.gap, 0.];
+        let first = self.positions.len() as u32;
+        let corners = [
+            (start - normal * half, [along, -half]),
+            (end - normal * half, [along + length, -half]),
+            (end + normal * half, [along + length, half]),
+            (start + normal * half, [along, half]),
+        ];
+        for (position, uv) in corners.iter() {
+            self.positions.push([position.x, position.y, 0.]);
+            self.uvs.push(*uv);
+            self.colors.push(color);
+            self.styles.push(params);
+        }
+        self.indices.extend_from_slice(&[first, first + 1, first + 2, first, first + 2, first + 3]);
+    }
+
+    /// Write the batch into a mesh, replacing its previous content
+    pub fn write_mesh(&self, mesh: &mut Mesh) {
+        mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, self.positions.clone());
+        mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, self.uvs.clone());
+        mesh.set_attribute("Vertex_Color", self.colors.clone());
+        mesh.set_attribute("Vertex_Style", self.styles.clone());
+        mesh.set_indices(Some(Indices::U32(self.indices.clone())));
+    }
+
+    pub fn to_mesh(&self) -> Mesh {
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        self.write_mesh(&mut mesh);
+        mesh
+    }
+}
+
+/// Spawn an entity drawing a line batch mesh
+pub fn spawn_line_batch(
+    commands: &mut Commands,
+    pipeline: &PathPipeline,
+    mesh: Handle<Mesh>,
+) -> Entity {
+    commands.spawn_bundle(MeshBundle {
+        mesh,
+        render_pipelines: RenderPipelines::from_pipelines(vec![RenderPipeline::new(pipeline.0.clone())]),
+        visible: Visible {
+            is_transparent: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+    .insert(LineBatchMesh)
+    .id()
+}
@@ -0,0 +1,179 @@
+use bevy::math::Vec2;
+use cgmath::{perspective, InnerSpace, Matrix3, Matrix4, Rad, SquareMatrix, Vector3, Vector4};
+use std::error;
+use std::str::FromStr;
+
+/// How the celestial sphere is flattened
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProjectionKind {
+    /// Seen from the centre of the sphere, great circles are straight lines
+    Gnomonic,
+    /// Seen from the point opposite the view, circles stay circles and wide
+    /// fields are less stretched at the borders
+    Stereographic,
+}
+
+impl ProjectionKind {
+    pub const ALL: [ProjectionKind; 2] = [ProjectionKind::Gnomonic, ProjectionKind::Stereographic];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProjectionKind::Gnomonic => "gnomonic",
+            ProjectionKind::Stereographic => "stereographic",
+        }
+    }
+
+    /// Field of view parameter of a projection showing `field` radians
+    /// across the window height, which spans half the perspective image
+    pub fn fov_for_field(&self, field: f32) -> f32 {
+        match self {
+            ProjectionKind::Gnomonic => 2. * (2. * (field / 2.).tan()).atan(),
+            ProjectionKind::Stereographic => 4. * (2. * (field / 4.).tan()).atan(),
+        }
+    }
+}
+
+impl FromStr for ProjectionKind {
+    type Err = Box<dyn error::Error>;
+    fn from_str(s: &str) -> Result<Self, Box<dyn error::Error>> {
+        ProjectionKind::ALL.iter()
+            .find(|kind| kind.name() == s)
+            .copied()
+            .ok_or_else(|| format!("unknown projection {}", s).into())
+    }
+}
+
+/// Sky view-projection, computed once per frame and shared by every system
+/// drawing, labelling or picking on the sky.
+///
+/// World positions are scene positions (see `frames::to_world`). Clip
+/// coordinates are homogeneous and visible within ±`CLIP_HALF` once divided
+/// by w. Screen positions are in pixels from the bottom left window corner,
+/// like cursor positions and UI offsets.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ViewProjection {
+    /// World to clip transform
+    pub matrix: Matrix4<f32>,
+    /// Clip to world transform
+    pub inverse: Matrix4<f32>,
+    /// Window size in pixels
+    pub viewport: Vec2,
+    /// Vertical field of view of the projection, in radians
+    pub fov: f32,
+    pub kind: ProjectionKind,
+}
+
+/// Half extent of the visible region after the perspective divide
+pub const CLIP_HALF: f32 = 0.5;
+
+impl Default for ViewProjection {
+    fn default() -> Self {
+        ViewProjection {
+            matrix: Matrix4::identity(),
+            inverse: Matrix4::identity(),
+            viewport: Vec2::new(1., 1.),
+            fov: 1.,
+            kind: ProjectionKind::Gnomonic,
+        }
+    }
+}
+
+impl ViewProjection {
+    /// Perspective projection of the scene seen through a camera view matrix
+    pub fn new(fov: f32, viewport: Vec2, view: Matrix3<f64>) -> ViewProjection {
+        ViewProjection::with_kind(ProjectionKind::Gnomonic, fov, viewport, view)
+    }
+
+    /// Projection of the unit sphere of the scene through a camera view
+    /// matrix. The stereographic projection is a perspective seen from one
+    /// unit behind the centre, with half the field of view so that both
+    /// projections agree at the centre of the view.
+    pub fn with_kind(kind: ProjectionKind, fov: f32, viewport: Vec2, view: Matrix3<f64>) -> ViewProjection {
+        let view: Matrix4<f32> = Matrix4::from(view.cast::<f32>().unwrap());
+        let matrix = match kind {
+            ProjectionKind::Gnomonic => perspective(Rad(fov), viewport.x / viewport.y, 0.1, 100.) * view,
+            ProjectionKind::Stereographic => {
+                let eye = Matrix4::from_translation(Vector3::new(0., 0., -1.));
+                perspective(Rad(fov / 2.), viewport.x / viewport.y, 0.1, 100.) * eye * view
+            }
+        };
+        ViewProjection {
+            matrix,
+            inverse: matrix.invert().unwrap_or_else(Matrix4::identity),
+            viewport,
+            fov,
+            kind,
+        }
+    }
+
+    /// Homogeneous clip coordinates of a world position
+    pub fn world_to_clip(&self, world: Vector4<f32>) -> Vector4<f32> {
+        self.matrix * world
+    }
+
+    /// Position after the perspective divide, `None` behind the camera
+    pub fn world_to_ndc(&self, world: Vector4<f32>) -> Option<Vec2> {
+        let clip = self.world_to_clip(world);
+        if clip.w <= 0. {
+            return None;
+        }
+        Some(Vec2::new(clip.x / clip.w, clip.y / clip.w))
+    }
+
+    /// Pixel position of a world position, `None` behind the camera
+    pub fn world_to_screen(&self, world: Vector4<f32>) -> Option<Vec2> {
+        self.world_to_ndc(world).map(|ndc| self.ndc_to_screen(ndc))
+    }
+
+    pub fn ndc_to_screen(&self, ndc: Vec2) -> Vec2 {
+        (ndc + Vec2::splat(CLIP_HALF)) * self.viewport
+    }
+
+    pub fn screen_to_ndc(&self, screen: Vec2) -> Vec2 {
+        screen / self.viewport - Vec2::splat(CLIP_HALF)
+    }
+
+    /// Unit world direction seen at a pixel position
+    pub fn screen_to_world(&self, screen: Vec2) -> Vector3<f32> {
+        let ndc = self.screen_to_ndc(screen);
+        let point = self.inverse * Vector4::new(ndc.x, ndc.y, 0., 1.);
+        let point = point.truncate() / point.w;
+        if self.kind == ProjectionKind::Gnomonic {
+            // the camera sits at the origin, any depth gives the same direction
+            return point.normalize();
+        }
+        // the eye is the point projected to infinity, the ray leaving it
+        // meets the unit sphere on the far side
+        let eye = self.inverse * Vector4::new(0., 0., 1., 0.);
+        let eye = eye.truncate() / eye.w;
+        let ray = (point - eye).normalize();
+        let along = -eye.dot(ray);
+        let t = along + (along * along - eye.magnitude2() + 1.).max(0.).sqrt();
+        (eye + ray * t).normalize()
+    }
+
+    /// Whether a world position falls inside the window
+    pub fn is_visible(&self, world: Vector4<f32>) -> bool {
+        self.is_visible_within(world, 0.)
+    }
+
+    /// Whether a world position falls inside the window grown by `margin`
+    /// pixels on every side, to keep labels and sprites near the border
+    pub fn is_visible_within(&self, world: Vector4<f32>, margin: f32) -> bool {
+        match self.world_to_screen(world) {
+            Some(screen) => {
+                screen.x >= -margin && screen.x <= self.viewport.x + margin
+                    && screen.y >= -margin && screen.y <= self.viewport.y + margin
+            }
+            None => false,
+        }
+    }
+
+    /// Pixels per radian of sky at the centre of the window
+    pub fn pixels_per_radian(&self) -> f32 {
+        match self.kind {
+            ProjectionKind::Gnomonic => self.viewport.y / (self.fov / 2.).tan(),
+            ProjectionKind::Stereographic => self.viewport.y / (2. * (self.fov / 4.).tan()),
+        }
+    }
+}
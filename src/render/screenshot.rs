@@ -0,0 +1,262 @@
+use bevy::prelude::*;
+use bevy::render::{
+    pass::{LoadOp, Operations, PassDescriptor, RenderPassDepthStencilAttachmentDescriptor, TextureAttachment},
+    render_graph::{base::{self, MainPass}, Node, PassNode, RenderGraph, ResourceSlotInfo, ResourceSlots, WindowTextureNode},
+    renderer::{BufferId, BufferInfo, BufferMapMode, BufferUsage, RenderContext, RenderResourceContext},
+    texture::{Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsage},
+};
+use bevy::ui;
+use image::RgbaImage;
+use std::cell::RefCell;
+use std::error;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// Window screenshots, read back from the GPU.
+///
+/// The swap chain cannot be copied from, so on request the sky and the UI
+/// text are drawn a second time, by the same cameras, into a window sized
+/// texture that can. It is copied to a buffer in the same frame and saved
+/// as a PNG the frame after, once the GPU is done. egui windows are left
+/// out: bevy_egui hands the shapes of a frame to its own pass only, a
+/// second egui pass would have nothing to draw.
+pub struct ScreenshotPlugin;
+
+/// Screenshots requested, and copied to the GPU buffers they are read from
+#[derive(Default)]
+pub struct Screenshots {
+    requested: Vec<ScreenshotRequest>,
+    copied: Vec<CopiedScreenshot>,
+}
+
+/// Where to save a screenshot, and the message telling how it went
+struct ScreenshotRequest {
+    path: String,
+    status: Arc<Mutex<String>>,
+}
+
+struct CopiedScreenshot {
+    request: ScreenshotRequest,
+    buffer: BufferId,
+    width: u32,
+    height: u32,
+    bytes_per_row: u32,
+}
+
+impl Screenshots {
+    /// Capture the next frame to the PNG file at `path`, writing the outcome
+    /// to `status`
+    pub fn request(&mut self, path: String, status: Arc<Mutex<String>>) {
+        *status.lock().unwrap() = format!("Saving {}...", path);
+        self.requested.push(ScreenshotRequest{path, status});
+    }
+}
+
+mod node {
+    pub const SCREENSHOT_TEXTURE: &str = "screenshot_texture";
+    pub const SCREENSHOT: &str = "screenshot";
+}
+
+impl Plugin for ScreenshotPlugin {
+    fn build(&self, app: &mut AppBuilder){
+        app
+        .init_resource::<Screenshots>()
+        .add_startup_system(setup_graph.system())
+        .add_system(save_screenshots.system());
+    }
+}
+
+fn setup_graph(mut render_graph: ResMut<RenderGraph>, msaa: Res<Msaa>) {
+    let texture = |sample_count, usage| WindowTextureNode::new(bevy::window::WindowId::primary(), TextureDescriptor {
+        // resized to the window before the first frame
+        size: Extent3d{width: 1, height: 1, depth: 1},
+        mip_level_count: 1,
+        sample_count,
+        dimension: TextureDimension::D2,
+        format: TextureFormat::default(),
+        usage,
+    });
+    // the copied texture is the resolved one, when there is one
+    render_graph.add_node(node::SCREENSHOT_TEXTURE, texture(1, TextureUsage::OUTPUT_ATTACHMENT | TextureUsage::COPY_SRC));
+    render_graph.add_node(node::SCREENSHOT, ScreenshotNode::new(&msaa));
+    if msaa.samples > 1 {
+        render_graph.add_node("screenshot_sampled_texture", texture(msaa.samples, TextureUsage::OUTPUT_ATTACHMENT));
+        render_graph.add_slot_edge("screenshot_sampled_texture", WindowTextureNode::OUT_TEXTURE, node::SCREENSHOT, "color_attachment").unwrap();
+        render_graph.add_slot_edge(node::SCREENSHOT_TEXTURE, WindowTextureNode::OUT_TEXTURE, node::SCREENSHOT, "color_resolve_target").unwrap();
+    } else {
+        render_graph.add_slot_edge(node::SCREENSHOT_TEXTURE, WindowTextureNode::OUT_TEXTURE, node::SCREENSHOT, "color_attachment").unwrap();
+    }
+    render_graph.add_slot_edge(base::node::MAIN_DEPTH_TEXTURE, WindowTextureNode::OUT_TEXTURE, node::SCREENSHOT, "depth").unwrap();
+    // after the window passes, which come after everything they draw
+    render_graph.add_node_edge(ui::node::UI_PASS, node::SCREENSHOT).unwrap();
+}
+
+/// Sky and UI passes into the screenshot texture, and its copy to a buffer,
+/// run only in the frames a screenshot was requested
+struct ScreenshotNode {
+    sky_pass: PassNode<&'static MainPass>,
+    ui_pass: PassNode<&'static ui::Node>,
+    inputs: Vec<ResourceSlotInfo>,
+    /// Index of the input slot of the copied texture
+    copied_input: usize,
+    /// Buffer, row size and size of the screenshot taken this frame
+    capture: Option<(BufferId, u32, Extent3d)>,
+}
+
+impl ScreenshotNode {
+    fn new(msaa: &Msaa) -> ScreenshotNode {
+        let pass = |load| PassDescriptor {
+            color_attachments: vec![msaa.color_attachment_descriptor(
+                TextureAttachment::Input("color_attachment".to_string()),
+                TextureAttachment::Input("color_resolve_target".to_string()),
+                Operations{load, store: true},
+            )],
+            depth_stencil_attachment: Some(RenderPassDepthStencilAttachmentDescriptor {
+                attachment: TextureAttachment::Input("depth".to_string()),
+                depth_ops: Some(Operations{load: LoadOp::Clear(1.0), store: true}),
+                stencil_ops: None,
+            }),
+            sample_count: msaa.samples,
+        };
+        // both passes take the same inputs, in the same order
+        let mut sky_pass = PassNode::<&MainPass>::new(pass(LoadOp::Clear(Color::BLACK)));
+        sky_pass.use_default_clear_color(0);
+        sky_pass.add_camera(base::camera::CAMERA_2D);
+        sky_pass.add_camera(base::camera::CAMERA_3D);
+        let mut ui_pass = PassNode::<&ui::Node>::new(pass(LoadOp::Load));
+        ui_pass.add_camera(ui::camera::CAMERA_UI);
+        let inputs = sky_pass.input().to_vec();
+        ScreenshotNode {
+            sky_pass,
+            ui_pass,
+            inputs,
+            copied_input: if msaa.samples > 1 {1} else {0},
+            capture: None,
+        }
+    }
+}
+
+impl Node for ScreenshotNode {
+    fn input(&self) -> &[ResourceSlotInfo] {
+        &self.inputs
+    }
+
+    fn prepare(&mut self, world: &mut World) {
+        self.capture = world.resource_scope(|world, mut screenshots: Mut<Screenshots>| start_capture(world, &mut screenshots));
+        if self.capture.is_some() {
+            self.sky_pass.prepare(world);
+            self.ui_pass.prepare(world);
+        }
+    }
+
+    fn update(
+        &mut self,
+        world: &World,
+        render_context: &mut dyn RenderContext,
+        input: &ResourceSlots,
+        output: &mut ResourceSlots,
+    ) {
+        let (buffer, bytes_per_row, size) = match self.capture.take() {
+            Some(capture) => capture,
+            None => return,
+        };
+        self.sky_pass.update(world, render_context, input, output);
+        self.ui_pass.update(world, render_context, input, output);
+        let texture = input.get(self.copied_input).and_then(|resource| resource.get_texture()).unwrap();
+        render_context.copy_texture_to_buffer(texture, [0, 0, 0], 0, buffer, 0, bytes_per_row, size);
+    }
+}
+
+/// Buffer the requested screenshots are copied to this frame, its row size
+/// and the window size, `None` when there are none
+fn start_capture(world: &World, screenshots: &mut Screenshots) -> Option<(BufferId, u32, Extent3d)> {
+    if screenshots.requested.is_empty() {
+        return None;
+    }
+    let window = world.get_resource::<Windows>()?.get_primary()?;
+    let (width, height) = (window.physical_width(), window.physical_height());
+    if width == 0 || height == 0 {
+        for request in screenshots.requested.drain(..) {
+            *request.status.lock().unwrap() = format!("Could not save {}: the window is minimized", request.path);
+        }
+        return None;
+    }
+    let render_resource_context = world.get_resource::<Box<dyn RenderResourceContext>>()?;
+    // rows of a texture copy are aligned
+    let bytes_per_row = render_resource_context.get_aligned_texture_size(width as usize * 4) as u32;
+    let buffer = render_resource_context.create_buffer(BufferInfo {
+        size: (bytes_per_row * height) as usize,
+        buffer_usage: BufferUsage::MAP_READ | BufferUsage::COPY_DST,
+        mapped_at_creation: false,
+    });
+    // every request of the frame gets the same image
+    let requests: Vec<ScreenshotRequest> = screenshots.requested.drain(..).collect();
+    for request in requests {
+        screenshots.copied.push(CopiedScreenshot{request, buffer, width, height, bytes_per_row});
+    }
+    Some((buffer, bytes_per_row, Extent3d{width, height, depth: 1}))
+}
+
+/// Read back the screenshots copied last frame and save them
+fn save_screenshots(
+    mut screenshots: ResMut<Screenshots>,
+    render_resource_context: Res<Box<dyn RenderResourceContext>>,
+) {
+    let mut read = vec![];
+    for copied in screenshots.copied.drain(..) {
+        // requests of one frame share their buffer
+        let pixels = match read.iter().find(|(buffer, _)| *buffer == copied.buffer) {
+            Some((_, pixels)) => Arc::clone(pixels),
+            None => {
+                let data = RefCell::new(vec![]);
+                render_resource_context.map_buffer(copied.buffer, BufferMapMode::Read);
+                render_resource_context.read_mapped_buffer(
+                    copied.buffer,
+                    0..(copied.bytes_per_row * copied.height) as u64,
+                    &|bytes, _| {
+                        data.replace(bgra_rows_to_rgba(bytes, copied.width, copied.height, copied.bytes_per_row));
+                    },
+                );
+                render_resource_context.unmap_buffer(copied.buffer);
+                render_resource_context.remove_buffer(copied.buffer);
+                let pixels = Arc::new(data.into_inner());
+                read.push((copied.buffer, Arc::clone(&pixels)));
+                pixels
+            }
+        };
+        let CopiedScreenshot{request, width, height, ..} = copied;
+        // encoding a large PNG takes a while, keep the view running meanwhile
+        std::thread::spawn(move || {
+            let message = match save_png(&request.path, width, height, pixels.to_vec()) {
+                Ok(()) => format!("Saved {}", request.path),
+                Err(e) => format!("Could not save {}: {}", request.path, e),
+            };
+            info!("{}", message);
+            *request.status.lock().unwrap() = message;
+        });
+    }
+}
+
+fn save_png(path: &str, width: u32, height: u32, pixels: Vec<u8>) -> Result<(), Box<dyn error::Error>> {
+    if let Some(parent) = Path::new(path).parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let image = RgbaImage::from_raw(width, height, pixels).ok_or("screenshot size mismatch")?;
+    image.save(path)?;
+    Ok(())
+}
+
+/// Opaque RGBA pixels of a copied BGRA texture, without the padding at the
+/// end of its rows
+pub fn bgra_rows_to_rgba(data: &[u8], width: u32, height: u32, bytes_per_row: u32) -> Vec<u8> {
+    let row_size = width as usize * 4;
+    let mut pixels = Vec::with_capacity(row_size * height as usize);
+    for row in data.chunks(bytes_per_row as usize).take(height as usize) {
+        for bgra in row[..row_size].chunks_exact(4) {
+            // blending leaves alpha anywhere, the window shows it opaque
+            pixels.extend_from_slice(&[bgra[2], bgra[1], bgra[0], 255]);
+        }
+    }
+    pixels
+}
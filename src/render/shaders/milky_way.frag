@@ -0,0 +1,18 @@
+#version 450
+
+layout(location = 0) in vec2 v_Uv;
+
+layout(location = 0) out vec4 o_Target;
+
+layout(set = 2, binding = 1) uniform MilkyWayMaterial_brightness {
+    float Brightness;
+};
+layout(set = 2, binding = 2) uniform texture2D MilkyWayMaterial_texture;
+layout(set = 2, binding = 3) uniform sampler MilkyWayMaterial_texture_sampler;
+
+void main() {
+    vec4 color = texture(
+        sampler2D(MilkyWayMaterial_texture, MilkyWayMaterial_texture_sampler),
+        v_Uv);
+    o_Target = vec4(color.rgb, Brightness);
+}
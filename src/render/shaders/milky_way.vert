@@ -0,0 +1,25 @@
+#version 450
+
+layout(location = 0) in vec3 Vertex_Position;
+layout(location = 1) in vec2 Vertex_Uv;
+
+layout(location = 0) out vec2 v_Uv;
+
+layout(set = 0, binding = 0) uniform CameraViewProj {
+    mat4 ViewProj;
+};
+layout(set = 1, binding = 0) uniform Transform {
+    mat4 Model;
+};
+layout(set = 2, binding = 0) uniform MilkyWayMaterial_view_proj {
+    mat4 SkyViewProj;
+};
+
+void main() {
+    vec4 clip = SkyViewProj * Model * vec4(Vertex_Position, 1.0);
+    // same ndc * window size pixel mapping as the star field, keeping w so
+    // that triangles are clipped and interpolated in perspective; the depth
+    // is pinned half way so that only w <= 0 gets clipped away
+    gl_Position = vec4(2.0 * clip.xy, 0.5 * clip.w, clip.w);
+    v_Uv = Vertex_Uv;
+}
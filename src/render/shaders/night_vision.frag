@@ -0,0 +1,14 @@
+#version 450
+
+layout(location = 0) in vec2 v_Uv;
+
+layout(location = 0) out vec4 o_Target;
+
+layout(set = 0, binding = 0) uniform texture2D NightVision_texture;
+layout(set = 0, binding = 1) uniform sampler NightVision_texture_sampler;
+
+void main() {
+    vec3 color = texture(sampler2D(NightVision_texture, NightVision_texture_sampler), v_Uv).rgb;
+    // brightest channel, in red: night_vision::NIGHT_BRIGHTNESS for white
+    o_Target = vec4(max(color.r, max(color.g, color.b)) * 0.6, 0.0, 0.0, 1.0);
+}
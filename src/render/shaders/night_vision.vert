@@ -0,0 +1,11 @@
+#version 450
+
+layout(location = 0) out vec2 v_Uv;
+
+void main() {
+    // one triangle covering the whole window, no vertex buffer needed
+    vec2 position = vec2(float((gl_VertexIndex << 1) & 2), float(gl_VertexIndex & 2));
+    gl_Position = vec4(2.0 * position - 1.0, 0.0, 1.0);
+    // texture rows go down, clip space y goes up
+    v_Uv = vec2(position.x, 1.0 - position.y);
+}
@@ -0,0 +1,22 @@
+#version 450
+
+// x: distance along the path (px), y: signed distance to the center line (px)
+layout(location = 0) in vec2 v_Uv;
+layout(location = 1) in vec4 v_Color;
+// x: half width (px), y: dash length (px), z: gap length (px)
+layout(location = 2) in vec4 v_Style;
+
+layout(location = 0) out vec4 o_Target;
+
+void main() {
+    // one pixel wide coverage ramp on both edges
+    float coverage = clamp(v_Style.x + 0.5 - abs(v_Uv.y), 0.0, 1.0);
+    if (v_Style.y > 0.0) {
+        float phase = mod(v_Uv.x, v_Style.y + v_Style.z);
+        coverage *= clamp(v_Style.y - phase + 0.5, 0.0, 1.0) * clamp(phase + 0.5, 0.0, 1.0);
+    }
+    if (coverage <= 0.0) {
+        discard;
+    }
+    o_Target = vec4(v_Color.rgb, v_Color.a * coverage);
+}
@@ -0,0 +1,15 @@
+#version 450
+
+layout(location = 0) in vec2 v_Uv;
+layout(location = 1) in vec4 v_Color;
+
+layout(location = 0) out vec4 o_Target;
+
+layout(set = 2, binding = 3) uniform texture2D StarFieldMaterial_texture;
+layout(set = 2, binding = 4) uniform sampler StarFieldMaterial_texture_sampler;
+
+void main() {
+    o_Target = v_Color * texture(
+        sampler2D(StarFieldMaterial_texture, StarFieldMaterial_texture_sampler),
+        v_Uv);
+}
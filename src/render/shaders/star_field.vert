@@ -0,0 +1,55 @@
+#version 450
+
+layout(location = 0) in vec3 Vertex_Position;
+layout(location = 1) in vec2 Vertex_Uv;
+layout(location = 2) in float Vertex_Magnitude;
+layout(location = 3) in vec3 Vertex_Color;
+
+layout(location = 0) out vec2 v_Uv;
+layout(location = 1) out vec4 v_Color;
+
+layout(set = 0, binding = 0) uniform CameraViewProj {
+    mat4 ViewProj;
+};
+layout(set = 1, binding = 0) uniform Transform {
+    mat4 Model;
+};
+layout(set = 2, binding = 0) uniform StarFieldMaterial_view_proj {
+    mat4 SkyViewProj;
+};
+// x: limiting magnitude, y: contrast, z: min radius (px), w: max radius (px)
+layout(set = 2, binding = 1) uniform StarFieldMaterial_photometry {
+    vec4 Photometry;
+};
+// xy: window size (px), z: color saturation
+layout(set = 2, binding = 2) uniform StarFieldMaterial_viewport {
+    vec4 Viewport;
+};
+
+void main() {
+    // mirrors BrightnessModel::point_source
+    float limit_flux = pow(10.0, -0.4);
+    float flux = pow(10.0, -0.4 * (Vertex_Magnitude - (Photometry.x - 1.0)));
+    float radius = Photometry.z * pow(flux, Photometry.y / 2.0);
+    float alpha = 1.0;
+    if (radius < Photometry.z) {
+        alpha = max((flux - limit_flux) / (1.0 - limit_flux), 0.0);
+        radius = Photometry.z;
+    }
+    radius = min(radius, Photometry.w);
+
+    vec4 clip = SkyViewProj * Model * vec4(Vertex_Position, 1.0);
+    if (clip.w <= 0.0 || alpha <= 0.0) {
+        // behind the camera or too faint: emit a degenerate vertex outside the viewport
+        gl_Position = vec4(2.0, 2.0, 2.0, 1.0);
+        v_Uv = Vertex_Uv;
+        v_Color = vec4(0.0);
+        return;
+    }
+    // projected coordinates map to pixels as ndc * window size, like sprites did
+    vec2 center = 2.0 * clip.xy / clip.w;
+    vec2 offset = Vertex_Uv * 2.0 * radius / Viewport.xy;
+    gl_Position = vec4(center + offset, 0.0, 1.0);
+    v_Uv = Vertex_Uv * 0.5 + 0.5;
+    v_Color = vec4(mix(vec3(1.0), Vertex_Color, Viewport.z), alpha);
+}
@@ -0,0 +1,129 @@
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::render::{
+    mesh::Indices,
+    pipeline::{CompareFunction, CullMode, PipelineDescriptor, PrimitiveTopology, RenderPipeline},
+    render_graph::{base, AssetRenderResourcesNode, RenderGraph},
+    renderer::RenderResources,
+    shader::{ShaderStage, ShaderStages},
+};
+use cgmath::{Matrix4, Vector4};
+
+/// GPU star rendering: every star of the catalogue lives in a single mesh
+/// uploaded once, and projection, sizing and fading happen in the vertex
+/// shader from the uniforms of a `StarFieldMaterial`.
+pub struct StarFieldPlugin;
+
+/// Marker for the entity holding the star field mesh
+pub struct StarField;
+
+/// Pipeline used to draw star field meshes
+pub struct StarFieldPipeline(pub Handle<PipelineDescriptor>);
+
+#[derive(RenderResources, TypeUuid)]
+#[uuid = "6b5f3a8e-2f0c-4c1e-9d4a-71f1c2f4b0d3"]
+pub struct StarFieldMaterial {
+    /// Sky to clip space projection
+    pub view_proj: Mat4,
+    /// Limiting magnitude, contrast, min radius and max radius in pixels
+    pub photometry: Vec4,
+    /// Window width and height in pixels, color saturation
+    pub viewport: Vec4,
+    pub texture: Handle<Texture>,
+}
+
+/// One star as uploaded to the GPU
+pub struct StarVertex {
+    pub position: Vector4<f32>,
+    pub mag: f32,
+    pub color: Color,
+}
+
+impl Plugin for StarFieldPlugin {
+    fn build(&self, app: &mut AppBuilder){
+        app
+        .add_asset::<StarFieldMaterial>()
+        .add_startup_system(setup_pipeline.system());
+    }
+}
+
+fn setup_pipeline(
+    mut commands: Commands,
+    mut pipelines: ResMut<Assets<PipelineDescriptor>>,
+    mut shaders: ResMut<Assets<Shader>>,
+    mut render_graph: ResMut<RenderGraph>,
+){
+    let mut descriptor = PipelineDescriptor::default_config(ShaderStages {
+        vertex: shaders.add(Shader::from_glsl(ShaderStage::Vertex, include_str!("shaders/star_field.vert"))),
+        fragment: Some(shaders.add(Shader::from_glsl(ShaderStage::Fragment, include_str!("shaders/star_field.frag")))),
+    });
+    // stars are blended over each other, not depth tested
+    descriptor.primitive.cull_mode = CullMode::None;
+    if let Some(depth_stencil) = descriptor.depth_stencil.as_mut() {
+        depth_stencil.depth_write_enabled = false;
+        depth_stencil.depth_compare = CompareFunction::Always;
+    }
+    commands.insert_resource(StarFieldPipeline(pipelines.add(descriptor)));
+
+    render_graph.add_system_node(
+        "star_field_material",
+        AssetRenderResourcesNode::<StarFieldMaterial>::new(true),
+    );
+    render_graph
+        .add_node_edge("star_field_material", base::node::MAIN_PASS)
+        .unwrap();
+}
+
+/// Build a mesh with one quad per star
+pub fn star_field_mesh(stars: &[StarVertex]) -> Mesh {
+    let corners: [[f32; 2]; 4] = [[-1., -1.], [1., -1.], [1., 1.], [-1., 1.]];
+    let mut positions = Vec::with_capacity(stars.len() * 4);
+    let mut uvs = Vec::with_capacity(stars.len() * 4);
+    let mut mags = Vec::with_capacity(stars.len() * 4);
+    let mut colors = Vec::with_capacity(stars.len() * 4);
+    let mut indices = Vec::with_capacity(stars.len() * 6);
+    for (i, star) in stars.iter().enumerate() {
+        let [r, g, b, _] = star.color.as_linear_rgba_f32();
+        for corner in corners.iter() {
+            positions.push([star.position[0], star.position[1], star.position[2]]);
+            uvs.push(*corner);
+            mags.push(star.mag);
+            colors.push([r, g, b]);
+        }
+        let first = i as u32 * 4;
+        indices.extend_from_slice(&[first, first + 1, first + 2, first, first + 2, first + 3]);
+    }
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh.set_attribute("Vertex_Magnitude", mags);
+    mesh.set_attribute("Vertex_Color", colors);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh
+}
+
+/// Spawn the star field entity
+pub fn spawn_star_field(
+    commands: &mut Commands,
+    pipeline: &StarFieldPipeline,
+    mesh: Handle<Mesh>,
+    material: Handle<StarFieldMaterial>,
+){
+    commands.spawn_bundle(MeshBundle {
+        mesh,
+        render_pipelines: RenderPipelines::from_pipelines(vec![RenderPipeline::new(pipeline.0.clone())]),
+        visible: Visible {
+            is_transparent: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+    .insert(material)
+    .insert(StarField);
+}
+
+/// Convert a cgmath matrix to a bevy one
+pub fn to_mat4(m: Matrix4<f32>) -> Mat4 {
+    let cols: [[f32; 4]; 4] = m.into();
+    Mat4::from_cols_array_2d(&cols)
+}
@@ -0,0 +1,198 @@
+//! SVG and PDF output of charts, for printing at any size.
+//!
+//! Texts are set in a monospace font so that labels and the legend can be
+//! laid out without reading a font file: SVG viewers pick the monospace
+//! font of the system and PDF uses the built-in Courier.
+use bevy::math::Vec2;
+use bevy::render::color::Color;
+use std::collections::BTreeMap;
+use std::error;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+use crate::render::chart::{Chart, ChartLabel, LegendBox};
+
+/// Advance of a monospace glyph, in text heights
+pub const MONOSPACE_ADVANCE: f32 = 0.6;
+/// Depth of the monospace descenders, in text heights
+const MONOSPACE_DESCENT: f32 = 0.2;
+
+/// Width of a line of monospace text `size` high
+pub fn monospace_width(text: &str, size: f32) -> f32 {
+    text.chars().count() as f32 * MONOSPACE_ADVANCE * size
+}
+
+/// Chart position of the start of a label's baseline
+fn baseline(label: &ChartLabel) -> Vec2 {
+    let size = Vec2::new(monospace_width(&label.text, label.size), label.size);
+    let corner = label.position - size * label.anchor;
+    corner + Vec2::new(0., MONOSPACE_DESCENT * label.size)
+}
+
+/// Labels and legend texts, with the legend boxes drawn under them
+fn texts(chart: &Chart) -> (Vec<LegendBox>, Vec<ChartLabel>) {
+    let (boxes, legend) = chart.legend_layout(monospace_width);
+    let labels = chart.labels.iter().cloned().chain(legend).collect();
+    (boxes, labels)
+}
+
+fn hex(color: Color) -> String {
+    let channel = |value: f32| (value.clamp(0., 1.) * 255.).round() as u8;
+    format!("#{:02x}{:02x}{:02x}", channel(color.r()), channel(color.g()), channel(color.b()))
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// SVG document of a chart, one user unit per chart pixel
+pub fn to_svg(chart: &Chart) -> String {
+    let (width, height) = chart.size();
+    let flip = |p: Vec2| Vec2::new(p.x, height as f32 - p.y);
+    let opacity = |attribute: &str, color: Color| {
+        if color.a() < 1. { format!(" {}=\"{:.3}\"", attribute, color.a()) } else { String::new() }
+    };
+    let mut svg = String::new();
+    writeln!(svg, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>").unwrap();
+    writeln!(svg, "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">",
+        w = width, h = height).unwrap();
+    writeln!(svg, "<rect width=\"{}\" height=\"{}\" fill=\"{}\"/>", width, height, hex(chart.background)).unwrap();
+    for line in &chart.lines {
+        let points: Vec<String> = line.points.iter()
+            .map(|p| flip(*p))
+            .map(|p| format!("{:.2},{:.2}", p.x, p.y))
+            .collect();
+        let style = &line.style;
+        let dash = if style.dash > 0. {
+            format!(" stroke-dasharray=\"{:.2} {:.2}\"", style.dash, style.gap)
+        } else {
+            String::new()
+        };
+        writeln!(svg, "<polyline points=\"{}\" fill=\"none\" stroke=\"{}\"{} stroke-width=\"{:.2}\" stroke-linecap=\"round\" stroke-linejoin=\"round\"{}/>",
+            points.join(" "), hex(style.color), opacity("stroke-opacity", style.color), style.width, dash).unwrap();
+    }
+    for star in &chart.stars {
+        let center = flip(star.position);
+        writeln!(svg, "<circle cx=\"{:.2}\" cy=\"{:.2}\" r=\"{:.2}\" fill=\"{}\"{}/>",
+            center.x, center.y, star.radius, hex(star.color), opacity("fill-opacity", star.color)).unwrap();
+    }
+    let (boxes, labels) = texts(chart);
+    for (min, max, color) in boxes {
+        writeln!(svg, "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\" fill=\"{}\"{}/>",
+            min.x, height as f32 - max.y, max.x - min.x, max.y - min.y, hex(color), opacity("fill-opacity", color)).unwrap();
+    }
+    for label in &labels {
+        let start = flip(baseline(label));
+        writeln!(svg, "<text x=\"{:.2}\" y=\"{:.2}\" font-family=\"monospace\" font-size=\"{:.2}\" fill=\"{}\"{}>{}</text>",
+            start.x, start.y, label.size, hex(label.color), opacity("fill-opacity", label.color), escape_xml(&label.text)).unwrap();
+    }
+    writeln!(svg, "</svg>").unwrap();
+    svg
+}
+
+/// PDF string literal of a text in the WinAnsi encoding of the built-in
+/// fonts, characters outside Latin-1 being replaced by `?`
+fn pdf_string(text: &str) -> String {
+    let mut literal = String::from("(");
+    for c in text.chars() {
+        match c as u32 {
+            0x28 | 0x29 | 0x5c => {
+                literal.push('\\');
+                literal.push(c);
+            }
+            0x20..=0x7e => literal.push(c),
+            code @ 0xa0..=0xff => write!(literal, "\\{:03o}", code).unwrap(),
+            _ => literal.push('?'),
+        }
+    }
+    literal.push(')');
+    literal
+}
+
+/// Single page PDF document of a chart, one point per chart pixel
+pub fn to_pdf(chart: &Chart) -> Vec<u8> {
+    let (width, height) = chart.size();
+    // graphics states for every opacity in use, by hundredths
+    let mut alphas: BTreeMap<u32, String> = BTreeMap::new();
+    let mut alpha = |color: Color| -> String {
+        let key = (color.a().clamp(0., 1.) * 100.).round() as u32;
+        alphas.entry(key).or_insert_with(|| format!("A{}", key)).clone()
+    };
+    let rgb = |color: Color| format!("{:.3} {:.3} {:.3}", color.r(), color.g(), color.b());
+    let mut content = String::new();
+    writeln!(content, "{} rg 0 0 {} {} re f", rgb(chart.background), width, height).unwrap();
+    for line in &chart.lines {
+        let style = &line.style;
+        let dash = if style.dash > 0. { format!("[{:.2} {:.2}]", style.dash, style.gap) } else { "[]".to_string() };
+        write!(content, "q /{} gs {} RG {:.2} w 1 J 1 j {} 0 d", alpha(style.color), rgb(style.color), style.width, dash).unwrap();
+        for (index, p) in line.points.iter().enumerate() {
+            write!(content, " {:.2} {:.2} {}", p.x, p.y, if index == 0 { "m" } else { "l" }).unwrap();
+        }
+        writeln!(content, " S Q").unwrap();
+    }
+    for star in &chart.stars {
+        // four cubic arcs
+        let (c, r) = (star.position, star.radius);
+        let k = r * 0.5523;
+        writeln!(content,
+            "q /{} gs {} rg {:.2} {:.2} m {:.2} {:.2} {:.2} {:.2} {:.2} {:.2} c {:.2} {:.2} {:.2} {:.2} {:.2} {:.2} c \
+             {:.2} {:.2} {:.2} {:.2} {:.2} {:.2} c {:.2} {:.2} {:.2} {:.2} {:.2} {:.2} c f Q",
+            alpha(star.color), rgb(star.color),
+            c.x + r, c.y,
+            c.x + r, c.y + k, c.x + k, c.y + r, c.x, c.y + r,
+            c.x - k, c.y + r, c.x - r, c.y + k, c.x - r, c.y,
+            c.x - r, c.y - k, c.x - k, c.y - r, c.x, c.y - r,
+            c.x + k, c.y - r, c.x + r, c.y - k, c.x + r, c.y).unwrap();
+    }
+    let (boxes, labels) = texts(chart);
+    for (min, max, color) in boxes {
+        writeln!(content, "q /{} gs {} rg {:.2} {:.2} {:.2} {:.2} re f Q",
+            alpha(color), rgb(color), min.x, min.y, max.x - min.x, max.y - min.y).unwrap();
+    }
+    for label in &labels {
+        let start = baseline(label);
+        writeln!(content, "q /{} gs BT /F1 {:.2} Tf {} rg {:.2} {:.2} Td {} Tj ET Q",
+            alpha(label.color), label.size, rgb(label.color), start.x, start.y, pdf_string(&label.text)).unwrap();
+    }
+
+    let states: Vec<String> = alphas.iter()
+        .map(|(key, name)| format!("/{} << /Type /ExtGState /CA {:.2} /ca {:.2} >>", name, *key as f32 / 100., *key as f32 / 100.))
+        .collect();
+    let objects = [
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+        format!("<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Contents 4 0 R \
+                 /Resources << /Font << /F1 5 0 R >> /ExtGState << {} >> >> >>", width, height, states.join(" ")),
+        // the end of line before endstream is not part of the stream
+        format!("<< /Length {} >>\nstream\n{}\nendstream", content.trim_end().len(), content.trim_end()),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Courier /Encoding /WinAnsiEncoding >>".to_string(),
+    ];
+    let mut pdf = String::from("%PDF-1.4\n");
+    let mut offsets = vec![];
+    for (index, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        write!(pdf, "{} 0 obj\n{}\nendobj\n", index + 1, object).unwrap();
+    }
+    let xref = pdf.len();
+    write!(pdf, "xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).unwrap();
+    for offset in offsets {
+        writeln!(pdf, "{:010} 00000 n ", offset).unwrap();
+    }
+    write!(pdf, "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", objects.len() + 1, xref).unwrap();
+    pdf.into_bytes()
+}
+
+/// Save a chart as SVG or PDF, following the extension of `path`
+pub fn save_chart<P: AsRef<Path>>(chart: &Chart, path: P) -> Result<(), Box<dyn error::Error>> {
+    let path = path.as_ref();
+    let bytes = match path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase()).as_deref() {
+        Some("svg") => to_svg(chart).into_bytes(),
+        Some("pdf") => to_pdf(chart),
+        _ => return Err(format!("{}: expected an .svg or .pdf file", path.display()).into()),
+    };
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, bytes)?;
+    Ok(())
+}
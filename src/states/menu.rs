@@ -1,6 +1,7 @@
 use bevy::prelude::*;
 use bevy::ecs::component::Component;
 use crate::consts::*;
+use crate::controls::bindings::{Action, ActionInput, Bindings};
 use keyframe::{keyframes, AnimationSequence};
 use std::time::Duration;
 pub struct Menu;
@@ -46,10 +47,15 @@ impl FromWorld for ButtonMaterials {
 }
 
 fn enter_stars(
-    keys: Res<Input<KeyCode>>, 
+    bindings: Res<Bindings>,
+    mut keys: ResMut<Input<KeyCode>>,
+    mut mouse: ResMut<Input<MouseButton>>,
+    mut gamepad: ResMut<Input<GamepadButton>>,
     mut app_state: ResMut<State<AppState>>
 ){
-    if keys.pressed(KeyCode::Return) {
+    if bindings.just_pressed(Action::Start, &ActionInput{keys: &keys, mouse: &mouse, gamepad: &gamepad}) {
+        // the sky view runs this frame, it must not see the press
+        bindings.consume(Action::Start, &mut keys, &mut mouse, &mut gamepad);
         app_state.set(AppState::Stars).unwrap();
     }
 }
@@ -1,3 +1,4 @@
 pub mod stars;
 pub mod menu;
-pub mod pause;
\ No newline at end of file
+pub mod pause;
+pub mod tour;
\ No newline at end of file
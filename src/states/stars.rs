@@ -1,64 +1,379 @@
 use bevy::prelude::*;
-use bevy::{core::FixedTimestep, input::mouse::{MouseWheel, MouseMotion, MouseButtonInput}};
-use cgmath::{Rad, perspective, Matrix4, Vector4, Vector3};
-use bevy_prototype_debug_lines::{DebugLinesPlugin, DebugLines};
-use std::fs::File;
+use bevy::asset::LoadState;
+use bevy::input::mouse::{MouseWheel, MouseMotion, MouseButtonInput};
+use cgmath::{InnerSpace, Matrix3, Quaternion, Vector4, Vector3};
+use std::fs::{self, File};
+use std::error;
+use std::sync::{Arc, Mutex};
+use ab_glyph::FontVec;
+use chrono::{NaiveDateTime, Utc};
 use crate::consts::*;
 use crate::units::polar::Polar;
+use crate::units::hms::HMS;
+use crate::units::dms::DMS;
+use crate::catalog::{reader::Catalogue, legacy};
+use crate::world::color::bv_to_rgb;
+use crate::world::photometry::BrightnessModel;
+use crate::world::frames::{Frame, to_cartesian, to_spherical, to_world};
+use crate::world::observer::Observer;
+use crate::world::clock::SimulationClock;
+use crate::world::sky::{SkyConditions, SkyState};
+use crate::world::deep_sky::read_deep_sky;
+use crate::world::equipment::Equipment;
+use crate::world::overlay::{self, Reticle};
+use crate::render::star_field::{
+    StarFieldPlugin, StarField, StarFieldMaterial, StarFieldPipeline, StarVertex,
+    star_field_mesh, spawn_star_field, to_mat4};
+use crate::render::camera::{SkyCamera, CameraMode, CameraFrame, CameraTransition};
+use crate::render::projection::{ProjectionKind, ViewProjection, CLIP_HALF};
+use crate::render::chart::Chart;
+use crate::render::screenshot::{ScreenshotPlugin, Screenshots};
+use crate::controls::navigation::{Gamepads, StickSettings, KEY_ROLL_SPEED, key_acceleration, key_snap, stick_acceleration};
+use crate::controls::dynamics::{CameraDynamics, CameraInertia, PIXELS_PER_LINE};
+use crate::controls::bindings::{Action, ActionInput, Binding, Bindings, BINDINGS_PATH};
+use crate::controls::bookmarks::{Bookmark, Bookmarks, BOOKMARKS_PATH};
+use crate::controls::replay::FrameTime;
+use crate::states::tour::{TourCommand, TourTarget};
+use crate::render::milky_way::{
+    MilkyWayPlugin, MilkyWay, MilkyWayMaterial, MilkyWayPipeline, milky_way_mesh, procedural_texture, spawn_milky_way};
+use crate::render::paths::{
+    PathRendererPlugin, PathPipeline, PathStyle, LineBatch, LineBatchMesh, Border,
+    border_crossings, spawn_line_batch};
 use bevy::ecs::component::Component;
+use bevy::utils::HashMap;
 use bevy_egui::{egui, EguiContext, EguiPlugin};
 
 pub struct Stars;
-struct Fov(f32);
-struct Camera{rot_x: f32, rot_y: f32}
-struct MouseInertia{x: f32, y: f32, z: f32}
+
+const CATALOGUE_PATH: &str = "assets/data/stars.bin";
+const LEGACY_CATALOGUE_PATH: &str = "assets/data/stars.csv";
+const DEEP_SKY_PATH: &str = "assets/data/deep_sky.csv";
+/// Equirectangular all-sky panorama in galactic coordinates
+const MILKY_WAY_TEXTURE: &str = "images/milky_way.png";
+/// System labels ordering camera updates before projection and drawing
+const CAMERA: &str = "camera";
+const VIEW_PROJ: &str = "view_proj";
+const PROJECTED_PATHS: &str = "projected_paths";
+/// Label of the system grabbing presses while a binding is being edited
+const BINDING_CAPTURE: &str = "binding_capture";
+/// Distance in pixels from the cursor within which stars are picked
+const PICK_RADIUS: f32 = 8.;
+/// Faintest limiting magnitude offered in the UI
+const MAX_STAR_MAG: f32 = 8.;
+/// Days the clock can move before the grids of frames of date are rebuilt
+const FRAME_OF_DATE_TOLERANCE: f64 = 10.;
+
+/// Field of view of the camera, in radians
+pub struct Fov(pub f32);
+/// How the sky view is flattened
+pub struct SkyProjection(pub ProjectionKind);
 struct Path3D(Vec<Vector4<f32>>);
 
-enum Path2DKind {
+/// What a projected path draws, which decides where it is labelled
+pub enum Path2DKind {
     PhiCircle,
     ThetaCircle,
-    Constellation
+    Equator,
+    Pole,
+    Constellation,
+    Overlay,
 }
 struct Path2D{
     data: Vec<Vector4<f32>>,
     marker: String,
     kind: Path2DKind}
 struct Grid;
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum GridPart {
+    Lines,
+    Equator,
+    Poles,
+    Meridian,
+}
+/// Coordinate system of a grid: a celestial frame or the local horizon
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum GridFrame {
+    Sky(Frame),
+    Horizon,
+}
+
+impl GridFrame {
+    fn all() -> Vec<GridFrame> {
+        let mut frames: Vec<GridFrame> = Frame::ALL.iter().map(|f| GridFrame::Sky(*f)).collect();
+        frames.push(GridFrame::Horizon);
+        frames
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            GridFrame::Sky(frame) => frame.name(),
+            GridFrame::Horizon => "Alt-azimuth",
+        }
+    }
+}
+/// Reference frame layer a grid path belongs to
+#[derive(Clone, Copy)]
+struct GridLayer{frame: GridFrame, part: GridPart}
+/// Visibility toggles of a reference frame's grid, equator, poles and meridian
+#[derive(Clone, Copy, PartialEq)]
+struct FrameLayers{grid: bool, equator: bool, poles: bool, meridian: bool}
+struct GridLayers(HashMap<GridFrame, FrameLayers>);
+
+impl Default for GridLayers {
+    fn default() -> Self {
+        let mut layers = HashMap::default();
+        for frame in GridFrame::all() {
+            let enabled = frame == GridFrame::Sky(Frame::Equatorial);
+            layers.insert(frame, FrameLayers{grid: enabled, equator: enabled, poles: enabled, meridian: enabled});
+        }
+        GridLayers(layers)
+    }
+}
+
+impl GridLayers {
+    fn is_visible(&self, layer: Option<&GridLayer>) -> bool {
+        let layer = match layer {
+            Some(layer) => layer,
+            None => return true,
+        };
+        match self.0.get(&layer.frame) {
+            Some(toggles) => match layer.part {
+                GridPart::Lines => toggles.grid,
+                GridPart::Equator => toggles.equator,
+                GridPart::Poles => toggles.poles,
+                GridPart::Meridian => toggles.meridian,
+            },
+            None => false,
+        }
+    }
+}
+/// Horizon coordinates (azimuth, altitude) of a path, reprojected as time passes
+struct HorizonPath(Vec<(f64, f64)>);
+/// Spherical coordinates (theta, phi) of a path in a frame of date,
+/// reprojected as time passes
+struct FramePath(Frame, Vec<(f32, f32)>);
 struct GridMarker;
+/// Where a grid marker is anchored: the n-th crossing of a viewport border,
+/// with line values on one side of the line and equator names on the other,
+/// or a pole center
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MarkerAnchor {
+    Border(Border, usize),
+    Name(Border, usize),
+    Center,
+}
+/// Pool of grid marker text entities, keyed by grid line and anchor
+#[derive(Default)]
+struct GridMarkers(HashMap<(Entity, MarkerAnchor), Entity>);
+/// Font shared by the labels drawn over the sky
+struct LabelFont(Handle<Font>);
+
+impl FromWorld for LabelFont {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.get_resource::<AssetServer>().unwrap();
+        LabelFont(asset_server.load("fonts/ShareTechMono-Regular.ttf"))
+    }
+}
 struct Constellation;
-struct MouseButtonPressed(bool);
+/// Whether the sky is being dragged
+pub struct MouseButtonPressed(pub bool);
 struct Star;
+struct StarId(u32);
+struct StarMagnitude(f32);
+/// Catalogue colour of a star, before saturation
+struct StarColor(Color);
 struct Position3D(Vector4<f32>);
 struct FpsLog(Vec<f32>);
+struct StarColorSaturation(f32);
+struct ShowMilkyWay(bool);
+struct ShowDeepSky(bool);
+/// Milky Way panorama being loaded, shown in place of the procedural one
+/// once it is
+struct PendingMilkyWay(Option<Handle<Texture>>);
+/// Master switches of the grids and of their border labels
+struct ShowGrids(bool);
+struct ShowLabels(bool);
+/// Camera move to a saved view in progress
+struct ViewTransition(Option<CameraTransition>);
+/// Seconds taken to fly to a saved view
+const VIEW_TRANSITION_DURATION: f32 = 1.5;
+/// Set to move the keyboard focus to the star search field
+struct SearchFocus(bool);
+/// State of the key bindings window
+#[derive(Default)]
+struct BindingsEditor{
+    open: bool,
+    /// Action waiting for the next key or button press
+    capture: Option<Action>,
+    status: String,
+}
+/// Object picked by the user, J2000 coordinates in radians
+#[derive(Clone, PartialEq)]
+struct SelectedObject{name: String, ra: f64, dec: f64}
+struct Selection(Option<SelectedObject>);
+/// What field of view overlays are centred on
+#[derive(Clone, Copy, PartialEq)]
+enum OverlayCenter {
+    View,
+    Selection,
+}
+#[derive(Clone, Copy, PartialEq)]
+struct OverlaySettings{
+    show_eyepiece: bool,
+    show_sensor: bool,
+    /// Angle of the sensor width from east towards north, degrees
+    rotation: f64,
+    center: OverlayCenter,
+}
+/// Where the finder reticle sits: screen centre or a fixed sky position
+#[derive(Clone, Copy, PartialEq)]
+enum ReticlePin {
+    Screen,
+    Sky{ra: f64, dec: f64},
+}
+#[derive(Clone, PartialEq)]
+struct ReticleSettings{
+    enabled: bool,
+    reticles: Vec<Reticle>,
+    selected: usize,
+    pin: ReticlePin,
+}
+/// One outline of the finder reticle, by index
+struct ReticlePath(usize);
+/// Outline of the field seen through the selected equipment
+#[derive(Clone, Copy, PartialEq)]
+enum FovOverlay {
+    Eyepiece,
+    Sensor,
+}
+const EYEPIECE_COLOR: Color = Color::rgb(1., 0.6, 0.2);
+const SENSOR_COLOR: Color = Color::rgb(0.4, 0.8, 1.);
+const DEEP_SKY_COLOR: Color = Color::rgb(1., 0.45, 0.45);
+/// Smallest radius of a deep sky outline, in radians
+const DEEP_SKY_MIN_RADIUS: f64 = 0.0015;
+/// Outline of a deep sky object, faded with the sky background
+struct DeepSkyOutline{surface_brightness: Option<f32>}
+/// Text size of the grid markers at window resolution
+const MARKER_FONT_SIZE: f32 = 13.;
+/// Folder where screenshots and charts are saved
+const SCREENSHOTS_PATH: &str = "screenshots";
+/// Font of the text of saved charts
+const CHART_FONT: &str = "assets/fonts/ShareTechMono-Regular.ttf";
+/// Request to save the window as a PNG file
+struct TakeScreenshot;
+/// Request to draw the sky view as a chart of the given size, saved as a
+/// PNG file
+struct ExportChart{size: Vec2}
+/// Outcome of the last screenshot or export, written by the thread saving it
+#[derive(Default)]
+struct ExportStatus(Arc<Mutex<String>>);
+/// Chart size typed in the export window
+struct ExportSettings{width: u32, height: u32}
+
+/// Systems moving, projecting and drawing the sky, shared by the sky view and
+/// guided tours
+fn sky_systems(set: SystemSet) -> SystemSet {
+    set
+    .with_system(advance_clock.system())
+    .with_system(update_horizon_paths.system().before(PROJECTED_PATHS))
+    .with_system(update_frame_paths.system().before(PROJECTED_PATHS))
+    .with_system(update_fov_overlays.system().before(PROJECTED_PATHS))
+    .with_system(update_reticle.system().before(PROJECTED_PATHS))
+    .with_system(apply_tour_commands.system().before(CAMERA))
+    .with_system(update_camera_frame.system().label(CAMERA))
+    .with_system(animate_view_transition.system().after(CAMERA).before(VIEW_PROJ))
+    .with_system(update_view_proj.system().label(VIEW_PROJ).after(CAMERA))
+    .with_system(path_projection.system().label(PROJECTED_PATHS).after(VIEW_PROJ))
+    .with_system(update_star_field.system().after(VIEW_PROJ))
+    .with_system(update_milky_way.system().after(VIEW_PROJ))
+    .with_system(load_milky_way.system())
+    .with_system(fade_deep_sky.system())
+    .with_system(apply_sky_conditions.system())
+    .with_system(render_2d_paths.system().after(PROJECTED_PATHS))
+    .with_system(render_grid_markers.system().after(PROJECTED_PATHS))
+}
 
 impl Plugin for Stars {
     fn build(&self, app: &mut AppBuilder){
-        const TIME_STEP: f32 = 1.0 / 200.0;
         app
         .insert_resource(Fov(1.6))
-        .insert_resource(Camera{rot_x: 0., rot_y: 0.})
-        .insert_resource(MouseInertia{x: 0., y: 0., z: 0.})
+        .insert_resource(SkyProjection(ProjectionKind::Gnomonic))
+        .insert_resource(SkyCamera::default())
+        .init_resource::<ViewProjection>()
+        .init_resource::<BindingsEditor>()
+        .insert_resource(SearchFocus(false))
+        .insert_resource(load_bookmarks())
+        .insert_resource(ViewTransition(None))
+        .insert_resource(ShowGrids(true))
+        .insert_resource(ShowLabels(true))
+        .init_resource::<StickSettings>()
+        .init_resource::<CameraInertia>()
+        .init_resource::<CameraDynamics>()
+        .add_event::<TakeScreenshot>()
+        .add_event::<ExportChart>()
+        .init_resource::<ExportStatus>()
+        .insert_resource(ExportSettings{width: 8000, height: 8000})
         .insert_resource(MouseButtonPressed(false))
         .insert_resource(FpsLog(vec![0.; 150]))
-        .add_plugin(DebugLinesPlugin)
+        .insert_resource(StarColorSaturation(0.6))
+        .insert_resource(ShowMilkyWay(true))
+        .insert_resource(PendingMilkyWay(None))
+        .insert_resource(ShowDeepSky(true))
+        .insert_resource(SkyConditions::from_bortle(Observer::default().bortle))
+        .insert_resource(BrightnessModel::default())
+        .init_resource::<GridLayers>()
+        .init_resource::<GridMarkers>()
+        .init_resource::<LabelFont>()
+        .init_resource::<Observer>()
+        .insert_resource(SimulationClock::now())
+        .init_resource::<Equipment>()
+        .insert_resource(Selection(None))
+        .insert_resource(ReticleSettings{
+            enabled: false,
+            reticles: vec![
+                Reticle::telrad(),
+                Reticle::raci(),
+                Reticle{name: "Custom".to_string(), rings: vec![1., 3.], crosshair: true}],
+            selected: 0,
+            pin: ReticlePin::Screen})
+        .insert_resource(OverlaySettings{show_eyepiece: false, show_sensor: false, rotation: 0., center: OverlayCenter::View})
+        .add_plugin(PathRendererPlugin)
+        .add_plugin(StarFieldPlugin)
+        .add_plugin(MilkyWayPlugin)
         .add_plugin(EguiPlugin)
+        .add_plugin(ScreenshotPlugin)
         .add_system_set(
-            SystemSet::on_update(AppState::Stars)
-        //    .with_run_criteria(FixedTimestep::step(TIME_STEP as f64))
-            .with_system(path_projection.system())
-            .with_system(draw_stars.system())
-            .with_system(render_2d_paths.system())
-            .with_system(render_grid_markers.system())
-            .with_system(fov_adjust.system())
-            .with_system(orbit_camera.system())
-            .with_system(pause.system())
+            sky_systems(SystemSet::on_update(AppState::Stars))
+            .with_system(fov_adjust.system().before(CAMERA))
+            .with_system(orbit_camera.system().label(CAMERA))
+            .with_system(capture_binding.system().label(BINDING_CAPTURE))
+            .with_system(keyboard_navigation.system().before(CAMERA).after(BINDING_CAPTURE))
+            .with_system(sky_actions.system().after(BINDING_CAPTURE))
+            .with_system(ui_bindings.system())
+            .with_system(ui_bookmarks.system())
+            .with_system(gamepad_navigation.system().before(CAMERA))
+            .with_system(pick_star.system().after(VIEW_PROJ).after(BINDING_CAPTURE))
+            .with_system(pause.system().after(BINDING_CAPTURE))
             .with_system(ui_infos.system())
+            .with_system(ui_layers.system())
+            .with_system(ui_observer.system())
+            .with_system(ui_equipment.system())
+            .with_system(ui_reticle.system())
+            .with_system(ui_export.system())
+            .with_system(take_screenshot.system())
+            .with_system(export_chart.system().after(VIEW_PROJ))
         )
+        // the sky keeps drawing under guided tours
+        .add_system_set(sky_systems(SystemSet::on_update(AppState::Tour)))
         .add_system_set(
             SystemSet::on_enter(AppState::Stars)
             .with_system(setup_2d_camera.system())
-            .with_system(setup_equatorial_grid.system())
-            .with_system(setup_sprites.system()),
+            .with_system(setup_grids.system())
+            .with_system(setup_horizon_grid.system())
+            .with_system(setup_star_field.system())
+            .with_system(setup_milky_way.system())
+            .with_system(setup_deep_sky.system())
+            .with_system(setup_fov_overlays.system()),
         )
         .add_system_set(
             SystemSet::on_exit(AppState::Stars)
@@ -66,6 +381,12 @@ impl Plugin for Stars {
             .with_system(cleanup_system::<Path2D>.system())
             .with_system(cleanup_system::<Star>.system())
             .with_system(cleanup_system::<Position3D>.system())
+            .with_system(cleanup_system::<StarField>.system())
+            .with_system(cleanup_system::<MilkyWay>.system())
+            .with_system(cleanup_system::<LineBatchMesh>.system())
+            .with_system(cleanup_system::<GridMarker>.system())
+            .with_system(clear_grid_markers.system())
+            .with_system(reset_clear_color.system())
         );
     }
 }
@@ -73,11 +394,18 @@ impl Plugin for Stars {
 fn ui_infos(
     egui_context: ResMut<EguiContext>, 
     app_state: Res<State<AppState>>,
-    mut query_stars: Query<(&mut Transform, &mut Position3D, With<Star>)>,
+    query_stars: Query<&Position3D, With<Star>>,
     fov: Res<Fov>,
-    camera: Res<Camera>,
+    mut camera: ResMut<SkyCamera>,
+    observer: Res<Observer>,
+    clock: Res<SimulationClock>,
     time: Res<Time>,
-    mut fps: ResMut<FpsLog>
+    mut fps: ResMut<FpsLog>,
+    mut saturation: ResMut<StarColorSaturation>,
+    mut brightness: ResMut<BrightnessModel>,
+    mut dynamics: ResMut<CameraDynamics>,
+    bindings: Res<Bindings>,
+    mut sky_projection: ResMut<SkyProjection>,
 ) {
     match app_state.current() {
         AppState::Menu => {
@@ -95,12 +423,47 @@ fn ui_infos(
                         ui.add(egui::Label::new(format!("{:.2} rad", fov.0)));
                     });
                     ui.horizontal(|ui| {
-                        ui.add(egui::Label::new("Rotation:").text_color(egui::Color32::from_rgb(110, 255, 110)));
-                        ui.add(egui::Label::new(format!("({:.2}, {:.2}) rad", camera.rot_x, camera.rot_y)));
+                        ui.add(egui::Label::new("Centre:").text_color(egui::Color32::from_rgb(110, 255, 110)));
+                        let (ra, dec) = view_center(&camera);
+                        ui.add(egui::Label::new(format!("{} {}",
+                            HMS::from_degrees(ra.to_degrees()).label(),
+                            DMS::from_degrees(dec.to_degrees()).label())));
+                    });
+                    let mut mode = camera.mode;
+                    let mut frame = camera.frame;
+                    ui.horizontal(|ui| {
+                        ui.add(egui::Label::new("Camera:").text_color(egui::Color32::from_rgb(110, 255, 110)));
+                        ui.radio_value(&mut frame, CameraFrame::Equatorial, "Equatorial");
+                        ui.radio_value(&mut frame, CameraFrame::Horizon, "Horizon");
+                        ui.radio_value(&mut mode, CameraMode::KeepLevel, "Level");
+                        let free = match (bindings.hint(Action::RollLeft), bindings.hint(Action::RollRight)) {
+                            (Some(left), Some(right)) => format!("Free ({}/{} roll)", left, right),
+                            _ => "Free".to_string(),
+                        };
+                        ui.radio_value(&mut mode, CameraMode::Free, free);
                     });
+                    if frame != camera.frame {
+                        let rotation = match frame {
+                            CameraFrame::Equatorial => Quaternion::new(1., 0., 0., 0.),
+                            CameraFrame::Horizon => horizon_rotation(&observer, clock.jd),
+                        };
+                        camera.set_frame(frame, rotation);
+                    }
+                    if mode != camera.mode {
+                        camera.set_mode(mode);
+                    }
+                    let mut kind = sky_projection.0;
+                    ui.horizontal(|ui| {
+                        ui.add(egui::Label::new("Projection:").text_color(egui::Color32::from_rgb(110, 255, 110)));
+                        ui.radio_value(&mut kind, ProjectionKind::Gnomonic, "Gnomonic");
+                        ui.radio_value(&mut kind, ProjectionKind::Stereographic, "Stereographic");
+                    });
+                    if kind != sky_projection.0 {
+                        sky_projection.0 = kind;
+                    }
                     ui.horizontal(|ui| {
                         ui.add(egui::Label::new("Stars:").text_color(egui::Color32::from_rgb(110, 255, 110)));
-                        ui.add(egui::Label::new(format!("{}", query_stars.iter_mut().count())));
+                        ui.add(egui::Label::new(format!("{}", query_stars.iter().count())));
                     });
                     fps.0.remove(0);
                     fps.0.push(1000./(time.delta().as_millis() as f32));
@@ -108,17 +471,219 @@ fn ui_infos(
                         ui.add(egui::Label::new("Framerate:").text_color(egui::Color32::from_rgb(110, 255, 110)));
                         ui.add(egui::Label::new(format!("{:.0}", fps.0.iter().sum::<f32>()/fps.0.len() as f32)));
                     });
+                    ui.horizontal(|ui| {
+                        ui.add(egui::Label::new("Star colors:").text_color(egui::Color32::from_rgb(110, 255, 110)));
+                        let mut value = saturation.0;
+                        ui.add(egui::Slider::new(&mut value, 0.0..=1.0));
+                        if value != saturation.0 {
+                            saturation.0 = value;
+                        }
+                    });
+                    let mut model = *brightness;
+                    ui.horizontal(|ui| {
+                        ui.add(egui::Label::new("Limiting magnitude:").text_color(egui::Color32::from_rgb(110, 255, 110)));
+                        ui.add(egui::Label::new(format!("{:.1}", model.limit_at(fov.0))));
+                        ui.add(egui::Slider::new(&mut model.limiting_mag, 1.0..=MAX_STAR_MAG));
+                    });
+                    ui.horizontal(|ui| {
+                        ui.add(egui::Label::new("Contrast:").text_color(egui::Color32::from_rgb(110, 255, 110)));
+                        ui.add(egui::Slider::new(&mut model.contrast, 0.1..=1.0));
+                    });
+                    ui.horizontal(|ui| {
+                        ui.add(egui::Label::new("Star scale:").text_color(egui::Color32::from_rgb(110, 255, 110)));
+                        ui.add(egui::Slider::new(&mut model.min_radius, 0.5..=3.0));
+                    });
+                    if model != *brightness {
+                        *brightness = model;
+                    }
+                    let mut motion = *dynamics;
+                    ui.horizontal(|ui| {
+                        ui.add(egui::Label::new("Camera damping:").text_color(egui::Color32::from_rgb(110, 255, 110)));
+                        ui.add(egui::Slider::new(&mut motion.damping, 0.2..=10.0).suffix(" /s"));
+                    });
+                    ui.horizontal(|ui| {
+                        ui.add(egui::Label::new("Drag throw:").text_color(egui::Color32::from_rgb(110, 255, 110)));
+                        ui.add(egui::Slider::new(&mut motion.drag_sensitivity, 0.0..=0.05));
+                    });
+                    ui.horizontal(|ui| {
+                        ui.add(egui::Label::new("Wheel zoom:").text_color(egui::Color32::from_rgb(110, 255, 110)));
+                        ui.add(egui::Slider::new(&mut motion.scroll_sensitivity, 0.05..=1.0));
+                    });
+                    if motion != *dynamics {
+                        *dynamics = motion;
+                    }
                 });
             });
         }
         AppState::Pause => {
             // TODO: play pause screen music
         }
+        // ui_infos only runs in Stars
+        AppState::Tour => {}
     }
 
 }
 
 
+/// Toggle reference frame grids, equators, poles and the local meridian
+fn ui_layers(
+    egui_context: ResMut<EguiContext>, 
+    mut layers: ResMut<GridLayers>,
+    mut show_milky_way: ResMut<ShowMilkyWay>,
+    mut show_deep_sky: ResMut<ShowDeepSky>,
+    mut show_grids: ResMut<ShowGrids>,
+    mut show_labels: ResMut<ShowLabels>,
+) {
+    let mut milky_way = show_milky_way.0;
+    let mut deep_sky = show_deep_sky.0;
+    let mut grids = show_grids.0;
+    let mut labels = show_labels.0;
+    let mut toggles = layers.0.clone();
+    egui::Window::new("Layers")
+    .resizable(false)
+    .default_pos(egui::pos2(10., 200.))
+    .show(egui_context.ctx(), |ui| {
+        egui::Grid::new("layers_grid").show(ui, |ui| {
+            ui.label("");
+            ui.label("Grid");
+            ui.label("Equator");
+            ui.label("Poles");
+            ui.label("Meridian");
+            ui.end_row();
+            for frame in GridFrame::all() {
+                if let Some(t) = toggles.get_mut(&frame) {
+                    let [r, g, b, _] = frame_color(frame).as_rgba_f32();
+                    let color = egui::Color32::from_rgb((r * 255.) as u8, (g * 255.) as u8, (b * 255.) as u8);
+                    ui.add(egui::Label::new(frame.name()).text_color(color));
+                    ui.checkbox(&mut t.grid, "");
+                    ui.checkbox(&mut t.equator, "");
+                    ui.checkbox(&mut t.poles, "");
+                    if frame == GridFrame::Horizon {
+                        ui.checkbox(&mut t.meridian, "");
+                    }
+                    ui.end_row();
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut grids, "Grids");
+            ui.checkbox(&mut labels, "Labels");
+            ui.checkbox(&mut milky_way, "Milky Way");
+            ui.checkbox(&mut deep_sky, "Deep sky");
+        });
+    });
+    if grids != show_grids.0 {
+        show_grids.0 = grids;
+    }
+    if labels != show_labels.0 {
+        show_labels.0 = labels;
+    }
+    if toggles != layers.0 {
+        layers.0 = toggles;
+    }
+    if milky_way != show_milky_way.0 {
+        show_milky_way.0 = milky_way;
+    }
+    if deep_sky != show_deep_sky.0 {
+        show_deep_sky.0 = deep_sky;
+    }
+}
+
+/// Edit the observer location and sky conditions, show the local sidereal time
+fn ui_observer(
+    egui_context: ResMut<EguiContext>, 
+    mut observer: ResMut<Observer>,
+    mut conditions: ResMut<SkyConditions>,
+    clock: Res<SimulationClock>,
+) {
+    let mut edited = observer.clone();
+    let mut edited_conditions = *conditions;
+    let sky = SkyState::at(&observer, clock.jd);
+    egui::Window::new("Observer")
+    .resizable(false)
+    .default_pos(egui::pos2(10., 400.))
+    .show(egui_context.ctx(), |ui| {
+        egui::ComboBox::from_label("Site")
+        .selected_text(edited.name.clone())
+        .show_ui(ui, |ui| {
+            for site in Observer::sites() {
+                let name = site.name.clone();
+                ui.selectable_value(&mut edited, site, name);
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.add(egui::Label::new("Latitude:").text_color(egui::Color32::from_rgb(110, 255, 110)));
+            ui.add(egui::DragValue::new(&mut edited.latitude).speed(0.1).clamp_range(-90.0..=90.0).suffix("°"));
+        });
+        ui.horizontal(|ui| {
+            ui.add(egui::Label::new("Longitude:").text_color(egui::Color32::from_rgb(110, 255, 110)));
+            ui.add(egui::DragValue::new(&mut edited.longitude).speed(0.1).clamp_range(-180.0..=180.0).suffix("°"));
+        });
+        let lst = HMS::from_degrees(observer.local_sidereal_time(clock.jd).to_degrees());
+        ui.horizontal(|ui| {
+            ui.add(egui::Label::new("Sidereal time:").text_color(egui::Color32::from_rgb(110, 255, 110)));
+            ui.add(egui::Label::new(format!("{:02}h{:02}m{:02.0}s", lst.hours, lst.minutes, lst.seconds.floor())));
+        });
+        ui.horizontal(|ui| {
+            ui.add(egui::Label::new("Sun altitude:").text_color(egui::Color32::from_rgb(110, 255, 110)));
+            ui.add(egui::Label::new(format!("{:+.1}°", sky.sun_altitude.to_degrees())));
+        });
+        ui.horizontal(|ui| {
+            ui.add(egui::Label::new("Moon:").text_color(egui::Color32::from_rgb(110, 255, 110)));
+            ui.add(egui::Label::new(format!("{:.0}% lit, {:+.1}°", sky.moon_illumination * 100., sky.moon_altitude.to_degrees())));
+        });
+        let mut bortle = edited_conditions.bortle();
+        ui.horizontal(|ui| {
+            ui.add(egui::Label::new("Bortle class:").text_color(egui::Color32::from_rgb(110, 255, 110)));
+            ui.add(egui::Slider::new(&mut bortle, 1..=9));
+        });
+        if bortle != conditions.bortle() {
+            edited_conditions = SkyConditions::from_bortle(bortle);
+        }
+        ui.horizontal(|ui| {
+            ui.add(egui::Label::new("Sky quality:").text_color(egui::Color32::from_rgb(110, 255, 110)));
+            ui.add(egui::DragValue::new(&mut edited_conditions.sky_quality).speed(0.05).clamp_range(16.0..=22.0).suffix(" mag/arcsec²"));
+        });
+        ui.horizontal(|ui| {
+            ui.add(egui::Label::new("Naked eye limit:").text_color(egui::Color32::from_rgb(110, 255, 110)));
+            ui.add(egui::Label::new(format!("{:.1}", edited_conditions.limiting_magnitude())));
+        });
+    });
+    // a new site brings its usual sky with it
+    if edited.bortle != observer.bortle || edited.name != observer.name {
+        edited_conditions = SkyConditions::from_bortle(edited.bortle);
+    }
+    if edited != *observer {
+        *observer = edited;
+    }
+    if edited_conditions != *conditions {
+        *conditions = edited_conditions;
+    }
+}
+
+/// Apply light pollution to the sky background and, when it changes, cap the
+/// limiting magnitude of the star brightness model
+fn apply_sky_conditions(
+    conditions: Res<SkyConditions>,
+    mut model: ResMut<BrightnessModel>,
+    mut clear_color: ResMut<ClearColor>,
+) {
+    let [r, g, b] = conditions.background_color();
+    let color = Color::rgb_linear(r, g, b);
+    if clear_color.0 != color {
+        clear_color.0 = color;
+    }
+    if conditions.is_changed() {
+        // stars are drawn with limiting_mag.min(sky_limit).min(MAX_STAR_MAG)
+        model.sky_limit = conditions.limiting_magnitude().min(MAX_STAR_MAG);
+    }
+}
+
+/// Give the menus their black background back
+fn reset_clear_color(mut clear_color: ResMut<ClearColor>) {
+    clear_color.0 = Color::BLACK;
+}
+
 fn cleanup_system<T: Component>(
     mut commands: Commands,
     q: Query<Entity, With<T>>,
@@ -129,46 +694,909 @@ fn cleanup_system<T: Component>(
 }
 
 pub fn pause(
+    bindings: Res<Bindings>,
     keyboard_input: Res<Input<KeyCode>>,
+    mouse_input: Res<Input<MouseButton>>,
+    gamepad_input: Res<Input<GamepadButton>>,
     mut app_state: ResMut<State<AppState>>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::Escape) {
+    let input = ActionInput{keys: &keyboard_input, mouse: &mouse_input, gamepad: &gamepad_input};
+    if bindings.just_pressed(Action::Pause, &input) {
         app_state.set(AppState::Pause).unwrap();
     }
 }
 
-/// Initialize sprites
-fn setup_sprites(
+/// Saved views of the bookmarks file, none when it is missing or invalid
+fn load_bookmarks() -> Bookmarks {
+    if !std::path::Path::new(BOOKMARKS_PATH).exists() {
+        return Bookmarks::default();
+    }
+    Bookmarks::load(BOOKMARKS_PATH).unwrap_or_else(|e| {
+        warn!("could not read saved views from {} ({})", BOOKMARKS_PATH, e);
+        Bookmarks::default()
+    })
+}
+
+/// Load the star catalogue, falling back to the legacy CSV export
+fn load_catalogue() -> Result<Catalogue, Box<dyn error::Error>> {
+    match Catalogue::open(CATALOGUE_PATH) {
+        Ok(catalogue) => Ok(catalogue),
+        Err(e) => {
+            warn!("could not open {} ({}), run the stargazer-db binary to generate it", CATALOGUE_PATH, e);
+            legacy::read_csv(File::open(LEGACY_CATALOGUE_PATH)?)
+        }
+    }
+}
+
+/// Upload every catalogue star to the GPU and keep picking data in the ECS
+fn setup_star_field(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StarFieldMaterial>>,
+    pipeline: Res<StarFieldPipeline>,
 ){
-    let sprite_handle = materials.add(asset_server.load("images/star.png").into());
-    let file_path = "assets/data/stars.csv";
-    let file = File::open(file_path).unwrap();
-    let mut rdr = csv::ReaderBuilder::new()
-        .has_headers(false)
-        .from_reader(file);
-    for result in rdr.records() {
-        let record = result.unwrap();
-        let mag = record.get(3).unwrap().parse::<f32>().unwrap();
+    let catalogue = match load_catalogue() {
+        Ok(catalogue) => catalogue,
+        Err(e) => {
+            error!("could not load star catalogue: {}", e);
+            return;
+        }
+    };
+    let mut vertices = Vec::with_capacity(catalogue.len());
+    for star in catalogue.iter() {
         let p = Polar{
-            theta: record.get(1).unwrap().parse::<f32>().unwrap(), 
-            phi: record.get(2).unwrap().parse::<f32>().unwrap(), 
+            theta: star.theta(), 
+            phi: star.phi(), 
             radius: 1.}.to_cart();
-        commands.spawn_bundle(SpriteBundle {
-            material: sprite_handle.clone(),
-            transform: Transform {
-                translation: Vec3::new(0., 0., 0.),
-                rotation: Quat::from_rotation_z(0.),
-                scale: Vec3::splat(1.),
-            },
-            sprite: Sprite::new(Vec2::splat(f32::max(5. - mag, 1.))),
-            ..Default::default()
-        }).insert(Star).insert(Position3D(p));
+        let [r, g, b] = bv_to_rgb(star.color_index, 1.);
+        vertices.push(StarVertex{position: p, mag: star.mag, color: Color::rgb(r, g, b)});
+        commands.spawn()
+        .insert(Star)
+        .insert(StarId(star.id))
+        .insert(StarMagnitude(star.mag))
+        .insert(StarColor(Color::rgb(r, g, b)))
+        .insert(Position3D(p));
+    }
+    let material = materials.add(StarFieldMaterial {
+        view_proj: Mat4::IDENTITY,
+        photometry: Vec4::ZERO,
+        viewport: Vec4::ZERO,
+        texture: asset_server.load("images/star.png"),
+    });
+    spawn_star_field(&mut commands, &pipeline, meshes.add(star_field_mesh(&vertices)), material);
+    // kept around to look objects up by name
+    commands.insert_resource(catalogue);
+}
+
+/// Feed camera, brightness model and color settings to the star field shader
+fn update_star_field(
+    fov: Res<Fov>,
+    projection: Res<ViewProjection>,
+    model: Res<BrightnessModel>,
+    saturation: Res<StarColorSaturation>,
+    wd: Res<WindowDescriptor>,
+    query: Query<&Handle<StarFieldMaterial>, With<StarField>>,
+    mut materials: ResMut<Assets<StarFieldMaterial>>,
+){
+    let view_proj = to_mat4(projection.matrix);
+
+    for handle in query.iter() {
+        if let Some(material) = materials.get_mut(handle) {
+            material.view_proj = view_proj;
+            material.photometry = Vec4::new(model.limit_at(fov.0), model.contrast, model.min_radius, model.max_radius);
+            material.viewport = Vec4::new(wd.width, wd.height, saturation.0, 0.);
+        }
     }
 }
 
+/// Spawn the Milky Way on the celestial sphere, procedural until the
+/// panorama is loaded
+fn setup_milky_way(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut textures: ResMut<Assets<Texture>>,
+    mut materials: ResMut<Assets<MilkyWayMaterial>>,
+    mut pending: ResMut<PendingMilkyWay>,
+    pipeline: Res<MilkyWayPipeline>,
+    clock: Res<SimulationClock>,
+){
+    let mesh = milky_way_mesh(Frame::Galactic.to_equatorial_matrix(clock.jd), 72, 36);
+    let material = materials.add(MilkyWayMaterial {
+        view_proj: Mat4::IDENTITY,
+        brightness: 0.,
+        texture: textures.add(procedural_texture(512, 256)),
+    });
+    pending.0 = Some(asset_server.load(MILKY_WAY_TEXTURE));
+    spawn_milky_way(&mut commands, &pipeline, meshes.add(mesh), material);
+}
+
+/// Swap the panorama in once loaded, or warn and keep the procedural Milky
+/// Way when it cannot be
+fn load_milky_way(
+    asset_server: Res<AssetServer>,
+    mut pending: ResMut<PendingMilkyWay>,
+    query: Query<&Handle<MilkyWayMaterial>, With<MilkyWay>>,
+    mut materials: ResMut<Assets<MilkyWayMaterial>>,
+){
+    let texture = match &pending.0 {
+        Some(texture) => texture.clone(),
+        None => return,
+    };
+    match asset_server.get_load_state(&texture) {
+        LoadState::Loaded => {
+            for handle in query.iter() {
+                if let Some(material) = materials.get_mut(handle) {
+                    material.texture = texture.clone();
+                }
+            }
+        }
+        LoadState::Failed => warn!("Could not load assets/{}, drawing a procedural Milky Way instead", MILKY_WAY_TEXTURE),
+        _ => return,
+    }
+    pending.0 = None;
+}
+
+/// Feed the camera and the darkness of the sky to the Milky Way shader
+fn update_milky_way(
+    projection: Res<ViewProjection>,
+    show: Res<ShowMilkyWay>,
+    conditions: Res<SkyConditions>,
+    observer: Res<Observer>,
+    clock: Res<SimulationClock>,
+    mut query: Query<(&Handle<MilkyWayMaterial>, &mut Visible), With<MilkyWay>>,
+    mut materials: ResMut<Assets<MilkyWayMaterial>>,
+){
+    let brightness = conditions.diffuse_visibility(&SkyState::at(&observer, clock.jd));
+    let view_proj = to_mat4(projection.matrix);
+    for (handle, mut visible) in query.iter_mut() {
+        let is_visible = show.0 && brightness > 0.;
+        if visible.is_visible != is_visible {
+            visible.is_visible = is_visible;
+        }
+        if let Some(material) = materials.get_mut(handle) {
+            material.view_proj = view_proj;
+            material.brightness = brightness;
+        }
+    }
+}
+
+/// Spawn the outlines of the deep sky objects, hidden until faded in
+fn setup_deep_sky(
+    mut commands: Commands,
+){
+    let objects = match File::open(DEEP_SKY_PATH) {
+        Ok(file) => read_deep_sky(file),
+        Err(e) => Err(e.into()),
+    };
+    let objects = match objects {
+        Ok(objects) => objects,
+        Err(e) => {
+            warn!("Could not read {}: {}", DEEP_SKY_PATH, e);
+            return;
+        }
+    };
+    for object in objects {
+        // small objects keep an outline that can be seen
+        let radius = (object.size / 2.).max(DEEP_SKY_MIN_RADIUS);
+        let points: Vec<Vector4<f32>> = overlay::circle(object.ra, object.dec, radius, 32).into_iter().map(to_world).collect();
+        commands.spawn()
+        .insert(Path3D(points.clone()))
+        .insert(Path2D{data: points, kind: Path2DKind::Overlay, marker: object.name.clone()})
+        .insert(PathStyle::solid(with_alpha(DEEP_SKY_COLOR, 0.), 1.))
+        .insert(DeepSkyOutline{surface_brightness: object.surface_brightness()});
+    }
+}
+
+/// Fade deep sky outlines with light pollution, twilight and moonlight
+fn fade_deep_sky(
+    show: Res<ShowDeepSky>,
+    conditions: Res<SkyConditions>,
+    observer: Res<Observer>,
+    clock: Res<SimulationClock>,
+    mut query: Query<(&DeepSkyOutline, &mut PathStyle)>,
+){
+    let sky = SkyState::at(&observer, clock.jd);
+    for (outline, mut style) in query.iter_mut() {
+        let visibility = match show.0 {
+            true => conditions.object_visibility(&sky, outline.surface_brightness),
+            false => 0.,
+        };
+        // in steps, so that the line mesh is not rebuilt every frame as the Sun sets
+        let color = with_alpha(DEEP_SKY_COLOR, 0.8 * (visibility * 20.).round() / 20.);
+        if style.color != color {
+            style.color = color;
+        }
+    }
+}
+
+/// Spawn the eyepiece and sensor outlines, empty until shown
+fn setup_fov_overlays(
+    mut commands: Commands,
+){
+    let overlays = [
+        (FovOverlay::Eyepiece, EYEPIECE_COLOR),
+        (FovOverlay::Sensor, SENSOR_COLOR),
+    ];
+    for (overlay, color) in overlays.iter() {
+        commands.spawn()
+        .insert(Path3D(vec![]))
+        .insert(Path2D{data: vec![], kind: Path2DKind::Overlay, marker: String::new()})
+        .insert(PathStyle::solid(*color, 1.5))
+        .insert(*overlay);
+    }
+}
+
+/// J2000 right ascension and declination (radians) at the centre of the view
+fn view_center(camera: &SkyCamera) -> (f64, f64) {
+    // scene axes are equatorial x, z, y
+    let forward = camera.forward();
+    to_spherical(Vector3::new(forward.x, forward.z, forward.y))
+}
+
+/// Rebuild the eyepiece circle and sensor rectangle around their centre
+fn update_fov_overlays(
+    camera: Res<SkyCamera>,
+    equipment: Res<Equipment>,
+    settings: Res<OverlaySettings>,
+    selection: Res<Selection>,
+    mut query: Query<(&FovOverlay, &mut Path3D)>,
+){
+    let (ra, dec) = match (settings.center, &selection.0) {
+        (OverlayCenter::Selection, Some(object)) => (object.ra, object.dec),
+        _ => view_center(&camera),
+    };
+    for (overlay, mut path) in query.iter_mut() {
+        let points = match overlay {
+            FovOverlay::Eyepiece if settings.show_eyepiece => {
+                overlay::circle(ra, dec, equipment.eyepiece_field() / 2., 128)
+            }
+            FovOverlay::Sensor if settings.show_sensor => {
+                let (width, height) = equipment.sensor_field();
+                overlay::rectangle(ra, dec, width, height, settings.rotation.to_radians(), 16)
+            }
+            _ => vec![],
+        };
+        let points: Vec<Vector4<f32>> = points.into_iter().map(to_world).collect();
+        // unchanged outlines leave the line mesh as it is
+        if path.0 != points {
+            path.0 = points;
+        }
+    }
+}
+
+/// Pick the optical train, toggle its overlays and find objects to centre on
+fn ui_equipment(
+    egui_context: ResMut<EguiContext>,
+    mut equipment: ResMut<Equipment>,
+    mut settings: ResMut<OverlaySettings>,
+    mut selection: ResMut<Selection>,
+    catalogue: Option<Res<Catalogue>>,
+    mut search: Local<String>,
+    mut search_focus: ResMut<SearchFocus>,
+) {
+    let mut edited = equipment.clone();
+    let mut edited_settings = *settings;
+    let mut found = None;
+    egui::Window::new("Equipment")
+    .resizable(false)
+    .default_pos(egui::pos2(10., 600.))
+    .show(egui_context.ctx(), |ui| {
+        let telescopes: Vec<String> = edited.telescopes.iter().map(|t| t.name.clone()).collect();
+        let correctors: Vec<String> = edited.correctors.iter().map(|c| c.name.clone()).collect();
+        let eyepieces: Vec<String> = edited.eyepieces.iter().map(|e| e.name.clone()).collect();
+        let sensors: Vec<String> = edited.sensors.iter().map(|s| s.name.clone()).collect();
+        equipment_combo(ui, "Telescope", &telescopes, &mut edited.telescope);
+        equipment_combo(ui, "Barlow/reducer", &correctors, &mut edited.corrector);
+        equipment_combo(ui, "Eyepiece", &eyepieces, &mut edited.eyepiece);
+        equipment_combo(ui, "Sensor", &sensors, &mut edited.sensor);
+        let telescope = edited.telescope().clone();
+        let corrector = edited.corrector().clone();
+        ui.horizontal(|ui| {
+            ui.add(egui::Label::new("Focal length:").text_color(egui::Color32::from_rgb(110, 255, 110)));
+            ui.add(egui::Label::new(format!("{:.0} mm, f/{:.1}",
+                telescope.effective_focal_length(&corrector),
+                telescope.focal_ratio() * corrector.factor)));
+        });
+        let eyepiece = edited.eyepiece();
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut edited_settings.show_eyepiece, "");
+            ui.add(egui::Label::new("Eyepiece:").text_color(egui::Color32::from_rgb(110, 255, 110)));
+            ui.add(egui::Label::new(format!("{:.0}x, {:.2}° field, {:.1} mm exit pupil",
+                eyepiece.magnification(&telescope, &corrector),
+                edited.eyepiece_field().to_degrees(),
+                eyepiece.exit_pupil(&telescope, &corrector))));
+        });
+        let (width, height) = edited.sensor_field();
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut edited_settings.show_sensor, "");
+            ui.add(egui::Label::new("Sensor:").text_color(egui::Color32::from_rgb(110, 255, 110)));
+            ui.add(egui::Label::new(format!("{:.2}° x {:.2}°, {:.2}\"/px",
+                width.to_degrees(),
+                height.to_degrees(),
+                edited.sensor().image_scale(&telescope, &corrector))));
+        });
+        ui.horizontal(|ui| {
+            ui.add(egui::Label::new("Rotation:").text_color(egui::Color32::from_rgb(110, 255, 110)));
+            ui.add(egui::DragValue::new(&mut edited_settings.rotation).speed(1.).clamp_range(-180.0..=180.0).suffix("°"));
+        });
+        ui.horizontal(|ui| {
+            ui.add(egui::Label::new("Centre:").text_color(egui::Color32::from_rgb(110, 255, 110)));
+            ui.radio_value(&mut edited_settings.center, OverlayCenter::View, "View");
+            let selected = match &selection.0 {
+                Some(object) => object.name.clone(),
+                None => "Selection".to_string(),
+            };
+            ui.radio_value(&mut edited_settings.center, OverlayCenter::Selection, selected);
+        });
+        ui.horizontal(|ui| {
+            ui.add(egui::Label::new("Find:").text_color(egui::Color32::from_rgb(110, 255, 110)));
+            let response = ui.text_edit_singleline(&mut *search);
+            if search_focus.0 {
+                response.request_focus();
+                search_focus.0 = false;
+            }
+            if response.lost_focus() && !search.is_empty() {
+                if let Some(catalogue) = &catalogue {
+                    found = find_star(catalogue, &search);
+                }
+            }
+        });
+    });
+    if let Some(object) = found {
+        edited_settings.center = OverlayCenter::Selection;
+        selection.0 = Some(object);
+    }
+    if edited != *equipment {
+        *equipment = edited;
+    }
+    if edited_settings != *settings {
+        *settings = edited_settings;
+    }
+}
+
+/// Rebuild the finder reticle outlines, spawning path entities as needed
+fn update_reticle(
+    mut commands: Commands,
+    camera: Res<SkyCamera>,
+    settings: Res<ReticleSettings>,
+    mut query: Query<(&ReticlePath, &mut Path3D)>,
+){
+    let outlines = match settings.reticles.get(settings.selected) {
+        Some(reticle) if settings.enabled => {
+            let (ra, dec) = match settings.pin {
+                ReticlePin::Screen => view_center(&camera),
+                ReticlePin::Sky{ra, dec} => (ra, dec),
+            };
+            reticle.outlines(ra, dec)
+        }
+        _ => vec![],
+    };
+    let mut count = 0;
+    for (index, mut path) in query.iter_mut() {
+        let points: Vec<Vector4<f32>> = match outlines.get(index.0) {
+            Some(points) => points.iter().map(|p| to_world(*p)).collect(),
+            None => vec![],
+        };
+        if path.0 != points {
+            path.0 = points;
+        }
+        count = count.max(index.0 + 1);
+    }
+    for (i, points) in outlines.iter().enumerate().skip(count) {
+        let vertices: Vec<Vector4<f32>> = points.iter().map(|p| to_world(*p)).collect();
+        commands.spawn()
+        .insert(Path3D(vertices.clone()))
+        .insert(Path2D{data: vec![], kind: Path2DKind::Overlay, marker: String::new()})
+        .insert(PathStyle::solid(Color::rgb(1., 0.25, 0.2), 1.5))
+        .insert(ReticlePath(i));
+    }
+}
+
+/// Choose and edit the finder reticle, and pin it on the screen or the sky
+fn ui_reticle(
+    egui_context: ResMut<EguiContext>,
+    mut settings: ResMut<ReticleSettings>,
+    camera: Res<SkyCamera>,
+    selection: Res<Selection>,
+    mut rings: Local<Option<String>>,
+) {
+    let mut edited = settings.clone();
+    egui::Window::new("Finder")
+    .resizable(false)
+    .default_pos(egui::pos2(1000., 200.))
+    .show(egui_context.ctx(), |ui| {
+        ui.checkbox(&mut edited.enabled, "Show reticle");
+        let names: Vec<String> = edited.reticles.iter().map(|r| r.name.clone()).collect();
+        equipment_combo(ui, "Reticle", &names, &mut edited.selected);
+        let reticle = &mut edited.reticles[edited.selected];
+        // ring diameters are edited as text, kept while typing
+        let text = rings.get_or_insert_with(|| ring_list(&reticle.rings));
+        ui.horizontal(|ui| {
+            ui.add(egui::Label::new("Rings:").text_color(egui::Color32::from_rgb(110, 255, 110)));
+            if ui.text_edit_singleline(text).changed() {
+                reticle.rings = text.split(',').filter_map(|d| d.trim().parse().ok()).collect();
+            }
+            ui.label("°");
+        });
+        ui.checkbox(&mut reticle.crosshair, "Crosshair");
+        ui.horizontal(|ui| {
+            ui.add(egui::Label::new("Pinned to:").text_color(egui::Color32::from_rgb(110, 255, 110)));
+            if ui.radio(edited.pin == ReticlePin::Screen, "Screen centre").clicked() {
+                edited.pin = ReticlePin::Screen;
+            }
+            if ui.radio(edited.pin != ReticlePin::Screen, "Sky").clicked() && edited.pin == ReticlePin::Screen {
+                let (ra, dec) = view_center(&camera);
+                edited.pin = ReticlePin::Sky{ra, dec};
+            }
+        });
+        ui.horizontal(|ui| {
+            if ui.button("Pin here").clicked() {
+                let (ra, dec) = view_center(&camera);
+                edited.pin = ReticlePin::Sky{ra, dec};
+            }
+            if let Some(object) = &selection.0 {
+                if ui.button(format!("Pin on {}", object.name)).clicked() {
+                    edited.pin = ReticlePin::Sky{ra: object.ra, dec: object.dec};
+                }
+            }
+        });
+    });
+    if edited.selected != settings.selected {
+        *rings = None;
+    }
+    if edited != *settings {
+        *settings = edited;
+    }
+}
+
+/// Ring diameters as comma separated text
+fn ring_list(rings: &[f64]) -> String {
+    rings.iter().map(|d| format!("{}", d)).collect::<Vec<String>>().join(", ")
+}
+
+/// Combo box choosing one item of an equipment list
+fn equipment_combo(ui: &mut egui::Ui, label: &str, names: &[String], selected: &mut usize) {
+    egui::ComboBox::from_label(label)
+    .selected_text(&names[*selected])
+    .show_ui(ui, |ui| {
+        for (i, name) in names.iter().enumerate() {
+            ui.selectable_value(selected, i, name.as_str());
+        }
+    });
+}
+
+/// Look a star up by proper name or designation, ignoring case
+fn find_star(catalogue: &Catalogue, name: &str) -> Option<SelectedObject> {
+    let (star, matched) = catalogue.find(name)?;
+    Some(SelectedObject{name: matched.to_string(), ra: star.ra as f64, dec: star.dec as f64})
+}
+
+/// Select the visible star closest to the cursor, or to the centre of the
+/// window without one
+fn pick_star(
+    bindings: Res<Bindings>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mouse_input: Res<Input<MouseButton>>,
+    gamepad_input: Res<Input<GamepadButton>>,
+    windows: Res<Windows>,
+    egui_context: Res<EguiContext>,
+    projection: Res<ViewProjection>,
+    catalogue: Option<Res<Catalogue>>,
+    model: Res<BrightnessModel>,
+    fov: Res<Fov>,
+    mut selection: ResMut<Selection>,
+){
+    let catalogue = match catalogue {
+        Some(catalogue) => catalogue,
+        None => return,
+    };
+    let input = ActionInput{keys: &keyboard_input, mouse: &mouse_input, gamepad: &gamepad_input};
+    if !bindings.just_pressed(Action::Pick, &input) || egui_context.ctx().wants_pointer_input() {
+        return;
+    }
+    let cursor = windows.get_primary()
+        .and_then(|window| window.cursor_position())
+        .unwrap_or(projection.viewport / 2.);
+    let direction = projection.screen_to_world(cursor);
+    // stars within a few pixels of the cursor, the closest wins
+    let max_angle = PICK_RADIUS / projection.pixels_per_radian();
+    let limit = model.limit_at(fov.0);
+    let picked = catalogue.iter()
+        .filter(|star| star.mag <= limit)
+        .map(|star| {
+            let position = to_world(to_cartesian(star.ra as f64, star.dec as f64)).truncate();
+            (star, position.angle(direction).0)
+        })
+        .filter(|(_, angle)| *angle < max_angle)
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+    if let Some((star, _)) = picked {
+        let name = catalogue.name(&star)
+            .or_else(|| catalogue.designation(&star))
+            .map(|name| name.to_string())
+            .unwrap_or_else(|| format!("HYG {}", star.id));
+        selection.0 = Some(SelectedObject{name, ra: star.ra as f64, dec: star.dec as f64});
+    }
+}
+
+/// Names of the layers shown, as saved in views
+fn shown_layers(layers: &GridLayers, grids: bool, labels: bool, milky_way: bool, deep_sky: bool) -> Vec<String> {
+    let mut shown = vec![];
+    for frame in GridFrame::all() {
+        if let Some(t) = layers.0.get(&frame) {
+            let parts = [(t.grid, "grid"), (t.equator, "equator"), (t.poles, "poles"), (t.meridian, "meridian")];
+            for (enabled, part) in parts.iter() {
+                if *enabled {
+                    shown.push(format!("{} {}", frame.name(), part));
+                }
+            }
+        }
+    }
+    let switches = [(grids, "Grids"), (labels, "Labels"), (milky_way, "Milky Way"), (deep_sky, "Deep sky")];
+    for (enabled, name) in switches.iter() {
+        if *enabled {
+            shown.push(name.to_string());
+        }
+    }
+    shown
+}
+
+/// Names of every layer, as used by saved views and tours
+fn layer_names() -> Vec<String> {
+    let mut names = vec![];
+    for frame in GridFrame::all() {
+        for part in ["grid", "equator", "poles", "meridian"].iter() {
+            names.push(format!("{} {}", frame.name(), part));
+        }
+    }
+    names.extend(["Grids", "Labels", "Milky Way", "Deep sky"].iter().map(|name| name.to_string()));
+    names
+}
+
+/// Visibility switch of a layer by name, `None` for unknown names
+fn layer_toggle<'a>(
+    name: &str,
+    layers: &'a mut GridLayers,
+    grids: &'a mut ShowGrids,
+    labels: &'a mut ShowLabels,
+    milky_way: &'a mut ShowMilkyWay,
+    deep_sky: &'a mut ShowDeepSky,
+) -> Option<&'a mut bool> {
+    match name {
+        "Grids" => return Some(&mut grids.0),
+        "Labels" => return Some(&mut labels.0),
+        "Milky Way" => return Some(&mut milky_way.0),
+        "Deep sky" => return Some(&mut deep_sky.0),
+        _ => {}
+    }
+    let (frame, part) = GridFrame::all().into_iter()
+        .find_map(|frame| name.strip_prefix(frame.name()).map(|part| (frame, part.trim())))?;
+    let toggles = layers.0.get_mut(&frame)?;
+    match part {
+        "grid" => Some(&mut toggles.grid),
+        "equator" => Some(&mut toggles.equator),
+        "poles" => Some(&mut toggles.poles),
+        "meridian" => Some(&mut toggles.meridian),
+        _ => None,
+    }
+}
+
+/// Carry out the sky commands of a guided tour: camera flights start from
+/// wherever the camera is, and several commands in a row share one flight
+fn apply_tour_commands(
+    mut tour_commands: EventReader<TourCommand>,
+    catalogue: Option<Res<Catalogue>>,
+    camera: Res<SkyCamera>,
+    fov: Res<Fov>,
+    mut transition: ResMut<ViewTransition>,
+    mut clock: ResMut<SimulationClock>,
+    mut observer: ResMut<Observer>,
+    mut conditions: ResMut<SkyConditions>,
+    mut layers: ResMut<GridLayers>,
+    mut show_grids: ResMut<ShowGrids>,
+    mut show_labels: ResMut<ShowLabels>,
+    mut show_milky_way: ResMut<ShowMilkyWay>,
+    mut show_deep_sky: ResMut<ShowDeepSky>,
+    mut selection: ResMut<Selection>,
+){
+    let (mut to, mut to_fov) = match &transition.0 {
+        Some(flight) => (flight.to, flight.to_fov),
+        None => (camera.orientation, fov.0),
+    };
+    let mut fly = false;
+    for command in tour_commands.iter() {
+        match command {
+            TourCommand::Goto(target) => {
+                let (ra, dec) = match target {
+                    TourTarget::Position{ra, dec} => (*ra, *dec),
+                    TourTarget::Object(name) => match catalogue.as_ref().and_then(|c| find_star(c, name)) {
+                        Some(object) => {
+                            let position = (object.ra, object.dec);
+                            selection.0 = Some(object);
+                            position
+                        }
+                        None => {
+                            warn!("tour: no star named {}", name);
+                            continue;
+                        }
+                    },
+                };
+                // earlier commands of the batch may have moved the observer or the clock
+                to = aim_camera(&camera, &observer, clock.jd, ra, dec);
+                fly = true;
+            }
+            TourCommand::Fov(new_fov) => {
+                to_fov = *new_fov;
+                fly = true;
+            }
+            TourCommand::Time(jd) => clock.jd = jd.unwrap_or_else(|| SimulationClock::now().jd),
+            TourCommand::Rate(rate) => clock.rate = *rate,
+            TourCommand::Observer(name) => {
+                match Observer::sites().into_iter().find(|site| site.name.to_lowercase() == name.to_lowercase()) {
+                    Some(site) => {
+                        *conditions = SkyConditions::from_bortle(site.bortle);
+                        *observer = site;
+                    }
+                    None => warn!("tour: no site named {}", name),
+                }
+            }
+            TourCommand::Layer(name, shown) => {
+                match layer_toggle(name, &mut layers, &mut show_grids, &mut show_labels, &mut show_milky_way, &mut show_deep_sky) {
+                    Some(toggle) => *toggle = *shown,
+                    None => warn!("tour: no layer named {}", name),
+                }
+            }
+            // handled by the tour itself
+            TourCommand::Caption(_) | TourCommand::Wait(_) => {}
+        }
+    }
+    if fly {
+        transition.0 = Some(CameraTransition::new(camera.orientation, to, fov.0, to_fov, VIEW_TRANSITION_DURATION));
+    }
+}
+
+/// Save, list and fly to named views
+fn ui_bookmarks(
+    egui_context: ResMut<EguiContext>,
+    mut bookmarks: ResMut<Bookmarks>,
+    mut camera: ResMut<SkyCamera>,
+    (fov, mut sky_projection): (Res<Fov>, ResMut<SkyProjection>),
+    mut observer: ResMut<Observer>,
+    mut conditions: ResMut<SkyConditions>,
+    mut clock: ResMut<SimulationClock>,
+    mut layers: ResMut<GridLayers>,
+    mut show_grids: ResMut<ShowGrids>,
+    mut show_labels: ResMut<ShowLabels>,
+    mut show_milky_way: ResMut<ShowMilkyWay>,
+    mut show_deep_sky: ResMut<ShowDeepSky>,
+    mut inertia: ResMut<CameraInertia>,
+    mut transition: ResMut<ViewTransition>,
+    mut name: Local<String>,
+    mut status: Local<String>,
+) {
+    let mut edited = bookmarks.clone();
+    let mut selected = None;
+    egui::Window::new("Views")
+    .resizable(false)
+    .default_pos(egui::pos2(1000., 400.))
+    .show(egui_context.ctx(), |ui| {
+        let mut removed = None;
+        for (i, bookmark) in edited.0.iter().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.button("Go").clicked() {
+                    selected = Some(bookmark.clone());
+                }
+                if ui.button("x").on_hover_text("Delete").clicked() {
+                    removed = Some(i);
+                }
+                ui.label(&bookmark.name);
+            });
+        }
+        if let Some(i) = removed {
+            edited.0.remove(i);
+        }
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut *name);
+            if ui.button("Save view").clicked() && !name.trim().is_empty() {
+                let bookmark = Bookmark {
+                    name: name.trim().to_string(),
+                    orientation: camera.orientation,
+                    frame: camera.frame,
+                    mode: camera.mode,
+                    fov: fov.0,
+                    projection: sky_projection.0,
+                    observer: observer.clone(),
+                    sky_quality: conditions.sky_quality,
+                    jd: clock.jd,
+                    rate: clock.rate,
+                    layers: shown_layers(&layers, show_grids.0, show_labels.0, show_milky_way.0, show_deep_sky.0),
+                };
+                // saving under an existing name replaces it
+                match edited.0.iter_mut().find(|b| b.name == bookmark.name) {
+                    Some(existing) => *existing = bookmark,
+                    None => edited.0.push(bookmark),
+                }
+                name.clear();
+            }
+        });
+        ui.label(&*status);
+    });
+    if edited != *bookmarks {
+        *status = match edited.save(BOOKMARKS_PATH) {
+            Ok(()) => format!("Saved to {}", BOOKMARKS_PATH),
+            Err(e) => format!("Could not save: {}", e),
+        };
+        *bookmarks = edited;
+    }
+    let bookmark = match selected {
+        Some(bookmark) => bookmark,
+        None => return,
+    };
+    // place and time first, so that the flight ends on the saved sky
+    *observer = bookmark.observer.clone();
+    conditions.sky_quality = bookmark.sky_quality;
+    clock.jd = bookmark.jd;
+    clock.rate = bookmark.rate;
+    for name in layer_names() {
+        if let Some(toggle) = layer_toggle(&name, &mut layers, &mut show_grids, &mut show_labels, &mut show_milky_way, &mut show_deep_sky) {
+            *toggle = bookmark.layers.contains(&name);
+        }
+    }
+    let frame_rotation = match bookmark.frame {
+        CameraFrame::Equatorial => Quaternion::new(1., 0., 0., 0.),
+        CameraFrame::Horizon => horizon_rotation(&observer, clock.jd),
+    };
+    camera.set_frame(bookmark.frame, frame_rotation);
+    camera.mode = bookmark.mode;
+    if sky_projection.0 != bookmark.projection {
+        sky_projection.0 = bookmark.projection;
+    }
+    inertia.stop();
+    transition.0 = Some(CameraTransition::new(
+        camera.orientation, bookmark.orientation, fov.0, bookmark.fov, VIEW_TRANSITION_DURATION));
+}
+
+/// Fly the camera to a saved view, grabbing the sky cancels the flight
+fn animate_view_transition(
+    time: Res<FrameTime>,
+    mouse_pressed: Res<MouseButtonPressed>,
+    mut transition: ResMut<ViewTransition>,
+    mut camera: ResMut<SkyCamera>,
+    mut fov: ResMut<Fov>,
+){
+    if transition.0.is_none() {
+        return;
+    }
+    if mouse_pressed.0 {
+        transition.0 = None;
+        return;
+    }
+    if let Some(flight) = transition.0.as_mut() {
+        let (orientation, new_fov) = flight.advance(time.delta_seconds());
+        camera.orientation = orientation;
+        if camera.mode == CameraMode::KeepLevel {
+            camera.level();
+        }
+        fov.0 = new_fov;
+        if flight.is_finished() {
+            transition.0 = None;
+        }
+    }
+}
+
+/// Key bindings window: add, remove and save bindings, listing conflicts
+fn ui_bindings(
+    egui_context: ResMut<EguiContext>,
+    mut bindings: ResMut<Bindings>,
+    mut editor: ResMut<BindingsEditor>,
+) {
+    let mut open = editor.open;
+    let mut edited = bindings.clone();
+    let conflicts = edited.conflicts();
+    egui::Window::new("Key bindings")
+    .open(&mut open)
+    .resizable(false)
+    .default_pos(egui::pos2(400., 100.))
+    .show(egui_context.ctx(), |ui| {
+        egui::ScrollArea::from_max_height(400.).show(ui, |ui| {
+            egui::Grid::new("bindings_grid").show(ui, |ui| {
+                for action in Action::ALL {
+                    ui.label(action.label());
+                    ui.horizontal(|ui| {
+                        for binding in edited.get(*action).to_vec() {
+                            let conflicting = conflicts.iter()
+                                .any(|c| c.binding == binding && c.actions.contains(action));
+                            let mut button = egui::Button::new(binding.name());
+                            if conflicting {
+                                button = button.text_color(egui::Color32::from_rgb(255, 90, 90));
+                            }
+                            // click a binding to remove it
+                            if ui.add(button).on_hover_text("Remove").clicked() {
+                                edited.remove(*action, binding);
+                            }
+                        }
+                        let adding = editor.capture == Some(*action);
+                        let text = match edited.hint(Action::CancelCapture) {
+                            _ if !adding => "+".to_string(),
+                            Some(cancel) => format!("press a key, {} cancels", cancel),
+                            None => "press a key...".to_string(),
+                        };
+                        if ui.selectable_label(adding, text).clicked() {
+                            editor.capture = if adding {None} else {Some(*action)};
+                        }
+                    });
+                    ui.end_row();
+                }
+            });
+        });
+        for conflict in conflicts.iter() {
+            let actions: Vec<&str> = conflict.actions.iter().map(|action| action.label()).collect();
+            ui.add(egui::Label::new(format!("{} is bound to {}", conflict.binding.name(), actions.join(", ")))
+                .text_color(egui::Color32::from_rgb(255, 90, 90)));
+        }
+        ui.horizontal(|ui| {
+            if ui.button("Save").clicked() {
+                editor.status = match edited.save(BINDINGS_PATH) {
+                    Ok(()) => format!("Saved to {}", BINDINGS_PATH),
+                    Err(e) => format!("Could not save: {}", e),
+                };
+            }
+            if ui.button("Defaults").clicked() {
+                edited = Bindings::default();
+            }
+            ui.label(&editor.status);
+        });
+    });
+    if !open {
+        editor.capture = None;
+    }
+    if open != editor.open {
+        editor.open = open;
+    }
+    if edited != *bindings {
+        *bindings = edited;
+    }
+}
+
+/// Bind the next key or button press to the action being edited, unless it
+/// is bound to cancelling. The press is consumed so that it does not trigger
+/// anything else.
+fn capture_binding(
+    mut editor: ResMut<BindingsEditor>,
+    mut bindings: ResMut<Bindings>,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    mut mouse_input: ResMut<Input<MouseButton>>,
+    mut gamepad_input: ResMut<Input<GamepadButton>>,
+){
+    let action = match editor.capture {
+        Some(action) => action,
+        None => return,
+    };
+    let input = ActionInput{keys: &keyboard_input, mouse: &mouse_input, gamepad: &gamepad_input};
+    let pressed = Binding::first_pressed(&input);
+    match pressed {
+        _ if bindings.just_pressed(Action::CancelCapture, &input) => {
+            bindings.consume(Action::CancelCapture, &mut keyboard_input, &mut mouse_input, &mut gamepad_input);
+        }
+        Some(binding) => {
+            bindings.add(action, binding);
+            match binding {
+                Binding::Key(code) => keyboard_input.reset(code),
+                Binding::Mouse(button) => mouse_input.reset(button),
+                Binding::Gamepad(_) => {
+                    let buttons: Vec<GamepadButton> = gamepad_input.get_just_pressed().copied().collect();
+                    for button in buttons {
+                        gamepad_input.reset(button);
+                    }
+                }
+            }
+        }
+        None => return,
+    }
+    editor.capture = None;
+}
+
 /// Instanciate 2D camera view
 fn setup_2d_camera(
     mut commands: Commands
@@ -199,349 +1627,776 @@ fn setup_constellations(
     commands.spawn()
     .insert(Path3D(path.clone()))
     .insert(Path2D{data: path.clone(), kind: Path2DKind::Constellation, marker: "None".to_string()})
+    .insert(PathStyle::solid(Color::RED, 1.5))
     .insert(Constellation);
 }
 
-/// Move sprites to star locations with projection
-fn draw_stars(
-    mut query: Query<(&mut Transform, &mut Position3D, With<Star>)>,
-    time: Res<Time>, 
-    fov: ResMut<Fov>,
-    camera: ResMut<Camera>, 
-    wd: ResMut<WindowDescriptor>,
-){
-    let w = wd.width;
-    let h = wd.height;
-    let t: f32 = time.seconds_since_startup() as f32/1.;
-    let aspect = wd.width / wd.height;
-    let proj_m: Matrix4<f32> = perspective(Rad(fov.0), aspect,0.1, 100.);
-    let translate_m: Matrix4<f32> = Matrix4::from_translation(Vector3::new(0., 0., 0.0));
-    let rotation_y_m: Matrix4<f32> = Matrix4::from_angle_y(Rad(camera.rot_y));
-    let rotation_x_m: Matrix4<f32> = Matrix4::from_angle_x(Rad(camera.rot_x));
-    let rotation_z_m: Matrix4<f32> = Matrix4::from_angle_z(Rad(0.));
+/// Display color of a reference frame's grid and labels
+fn frame_color(frame: GridFrame) -> Color {
+    match frame {
+        GridFrame::Sky(Frame::Equatorial) => Color::GRAY,
+        GridFrame::Sky(Frame::Ecliptic) => Color::rgb(1., 0.8, 0.3),
+        GridFrame::Sky(Frame::Galactic) => Color::rgb(0.8, 0.4, 1.),
+        GridFrame::Sky(Frame::Supergalactic) => Color::rgb(0.3, 0.8, 1.),
+        GridFrame::Horizon => Color::rgb(0.4, 0.9, 0.4),
+    }
+}
 
-    for (mut transform, position3d, _) in query.iter_mut() {
-        let translation = &mut transform.translation;
-        let vertex_proj = proj_m * translate_m * rotation_z_m * rotation_x_m * rotation_y_m * position3d.0;
-        let vertex_proj = vertex_proj / vertex_proj[3];
-        translation.x = vertex_proj[0]*w;
-        translation.y = vertex_proj[1]*h;
+fn with_alpha(color: Color, alpha: f32) -> Color {
+    let mut color = color;
+    color.set_a(alpha);
+    color
+}
+
+/// Initialize reference frame grids 3D paths
+fn setup_grids(
+    mut commands: Commands,
+    clock: Res<SimulationClock>,
+){
+    for frame in Frame::ALL.iter() {
+        setup_frame_grid(&mut commands, *frame, clock.jd);
     }
 }
 
-/// Initialize equatorial grid 3D paths
-fn setup_equatorial_grid(
-    mut commands: Commands
+/// Spawn the grid lines, equator and pole markers of a reference frame
+fn setup_frame_grid(
+    commands: &mut Commands,
+    frame: Frame,
+    jd: f64,
 ){
-    let phi_split = 20;
-    let theta_split = 20;
+    let phi_split = 24;
+    let theta_split = 18;
     let resolution = 200;
-    // phi circles
-    for split in 0..phi_split+1 {
-        let mut vertices = vec![];
-        let phi = split as f32 * 2. * std::f32::consts::PI / (phi_split as f32);
-        for m in 0..resolution+1 {
-            let theta = m as f32 * std::f32::consts::PI / (resolution as f32);
-            let p = Polar{theta:theta, phi: phi, radius: 1.};
-            let p_cart: Vector4<f32> = p.to_cart();
-            vertices.push(p_cart);
-        }
-        commands.spawn()
+    let pi = std::f32::consts::PI;
+    let color = frame_color(GridFrame::Sky(frame));
+    let lines_style = PathStyle::solid(with_alpha(color, 0.15), 1.);
+    let spawn = |commands: &mut Commands, points: Vec<(f32, f32)>, kind: Path2DKind, marker: String, style: PathStyle, part: GridPart| {
+        let vertices = frame_vertices(frame, &points, jd);
+        let mut entity = commands.spawn();
+        entity
         .insert(Path3D(vertices.clone()))
-        .insert(Path2D{
-            data: vertices.clone(), 
-            kind: Path2DKind::PhiCircle, 
-            marker: format!("{:.2}", phi*180./std::f32::consts::PI)})
+        .insert(Path2D{data: vertices, kind, marker})
+        .insert(style)
+        .insert(GridLayer{frame: GridFrame::Sky(frame), part})
         .insert(Grid);
+        if frame.is_of_date() {
+            entity.insert(FramePath(frame, points));
+        }
+    };
+    // phi circles
+    for split in 0..phi_split {
+        let phi = split as f32 * 2. * pi / (phi_split as f32);
+        let points = (0..resolution+1).map(|m| (m as f32 * pi / (resolution as f32), phi)).collect();
+        spawn(commands, points, Path2DKind::PhiCircle, longitude_label(frame, phi.to_degrees() as f64), lines_style, GridPart::Lines);
     }
-    // theta circles
-    for split in 0..theta_split+1 {
-        let mut vertices = vec![];
-        let theta = split as f32 * std::f32::consts::PI / (theta_split as f32);
-        for m in 0..resolution+1 {
-            let phi = m as f32 * 2. * std::f32::consts::PI / (resolution as f32);
-            let p = Polar{theta:theta, phi: phi, radius: 1.};
-            let p_cart: Vector4<f32> = p.to_cart();
-            vertices.push(p_cart);
+    // theta circles, without the poles and the equator drawn below
+    for split in 1..theta_split {
+        if split * 2 == theta_split {
+            continue;
         }
+        let theta = split as f32 * pi / (theta_split as f32);
+        let points = (0..resolution+1).map(|m| (theta, m as f32 * 2. * pi / (resolution as f32))).collect();
+        spawn(commands, points, Path2DKind::ThetaCircle, latitude_label(frame, 90. - theta.to_degrees() as f64), lines_style, GridPart::Lines);
+    }
+    // equator
+    let points = (0..resolution+1).map(|m| (pi / 2., m as f32 * 2. * pi / (resolution as f32))).collect();
+    spawn(commands, points, Path2DKind::Equator, frame.name().to_string(), PathStyle::solid(with_alpha(color, 0.6), 1.5), GridPart::Equator);
+    // poles, as small circles around them
+    let (north, south) = frame.pole_labels();
+    let pole_radius = 1.5_f32.to_radians();
+    for (theta, label) in [(pole_radius, north), (pi - pole_radius, south)].iter() {
+        let points = (0..33).map(|m| (*theta, m as f32 * 2. * pi / 32.)).collect();
+        spawn(commands, points, Path2DKind::Pole, label.to_string(), PathStyle::solid(with_alpha(color, 0.8), 1.5), GridPart::Poles);
+    }
+}
+
+/// Scene positions of frame spherical coordinates (theta, phi) at a julian date
+fn frame_vertices(frame: Frame, points: &[(f32, f32)], jd: f64) -> Vec<Vector4<f32>> {
+    let to_equatorial = frame.to_equatorial_matrix(jd);
+    points.iter()
+        .map(|(theta, phi)| {
+            let lat = std::f64::consts::FRAC_PI_2 - *theta as f64;
+            to_world(to_equatorial * to_cartesian(*phi as f64, lat))
+        })
+        .collect()
+}
+
+/// Reproject the grids of frames of date once the clock has moved
+/// FRAME_OF_DATE_TOLERANCE days away from the date they were drawn for
+fn update_frame_paths(
+    clock: Res<SimulationClock>,
+    mut drawn_jd: Local<Option<f64>>,
+    mut query: Query<(&FramePath, &mut Path3D)>,
+){
+    if let Some(jd) = *drawn_jd {
+        if (clock.jd - jd).abs() < FRAME_OF_DATE_TOLERANCE {
+            return;
+        }
+    }
+    *drawn_jd = Some(clock.jd);
+    for (path, mut path3d) in query.iter_mut() {
+        path3d.0 = frame_vertices(path.0, &path.1, clock.jd);
+    }
+}
+
+/// Grid label of a longitude in degrees, right ascension is shown in hours
+fn longitude_label(frame: Frame, degrees: f64) -> String {
+    match frame {
+        Frame::Equatorial => HMS::from_degrees(degrees).label(),
+        _ => format!("{:.0}°", degrees.round().rem_euclid(360.)),
+    }
+}
+
+/// Grid label of a latitude in degrees, declination is shown in DMS
+fn latitude_label(frame: Frame, degrees: f64) -> String {
+    match frame {
+        Frame::Equatorial => DMS::from_degrees(degrees).label(),
+        _ => format!("{:+.0}°", degrees),
+    }
+}
+
+/// Advance the simulated time
+fn advance_clock(
+    time: Res<FrameTime>,
+    mut clock: ResMut<SimulationClock>,
+){
+    clock.advance(time.delta_seconds_f64());
+}
+
+/// Initialize the alt-azimuth grid, horizon, meridian, zenith and nadir
+fn setup_horizon_grid(
+    mut commands: Commands,
+    clock: Res<SimulationClock>,
+    observer: Res<Observer>,
+){
+    let az_split = 24;
+    let alt_split = 12;
+    let resolution = 200;
+    let pi = std::f64::consts::PI;
+    let color = frame_color(GridFrame::Horizon);
+    let lines_style = PathStyle::solid(with_alpha(color, 0.2), 1.);
+    let spawn = |commands: &mut Commands, points: Vec<(f64, f64)>, kind: Path2DKind, marker: String, style: PathStyle, part: GridPart| {
+        let vertices = horizon_vertices(&points, &observer, clock.jd);
         commands.spawn()
         .insert(Path3D(vertices.clone()))
-        .insert(Path2D{
-            data: vertices.clone(), 
-            kind: Path2DKind::ThetaCircle, 
-            marker: format!("{:.2}", theta*180./std::f32::consts::PI)})
+        .insert(Path2D{data: vertices, kind, marker})
+        .insert(HorizonPath(points))
+        .insert(style)
+        .insert(GridLayer{frame: GridFrame::Horizon, part})
         .insert(Grid);
+    };
+    // azimuth circles, from nadir to zenith
+    for split in 0..az_split {
+        let az = split as f64 * 2. * pi / az_split as f64;
+        let points = (0..resolution+1).map(|m| (az, -pi / 2. + m as f64 * pi / resolution as f64)).collect();
+        spawn(&mut commands, points, Path2DKind::PhiCircle, format!("{:.0}°", az.to_degrees()), lines_style, GridPart::Lines);
+    }
+    // altitude circles, horizon excluded
+    for split in 1..alt_split {
+        if split * 2 == alt_split {
+            continue;
+        }
+        let alt = -pi / 2. + split as f64 * pi / alt_split as f64;
+        let points = (0..resolution+1).map(|m| (m as f64 * 2. * pi / resolution as f64, alt)).collect();
+        spawn(&mut commands, points, Path2DKind::ThetaCircle, format!("{:+.0}°", alt.to_degrees()), lines_style, GridPart::Lines);
+    }
+    // horizon
+    let points = (0..resolution+1).map(|m| (m as f64 * 2. * pi / resolution as f64, 0.)).collect();
+    spawn(&mut commands, points, Path2DKind::Equator, "Horizon".to_string(), PathStyle::solid(with_alpha(color, 0.8), 2.), GridPart::Equator);
+    // local meridian, from the north point through the zenith to the south point
+    let points = (0..resolution+1)
+        .map(|m| {
+            let t = m as f64 * pi / resolution as f64;
+            if t <= pi / 2. { (0., t) } else { (pi, pi - t) }
+        })
+        .collect();
+    spawn(&mut commands, points, Path2DKind::Equator, "Meridian".to_string(), PathStyle::dashed(with_alpha(color, 0.6), 1.5, 8., 6.), GridPart::Meridian);
+    // zenith and nadir
+    let pole_radius = 1.5_f64.to_radians();
+    for (alt, label) in [(pi / 2. - pole_radius, "Zenith"), (-pi / 2. + pole_radius, "Nadir")].iter() {
+        let points = (0..33).map(|m| (m as f64 * 2. * pi / 32., *alt)).collect();
+        spawn(&mut commands, points, Path2DKind::Pole, label.to_string(), PathStyle::solid(with_alpha(color, 0.8), 1.5), GridPart::Poles);
+    }
+}
+
+/// Scene positions of horizon coordinates for an observer at a julian date
+fn horizon_vertices(points: &[(f64, f64)], observer: &Observer, jd: f64) -> Vec<Vector4<f32>> {
+    points.iter()
+        .map(|(az, alt)| {
+            let (ra, dec) = observer.horizon_to_equatorial(*az, *alt, jd);
+            to_world(to_cartesian(ra, dec))
+        })
+        .collect()
+}
+
+/// Reproject horizon paths on the sky for the current observer and time
+fn update_horizon_paths(
+    clock: Res<SimulationClock>,
+    observer: Res<Observer>,
+    mut query: Query<(&HorizonPath, &mut Path3D)>,
+){
+    if !clock.is_changed() && !observer.is_changed() {
+        return;
+    }
+    for (horizon, mut path3d) in query.iter_mut() {
+        path3d.0 = horizon_vertices(&horizon.0, &observer, clock.jd);
     }
 }
 
-/// Project all 3D paths to 2D paths
+/// Project 3D paths to 2D paths, in homogeneous clip coordinates, when they
+/// or the view change
 fn path_projection(
-    time: Res<Time>, 
-    fov: ResMut<Fov>,
-    camera: ResMut<Camera>, 
-    mut query: Query<(&mut Path2D, &mut Path3D)>,
-    wd: ResMut<WindowDescriptor>,
-){
-    let t: f32 = time.seconds_since_startup() as f32/100.;
-    let aspect = wd.width / wd.height;
-    let proj_m: Matrix4<f32> = perspective(Rad(fov.0), aspect,0.1, 100.);
-    let translate_m: Matrix4<f32> = Matrix4::from_translation(Vector3::new(0., 0., 0.));
-    let rotation_y_m: Matrix4<f32> = Matrix4::from_angle_y(Rad(camera.rot_y));
-    let rotation_x_m: Matrix4<f32> = Matrix4::from_angle_x(Rad(camera.rot_x));
-    let rotation_z_m: Matrix4<f32> = Matrix4::from_angle_z(Rad(0.));
-
-    for (mut path2d, path3d) in query.iter_mut() { 
+    projection: Res<ViewProjection>,
+    mut query: Query<(&mut Path2D, &Path3D, ChangeTrackers<Path3D>)>,
+){
+    for (mut path2d, path3d, tracker) in query.iter_mut() {
+        if !projection.is_changed() && !tracker.is_changed() {
+            continue;
+        }
         let mut vertices_proj = vec![];
         for vertex in &path3d.0 {
-            let vertex_proj = proj_m * translate_m * rotation_z_m * rotation_x_m * rotation_y_m * vertex;
-            let vertex_proj = vertex_proj / vertex_proj[3];
-            vertices_proj.push(vertex_proj);
+            vertices_proj.push(projection.world_to_clip(*vertex));
         }
         path2d.data = vertices_proj;
     }
 }
 
+/// Compute the sky view-projection shared by every system drawing, labelling
+/// or picking on the sky
+fn update_view_proj(
+    fov: Res<Fov>,
+    sky_projection: Res<SkyProjection>,
+    camera: Res<SkyCamera>,
+    wd: Res<WindowDescriptor>,
+    mut projection: ResMut<ViewProjection>,
+){
+    let view_projection = ViewProjection::with_kind(sky_projection.0, fov.0, Vec2::new(wd.width, wd.height), camera.view_matrix());
+    // only a moving view reprojects the paths
+    if *projection != view_projection {
+        *projection = view_projection;
+    }
+}
+
+/// Keep the horizon frame of the camera in step with the observer and time
+fn update_camera_frame(
+    mut camera: ResMut<SkyCamera>,
+    observer: Res<Observer>,
+    clock: Res<SimulationClock>,
+){
+    if camera.frame == CameraFrame::Horizon {
+        camera.frame_rotation = horizon_rotation(&observer, clock.jd);
+    }
+}
+
+/// Orientation looking at a J2000 position once the camera frame follows
+/// `observer` at julian date `jd`, which `update_camera_frame` only applies
+/// later in the frame
+pub fn aim_camera(camera: &SkyCamera, observer: &Observer, jd: f64, ra: f64, dec: f64) -> Quaternion<f64> {
+    let mut target = *camera;
+    if target.frame == CameraFrame::Horizon {
+        target.frame_rotation = horizon_rotation(observer, jd);
+    }
+    let v = to_cartesian(ra, dec);
+    target.look_to_scene(Vector3::new(v.x, v.z, v.y));
+    target.orientation
+}
+
+/// Rotation from horizon frame axes (y to the zenith, x to the north point)
+/// to scene axes
+pub fn horizon_rotation(observer: &Observer, jd: f64) -> Quaternion<f64> {
+    let north = horizon_direction(observer, jd, 0., 0.);
+    let zenith = horizon_direction(observer, jd, 0., std::f64::consts::FRAC_PI_2);
+    Quaternion::from(Matrix3::from_cols(north, zenith, north.cross(zenith))).normalize()
+}
+
+/// Scene direction of horizon coordinates for an observer at a julian date
+fn horizon_direction(observer: &Observer, jd: f64, az: f64, alt: f64) -> Vector3<f64> {
+    let (ra, dec) = observer.horizon_to_equatorial(az, alt, jd);
+    let v = to_cartesian(ra, dec);
+    Vector3::new(v.x, v.z, v.y)
+}
+
+/// Screen positions labelled on a projected grid path: where lines cross the
+/// viewport sides they are labelled on, where equators cross any side, and
+/// the centre of pole circles
+pub fn grid_marker_spots(kind: &Path2DKind, clip: &[Vector4<f32>], projection: &ViewProjection) -> Vec<(MarkerAnchor, Vec2)> {
+    let mut spots = vec![];
+    match kind {
+        // declination-like circles are labelled on the sides, meridians on top and bottom
+        Path2DKind::ThetaCircle | Path2DKind::PhiCircle => {
+            let sides = if matches!(kind, Path2DKind::ThetaCircle) {
+                [Border::Left, Border::Right]
+            } else {
+                [Border::Bottom, Border::Top]
+            };
+            let mut count: HashMap<Border, usize> = HashMap::default();
+            for crossing in border_crossings(clip, Vec2::splat(CLIP_HALF)) {
+                if !sides.contains(&crossing.border) {
+                    continue;
+                }
+                let index = count.entry(crossing.border).or_insert(0);
+                spots.push((MarkerAnchor::Border(crossing.border, *index), projection.ndc_to_screen(crossing.position)));
+                *index += 1;
+            }
+        }
+        // equators, the horizon and the meridian are named wherever they cross the border
+        Path2DKind::Equator => {
+            let mut count: HashMap<Border, usize> = HashMap::default();
+            for crossing in border_crossings(clip, Vec2::splat(CLIP_HALF)) {
+                let index = count.entry(crossing.border).or_insert(0);
+                spots.push((MarkerAnchor::Name(crossing.border, *index), projection.ndc_to_screen(crossing.position)));
+                *index += 1;
+            }
+        }
+        Path2DKind::Pole => {
+            // label at the projected center of the pole circle
+            if clip.iter().any(|v| v[3] <= 0.) {
+                return spots;
+            }
+            let n = clip.len() as f32;
+            let x = clip.iter().map(|v| v[0] / v[3]).sum::<f32>() / n;
+            let y = clip.iter().map(|v| v[1] / v[3]).sum::<f32>() / n;
+            if (x > -CLIP_HALF) & (x < CLIP_HALF) & (y > -CLIP_HALF) & (y < CLIP_HALF) {
+                spots.push((MarkerAnchor::Center, projection.ndc_to_screen(Vec2::new(x, y))));
+            }
+        }
+        _ => {}
+    }
+    spots
+}
+
+/// Label grid lines where they cross the viewport border, and poles at their center.
+/// Label entities are pooled and updated in place, unused ones are hidden.
 fn render_grid_markers(
-    mut query: Query<&mut Path2D>,
-    query_text: Query<Entity, With<GridMarker>>,
-    wd: ResMut<WindowDescriptor>,
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
+    query: Query<(Entity, &Path2D, &PathStyle, Option<&GridLayer>)>,
+    layers: Res<GridLayers>,
+    show_grids: Res<ShowGrids>,
+    show_labels: Res<ShowLabels>,
+    mut pool: ResMut<GridMarkers>,
+    mut query_text: Query<(&mut Style, &mut Text, &mut Visible), With<GridMarker>>,
+    projection: Res<ViewProjection>,
+    font: Res<LabelFont>,
 ) {
-    for e in query_text.iter() {
-        commands.entity(e).despawn_recursive();
+    let mut wanted: HashMap<(Entity, MarkerAnchor), (Rect<Val>, Color, &str)> = HashMap::default();
+    for (entity, path, style, layer) in query.iter() { 
+        if !layers.is_visible(layer) || !show_grids.0 || !show_labels.0 {
+            continue;
+        }
+        let marker_color = with_alpha(style.color, 1.);
+        for (anchor, screen) in grid_marker_spots(&path.kind, &path.data, &projection) {
+            let position = match anchor {
+                MarkerAnchor::Border(Border::Left, _) => Rect {left: Val::Px(4.), bottom: Val::Px(screen.y + 2.), ..Default::default()},
+                MarkerAnchor::Border(Border::Right, _) => Rect {right: Val::Px(4.), bottom: Val::Px(screen.y + 2.), ..Default::default()},
+                MarkerAnchor::Border(Border::Bottom, _) => Rect {left: Val::Px(screen.x + 4.), bottom: Val::Px(4.), ..Default::default()},
+                MarkerAnchor::Border(Border::Top, _) => Rect {left: Val::Px(screen.x + 4.), top: Val::Px(4.), ..Default::default()},
+                // names go below or left of the line, clear of the line values
+                MarkerAnchor::Name(Border::Left, _) => Rect {left: Val::Px(4.), bottom: Val::Px(screen.y - MARKER_FONT_SIZE - 2.), ..Default::default()},
+                MarkerAnchor::Name(Border::Right, _) => Rect {right: Val::Px(4.), bottom: Val::Px(screen.y - MARKER_FONT_SIZE - 2.), ..Default::default()},
+                MarkerAnchor::Name(Border::Bottom, _) => Rect {right: Val::Px(projection.viewport.x - screen.x + 4.), bottom: Val::Px(4.), ..Default::default()},
+                MarkerAnchor::Name(Border::Top, _) => Rect {right: Val::Px(projection.viewport.x - screen.x + 4.), top: Val::Px(4.), ..Default::default()},
+                MarkerAnchor::Center => Rect {bottom: Val::Px(screen.y + 8.), left: Val::Px(screen.x + 8.), ..Default::default()},
+            };
+            wanted.insert((entity, anchor), (position, marker_color, &path.marker));
+        }
     }
-    let w = wd.width;
-    let h = wd.height;
-    for path in query.iter_mut() { 
-        if matches!(path.kind, Path2DKind::ThetaCircle) {
-            let mesh = &path.data;
-            let mut right_marker: Vector4<f32> = Vector4::new(-1., -1., -1., -1.);
-            let mut left_marker: Vector4<f32> = Vector4::new(1., 1., 1., 1.); 
-            let mut draw_right = false;
-            let mut draw_left = false;
-            for m in 0..mesh.len()-1 {
-                if (mesh[m][2] > -1.) & (mesh[m][2] < 1.) {
-                    // right indicators
-                    if (mesh[m][0] < 0.5) & (mesh[m][0] > 0.2) & (mesh[m][1] < 0.5) & (mesh[m][1] > -0.5) {
-                        draw_right = true;
-                        if mesh[m][0] > right_marker[0] {
-                            right_marker[0] = mesh[m][0];
-                            right_marker[1] = mesh[m][1];
-                        }
-                    }
-                    // left indicators
-                    if (mesh[m][0] > -0.5) & (mesh[m][0] < -0.2) & & (mesh[m][1] < 0.5) & (mesh[m][1] > -0.5){
-                        draw_left = true;
-                        if mesh[m][0] < left_marker[0] {
-                            left_marker[0] = mesh[m][0];
-                            left_marker[1] = mesh[m][1];
-                        }
-                    }
-                }
+    // hide pooled labels that are not needed this frame
+    for (key, label) in pool.0.iter() {
+        if wanted.contains_key(key) {
+            continue;
+        }
+        if let Ok((_, _, mut visible)) = query_text.get_mut(*label) {
+            if visible.is_visible {
+                visible.is_visible = false;
             }
-            if draw_right {
-                commands.spawn_bundle(TextBundle {
-                    style: Style {
-                        position_type: PositionType::Absolute,
-                        position: Rect {
-                            bottom: Val::Px(h/2. + (right_marker[1]*h) + 5.),
-                            left: Val::Px(w-40.),
-                            ..Default::default()
-                        },
-                        ..Default::default()
-                    },
-                    text: Text::with_section(
-                        &path.marker,
-                        TextStyle {
-                            font: asset_server.load("fonts/ShareTechMono-Regular.ttf"),
-                            font_size: 13.0,
-                            color: Color::GRAY,
-                        },
-                        TextAlignment {
-                            horizontal: HorizontalAlign::Center,
-                            ..Default::default()
-                        },
-                    ),
-                    ..Default::default()
-                }).insert(GridMarker);
-            }
-            if draw_left {
-                commands.spawn_bundle(TextBundle {
-                    style: Style {
-                        position_type: PositionType::Absolute,
-                        position: Rect {
-                            bottom: Val::Px(h/2. + (left_marker[1]*h) + 5.),
-                            left: Val::Px(10.),
-                            ..Default::default()
-                        },
-                        ..Default::default()
-                    },
-                    text: Text::with_section(
-                        &path.marker,
-                        TextStyle {
-                            font: asset_server.load("fonts/ShareTechMono-Regular.ttf"),
-                            font_size: 13.0,
-                            color: Color::GRAY,
-                        },
-                        TextAlignment {
-                            horizontal: HorizontalAlign::Center,
-                            ..Default::default()
-                        },
-                    ),
-                    ..Default::default()
-                }).insert(GridMarker);
-            }
-            
-        } else if matches!(path.kind, Path2DKind::PhiCircle) {
-            let mesh = &path.data;
-            let mut top_marker: Vector4<f32> = Vector4::new(-1., -1., -1., -1.);
-            let mut bottom_marker: Vector4<f32> = Vector4::new(1., 1., 1., 1.); 
-            let mut draw_top = false;
-            let mut draw_bottom = false;
-            for m in 0..mesh.len()-1 {
-                
-                if (mesh[m][2] > -1.) & (mesh[m][2] < 1.) {
-                    // top indicators
-                    if (mesh[m][1] < 0.5) & (mesh[m][1] > 0.2) & (mesh[m][0] < 0.5) & (mesh[m][0] > -0.5) {
-                        draw_top = true;
-                        if mesh[m][1] > top_marker[1] {
-                            top_marker[0] = mesh[m][0];
-                            top_marker[1] = mesh[m][1];
-                        }
+        }
+    }
+    for (key, (position, color, marker)) in wanted {
+        match pool.0.get(&key).copied() {
+            Some(label) => {
+                // labels spawned last frame may not be queryable yet
+                if let Ok((mut style, mut text, mut visible)) = query_text.get_mut(label) {
+                    if style.position != position {
+                        style.position = position;
                     }
-                    // bottom indicators
-                    if (mesh[m][1] > -0.5) & (mesh[m][1] < -0.2) & & (mesh[m][0] < 0.5) & (mesh[m][0] > -0.5){
-                        draw_bottom = true;
-                        if mesh[m][1] < bottom_marker[1] {
-                            bottom_marker[0] = mesh[m][0];
-                            bottom_marker[1] = mesh[m][1];
-                        }
+                    let section = &text.sections[0];
+                    if section.value != marker || section.style.color != color {
+                        let section = &mut text.sections[0];
+                        section.value = marker.to_string();
+                        section.style.color = color;
+                    }
+                    if !visible.is_visible {
+                        visible.is_visible = true;
                     }
                 }
-            }  
-            if draw_top {
-                commands.spawn_bundle(TextBundle {
-                    style: Style {
-                        position_type: PositionType::Absolute,
-                        position: Rect {
-                            bottom: Val::Px(h-20.),
-                            left: Val::Px(w/2. + top_marker[0]*w),
-                            ..Default::default()
-                        },
-                        ..Default::default()
-                    },
-                    text: Text::with_section(
-                        &path.marker,
-                        TextStyle {
-                            font: asset_server.load("fonts/ShareTechMono-Regular.ttf"),
-                            font_size: 13.0,
-                            color: Color::GRAY,
-                        },
-                        TextAlignment {
-                            horizontal: HorizontalAlign::Center,
-                            ..Default::default()
-                        },
-                    ),
-                    ..Default::default()
-                }).insert(GridMarker);
-            }
-            if draw_bottom {
-                commands.spawn_bundle(TextBundle {
-                    style: Style {
-                        position_type: PositionType::Absolute,
-                        position: Rect {
-                            bottom: Val::Px(20.),
-                            left: Val::Px(w/2. + bottom_marker[0]*w),
-                            ..Default::default()
-                        },
-                        ..Default::default()
-                    },
-                    text: Text::with_section(
-                        &path.marker,
-                        TextStyle {
-                            font: asset_server.load("fonts/ShareTechMono-Regular.ttf"),
-                            font_size: 13.0,
-                            color: Color::GRAY,
-                        },
-                        TextAlignment {
-                            horizontal: HorizontalAlign::Center,
-                            ..Default::default()
-                        },
-                    ),
-                    ..Default::default()
-                }).insert(GridMarker);
+            }
+            None => {
+                let label = spawn_grid_marker(&mut commands, &font, marker, position, color);
+                pool.0.insert(key, label);
             }
         }
     }
 }
 
-/// Render 2D paths with lines
+/// Spawn a grid marker text entity at an absolute screen position
+fn spawn_grid_marker(
+    commands: &mut Commands,
+    font: &LabelFont,
+    marker: &str,
+    position: Rect<Val>,
+    color: Color,
+) -> Entity {
+    commands.spawn_bundle(TextBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            position,
+            ..Default::default()
+        },
+        text: Text::with_section(
+            marker,
+            TextStyle {
+                font: font.0.clone(),
+                font_size: MARKER_FONT_SIZE,
+                color,
+            },
+            TextAlignment {
+                horizontal: HorizontalAlign::Center,
+                ..Default::default()
+            },
+        ),
+        ..Default::default()
+    })
+    .insert(GridMarker)
+    .id()
+}
+
+/// Forget pooled grid markers, their entities are despawned on exit
+fn clear_grid_markers(mut pool: ResMut<GridMarkers>) {
+    pool.0.clear();
+}
+
+/// Batch all projected paths into a single clipped line mesh, rebuilt only
+/// when the paths, their styles or the shown layers change
 fn render_2d_paths(
-    mut lines: ResMut<DebugLines>, 
-    mut query: Query<(&mut Path2D, Option<&Constellation>)>,
-    wd: ResMut<WindowDescriptor>,
-){
-    let w = wd.width;
-    let h = wd.height;
-    let step_size = 1;
-    for (path, constellation) in query.iter_mut() {
-        let color = match constellation {
-            Some(x) => Color::RED,
-            None => Color::Rgba{red: 1., green: 1., blue: 1., alpha: 0.01},
-        };
-        if matches!(path.kind, Path2DKind::ThetaCircle) {
-            let mesh = &path.data;
-            for m in (0..mesh.len()-step_size).step_by(step_size) {
-                if (mesh[m][2] > -1.) & (mesh[m][2] < 1.) & (mesh[m+step_size][2] > -1.) & (mesh[m+step_size][2] < 1.) {
-                    lines.line_colored(
-                        Vec3::new(mesh[m][0]*w, mesh[m][1]*h, 0.), 
-                        Vec3::new(mesh[m+step_size][0]*w, mesh[m+step_size][1]*h, 0.), 
-                        0.,
-                        color);
+    mut commands: Commands,
+    mut batch: Local<LineBatch>,
+    query: Query<(&Path2D, &PathStyle, Option<&GridLayer>)>,
+    query_changed: Query<(), Or<(Changed<Path2D>, Changed<PathStyle>, Changed<GridLayer>)>>,
+    removed: RemovedComponents<Path2D>,
+    layers: Res<GridLayers>,
+    show_grids: Res<ShowGrids>,
+    mut query_lines: Query<(&Handle<Mesh>, &mut Visible), With<LineBatchMesh>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    pipeline: Res<PathPipeline>,
+    projection: Res<ViewProjection>,
+){
+    let changed = query_changed.iter().next().is_some()
+        || removed.iter().next().is_some()
+        || layers.is_changed()
+        || show_grids.is_changed()
+        || projection.is_changed();
+    if !changed {
+        return;
+    }
+    let scale = projection.viewport;
+    batch.clear();
+    for (path, style, layer) in query.iter() {
+        if layers.is_visible(layer) && (layer.is_none() || show_grids.0) {
+            batch.add_path(&path.data, style, scale);
+        }
+    }
+    match query_lines.iter_mut().next() {
+        Some((handle, mut visible)) => {
+            // empty meshes cannot be uploaded, hide the previous lines instead
+            visible.is_visible = !batch.is_empty();
+            if !batch.is_empty() {
+                if let Some(mesh) = meshes.get_mut(handle) {
+                    batch.write_mesh(mesh);
+                }
+            }
+        }
+        None => {
+            if !batch.is_empty() {
+                spawn_line_batch(&mut commands, &pipeline, meshes.add(batch.to_mesh()));
+            }
+        }
+    }
+}
+
+/// UTC date and time of a julian date, to the minute
+fn utc_label(jd: f64) -> String {
+    let seconds = ((jd - 2440587.5) * 86400.).round() as i64;
+    NaiveDateTime::from_timestamp(seconds, 0).format("%Y-%m-%d %H:%M UTC").to_string()
+}
+
+/// Lay out the sky view, its grids, markers and overlay legend on a chart
+/// of any size, keeping the vertical field of view
+fn export_chart(
+    mut export_evr: EventReader<ExportChart>,
+    query_stars: Query<(&Position3D, &StarMagnitude, &StarColor), With<Star>>,
+    query_paths: Query<(&Path2D, &Path3D, &PathStyle, Option<&GridLayer>)>,
+    projection: Res<ViewProjection>,
+    camera: Res<SkyCamera>,
+    brightness: Res<BrightnessModel>,
+    saturation: Res<StarColorSaturation>,
+    layers: Res<GridLayers>,
+    show_grids: Res<ShowGrids>,
+    show_labels: Res<ShowLabels>,
+    overlay: Res<OverlaySettings>,
+    equipment: Res<Equipment>,
+    observer: Res<Observer>,
+    clock: Res<SimulationClock>,
+    clear_color: Res<ClearColor>,
+    status: Res<ExportStatus>,
+){
+    for request in export_evr.iter() {
+        let size = request.size;
+        let chart_projection = ViewProjection::with_kind(projection.kind, projection.fov, size, camera.view_matrix());
+        let scale = size.y / projection.viewport.y;
+        let mut chart = Chart::new(chart_projection, scale, clear_color.0);
+        for (path2d, path3d, style, layer) in query_paths.iter() {
+            if !layers.is_visible(layer) || (layer.is_some() && !show_grids.0) {
+                continue;
+            }
+            chart.add_path(&path3d.0, style);
+            if !show_grids.0 || !show_labels.0 {
+                continue;
+            }
+            let clip: Vec<Vector4<f32>> = path3d.0.iter().map(|v| chart_projection.world_to_clip(*v)).collect();
+            let color = with_alpha(style.color, 1.);
+            for (anchor, screen) in grid_marker_spots(&path2d.kind, &clip, &chart_projection) {
+                match anchor {
+                    MarkerAnchor::Border(border, _) => chart.add_border_label(border, screen, &path2d.marker, color, MARKER_FONT_SIZE),
+                    MarkerAnchor::Name(border, _) => chart.add_border_name(border, screen, &path2d.marker, color, MARKER_FONT_SIZE),
+                    MarkerAnchor::Center => chart.add_label(screen + Vec2::splat(8. * scale), &path2d.marker, color, MARKER_FONT_SIZE, Vec2::ZERO),
                 }
             }
-        } else if matches!(path.kind, Path2DKind::PhiCircle) {
-            let mesh = &path.data;
-            for m in (0..mesh.len()-step_size).step_by(step_size) {
-                if (mesh[m][2] > -1.) & (mesh[m][2] < 1.) & (mesh[m+step_size][2] > -1.) & (mesh[m+step_size][2] < 1.) {
-                    lines.line_colored(
-                        Vec3::new(mesh[m][0]*w, mesh[m][1]*h, 0.), 
-                        Vec3::new(mesh[m+step_size][0]*w, mesh[m+step_size][1]*h, 0.), 
-                        0.,
-                        color);
+        }
+        for (position, magnitude, color) in query_stars.iter() {
+            // white mixed with the catalogue colour, like the star field shader
+            let mix = |channel: f32| 1. + (channel - 1.) * saturation.0;
+            let color = Color::rgb(mix(color.0.r()), mix(color.0.g()), mix(color.0.b()));
+            chart.add_star(position.0, magnitude.0, color, &brightness);
+        }
+        let (ra, dec) = view_center(&camera);
+        chart.add_legend(&format!("{}, {}", observer.name, utc_label(clock.jd)), None);
+        chart.add_legend(&format!("Centre {} {}, field {:.1}°",
+            HMS::from_degrees(ra.to_degrees()).label(),
+            DMS::from_degrees(dec.to_degrees()).label(),
+            projection.fov.to_degrees()), None);
+        if overlay.show_eyepiece {
+            chart.add_legend(&format!("{} eyepiece, {:.2}° field",
+                equipment.eyepiece().name, equipment.eyepiece_field().to_degrees()), Some(EYEPIECE_COLOR));
+        }
+        if overlay.show_sensor {
+            let (width, height) = equipment.sensor_field();
+            chart.add_legend(&format!("{} sensor, {:.2}°×{:.2}°",
+                equipment.sensor().name, width.to_degrees(), height.to_degrees()), Some(SENSOR_COLOR));
+        }
+        if show_grids.0 {
+            for frame in GridFrame::all() {
+                let shown = layers.0.get(&frame).map_or(false, |toggles| toggles.grid || toggles.equator);
+                if shown {
+                    chart.add_legend(&format!("{} grid", frame.name()), Some(frame_color(frame)));
                 }
             }
         }
+        let (width, height) = chart.size();
+        let path = format!("{}/chart-{}-{}x{}.png", SCREENSHOTS_PATH, Utc::now().format("%Y%m%d-%H%M%S"), width, height);
+        *status.0.lock().unwrap() = format!("Saving {}...", path);
+        // large charts take a while to draw, keep the view running meanwhile
+        let status = status.0.clone();
+        std::thread::spawn(move || {
+            let message = match save_chart(&chart, &path) {
+                Ok(()) => format!("Saved {}", path),
+                Err(e) => format!("Could not save {}: {}", path, e),
+            };
+            info!("{}", message);
+            *status.lock().unwrap() = message;
+        });
     }
 }
 
+/// Capture the window as it is drawn, at its resolution
+fn take_screenshot(
+    mut screenshot_evr: EventReader<TakeScreenshot>,
+    windows: Res<Windows>,
+    mut screenshots: ResMut<Screenshots>,
+    status: Res<ExportStatus>,
+){
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    for _ in screenshot_evr.iter() {
+        let path = format!("{}/sky-{}-{}x{}.png", SCREENSHOTS_PATH, Utc::now().format("%Y%m%d-%H%M%S"),
+            window.physical_width(), window.physical_height());
+        screenshots.request(path, status.0.clone());
+    }
+}
+
+fn save_chart(chart: &Chart, path: &str) -> Result<(), Box<dyn error::Error>> {
+    let font = FontVec::try_from_vec(fs::read(CHART_FONT)?)?;
+    chart.save_png(path, &font)
+}
+
+/// Screenshot and high resolution chart buttons
+fn ui_export(
+    egui_context: ResMut<EguiContext>,
+    mut settings: ResMut<ExportSettings>,
+    status: Res<ExportStatus>,
+    mut take_screenshot: EventWriter<TakeScreenshot>,
+    mut export_chart: EventWriter<ExportChart>,
+    bindings: Res<Bindings>,
+) {
+    egui::Window::new("Export")
+    .resizable(false)
+    .collapsible(true)
+    .default_pos(egui::pos2(400., 600.))
+    .show(egui_context.ctx(), |ui| {
+        let screenshot = match bindings.hint(Action::Screenshot) {
+            Some(binding) => format!("Screenshot ({})", binding),
+            None => "Screenshot".to_string(),
+        };
+        if ui.button(screenshot).clicked() {
+            take_screenshot.send(TakeScreenshot);
+        }
+        // the screenshot pass redraws the sky and the bevy UI only
+        ui.add(egui::Label::new("Screenshots leave out these windows").weak().small());
+        ui.horizontal(|ui| {
+            ui.add(egui::Label::new("Chart size:").text_color(egui::Color32::from_rgb(110, 255, 110)));
+            ui.add(egui::DragValue::new(&mut settings.width).speed(10.).clamp_range(100..=16000).suffix(" px"));
+            ui.label("×");
+            ui.add(egui::DragValue::new(&mut settings.height).speed(10.).clamp_range(100..=16000).suffix(" px"));
+        });
+        if ui.button("Export chart").clicked() {
+            export_chart.send(ExportChart{size: Vec2::new(settings.width as f32, settings.height as f32)});
+        }
+        ui.label(status.0.lock().unwrap().as_str());
+    });
+}
+
 /// Adjust field of view with mousewheel or trackpad
-fn fov_adjust(
+pub fn fov_adjust(
     mut scroll_evr: EventReader<MouseWheel>, 
-    mut mouse_inertia: ResMut<MouseInertia>,
-    mut fov: ResMut<Fov>
+    dynamics: Res<CameraDynamics>,
+    mut inertia: ResMut<CameraInertia>,
 ){
     use bevy::input::mouse::MouseScrollUnit;
     for ev in scroll_evr.iter() {
-        match ev.unit {
-            MouseScrollUnit::Line => {
-                mouse_inertia.z += ev.y * 0.005;
-            }
-            MouseScrollUnit::Pixel => {
-                mouse_inertia.z += ev.y * 0.0001;
-            }
-        }
+        let lines = match ev.unit {
+            MouseScrollUnit::Line => ev.y,
+            MouseScrollUnit::Pixel => ev.y / PIXELS_PER_LINE,
+        };
+        // the field of view itself moves with the camera inertia
+        inertia.push(Vec3::new(0., 0., lines * dynamics.scroll_sensitivity));
+    }
+}
+
+/// Keyboard panning, zooming and snapping, fed to the camera inertia
+fn keyboard_navigation(
+    bindings: Res<Bindings>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mouse_input: Res<Input<MouseButton>>,
+    gamepad_input: Res<Input<GamepadButton>>,
+    egui_context: Res<EguiContext>,
+    observer: Res<Observer>,
+    clock: Res<SimulationClock>,
+    time: Res<FrameTime>,
+    mut inertia: ResMut<CameraInertia>,
+    mut camera: ResMut<SkyCamera>,
+){
+    // typing in a text field
+    if egui_context.ctx().wants_keyboard_input() {
+        return;
+    }
+    let input = ActionInput{keys: &keyboard_input, mouse: &mouse_input, gamepad: &gamepad_input};
+    inertia.accelerate(key_acceleration(&bindings, &input));
+    if let Some(target) = key_snap(&bindings, &input) {
+        let direction = match target.horizon() {
+            Some((az, alt)) => horizon_direction(&observer, clock.jd, az, alt),
+            // the pole above the horizon, scene y points north
+            None if observer.latitude >= 0. => Vector3::unit_y(),
+            None => -Vector3::unit_y(),
+        };
+        camera.look_to_scene(direction);
+        inertia.velocity.x = 0.;
+        inertia.velocity.y = 0.;
+    }
+    // roll, only free cameras can
+    if bindings.pressed(Action::RollLeft, &input) {
+        camera.roll(KEY_ROLL_SPEED * time.delta_seconds_f64());
+    }
+    if bindings.pressed(Action::RollRight, &input) {
+        camera.roll(-KEY_ROLL_SPEED * time.delta_seconds_f64());
+    }
+}
+
+/// Toggles, time warp and shortcuts bound to actions
+fn sky_actions(
+    bindings: Res<Bindings>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mouse_input: Res<Input<MouseButton>>,
+    gamepad_input: Res<Input<GamepadButton>>,
+    egui_context: Res<EguiContext>,
+    mut clock: ResMut<SimulationClock>,
+    mut show_grids: ResMut<ShowGrids>,
+    mut show_labels: ResMut<ShowLabels>,
+    mut search_focus: ResMut<SearchFocus>,
+    mut editor: ResMut<BindingsEditor>,
+    mut take_screenshot: EventWriter<TakeScreenshot>,
+){
+    if egui_context.ctx().wants_keyboard_input() {
+        return;
+    }
+    let input = ActionInput{keys: &keyboard_input, mouse: &mouse_input, gamepad: &gamepad_input};
+    if bindings.just_pressed(Action::ToggleGrid, &input) {
+        show_grids.0 = !show_grids.0;
+    }
+    if bindings.just_pressed(Action::ToggleLabels, &input) {
+        show_labels.0 = !show_labels.0;
+    }
+    if bindings.just_pressed(Action::TimeFaster, &input) {
+        clock.rate *= 10.;
+    }
+    if bindings.just_pressed(Action::TimeSlower, &input) {
+        clock.rate /= 10.;
+    }
+    if bindings.just_pressed(Action::TimeNormal, &input) {
+        clock.rate = 1.;
+    }
+    if bindings.just_pressed(Action::Search, &input) {
+        search_focus.0 = true;
+    }
+    if bindings.just_pressed(Action::Settings, &input) {
+        editor.open = !editor.open;
+    }
+    if bindings.just_pressed(Action::Screenshot, &input) {
+        take_screenshot.send(TakeScreenshot);
+    }
+}
+
+/// Gamepad sticks, fed to the camera inertia
+fn gamepad_navigation(
+    settings: Res<StickSettings>,
+    axes: Res<Axis<GamepadAxis>>,
+    gamepads: Res<Gamepads>,
+    mut inertia: ResMut<CameraInertia>,
+){
+    for gamepad in gamepads.0.iter() {
+        let axis = |axis_type| axes.get(GamepadAxis(*gamepad, axis_type)).unwrap_or(0.);
+        let pan = Vec2::new(axis(GamepadAxisType::LeftStickX), axis(GamepadAxisType::LeftStickY));
+        inertia.accelerate(stick_acceleration(&settings, pan, axis(GamepadAxisType::RightStickY)));
     }
-    mouse_inertia.z -= mouse_inertia.z / 40.;
-    fov.0 = f32::min(f32::max(fov.0 - mouse_inertia.z, 0.1), 3.14/1.5);
 }
 
 /// Camera controller
-fn orbit_camera(
-    fov: ResMut<Fov>,
-    mut mouse_inertia: ResMut<MouseInertia>,
-    mut camera: ResMut<Camera>,
+pub fn orbit_camera(
+    time: Res<FrameTime>,
+    dynamics: Res<CameraDynamics>,
+    mut fov: ResMut<Fov>,
+    mut inertia: ResMut<CameraInertia>,
+    mut camera: ResMut<SkyCamera>,
     mut mouse_pressed: ResMut<MouseButtonPressed>,
     mut motion_evr: EventReader<MouseMotion>,
     mut mousebtn_evr: EventReader<MouseButtonInput>,
@@ -555,15 +2410,24 @@ fn orbit_camera(
     }
     if mouse_pressed.0 { 
         for ev in motion_evr.iter(){
-            mouse_inertia.x += ev.delta.y as f32 / 5000.;
-            mouse_inertia.y += ev.delta.x as f32 / 5000.;
-            camera.rot_x -= fov.0 / 3.14 * ev.delta.y as f32 / 300.;
-            camera.rot_y -= fov.0 / 3.14 * ev.delta.x as f32 / 300.;
-        }
-    } else {
-        camera.rot_x -= fov.0 / 3.14 * mouse_inertia.x;
-        camera.rot_y -= fov.0 / 3.14 * mouse_inertia.y;
-    }
-    mouse_inertia.x -= mouse_inertia.x / 40.;
-    mouse_inertia.y -= mouse_inertia.y / 40.;
-}
\ No newline at end of file
+            // the sky follows the cursor, and is thrown on release
+            inertia.push(Vec3::new(ev.delta.y, ev.delta.x, 0.) * dynamics.drag_sensitivity);
+            camera.pitch((fov.0 / 3.14 * ev.delta.y as f32 / 300.) as f64);
+            camera.yaw((fov.0 / 3.14 * ev.delta.x as f32 / 300.) as f64);
+        }
+    }
+    let distance = inertia.step(&dynamics, time.delta_seconds());
+    if !mouse_pressed.0 {
+        camera.pitch((fov.0 / 3.14 * distance.x) as f64);
+        camera.yaw((fov.0 / 3.14 * distance.y) as f64);
+    }
+    let new_fov = f32::min(f32::max(fov.0 - distance.z, 0.1), 3.14/1.5);
+    if new_fov != fov.0 - distance.z {
+        // stop at the zoom limits instead of pushing against them
+        inertia.velocity.z = 0.;
+    }
+    // only write on change so that fov dependent systems can skip idle frames
+    if new_fov != fov.0 {
+        fov.0 = new_fov;
+    }
+}
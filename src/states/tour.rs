@@ -0,0 +1,367 @@
+use bevy::ecs::schedule::StateError;
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use hifitime::Epoch;
+use std::error;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use crate::consts::*;
+use crate::controls::bindings::{Action, ActionInput, Bindings};
+use crate::controls::replay::FrameTime;
+use crate::units::dms::DMS;
+use crate::units::hms::HMS;
+
+/// Scripted guided tours, played over the sky view.
+///
+/// A tour script has one command per line, `#` starts a comment:
+///
+/// ```text
+/// observer Saint-Véran
+/// time 2022-01-15T21:00:00 UTC
+/// goto Betelgeuse
+/// fov 30
+/// caption Orion, the hunter
+/// wait 4
+/// show Equatorial grid
+/// goto 05:35:17 -05:23:28
+/// wait 3
+/// ```
+pub struct Tour;
+
+/// Folder searched for `.tour` scripts
+pub const TOURS_PATH: &str = "assets/tours";
+
+/// What the camera flies to
+#[derive(Debug, Clone, PartialEq)]
+pub enum TourTarget {
+    /// Star proper name or designation
+    Object(String),
+    /// J2000 right ascension and declination in radians
+    Position{ra: f64, dec: f64},
+}
+
+/// One step of a tour script
+#[derive(Debug, Clone, PartialEq)]
+pub enum TourCommand {
+    Goto(TourTarget),
+    /// Vertical field of view in radians
+    Fov(f32),
+    /// Simulated julian date, `None` for the current time
+    Time(Option<f64>),
+    /// Simulated time rate
+    Rate(f64),
+    /// Observing site, by name
+    Observer(String),
+    /// Layer shown or hidden, by the name used in saved views
+    Layer(String, bool),
+    /// Text shown under the sky, empty to clear it
+    Caption(String),
+    /// Seconds before the next commands
+    Wait(f32),
+}
+
+/// Parse a tour script
+pub fn parse_script(script: &str) -> Result<Vec<TourCommand>, Box<dyn error::Error>> {
+    let mut commands = vec![];
+    for (number, line) in script.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let command = parse_command(line).map_err(|e| format!("line {}: {}", number + 1, e))?;
+        commands.push(command);
+    }
+    Ok(commands)
+}
+
+fn parse_command(line: &str) -> Result<TourCommand, Box<dyn error::Error>> {
+    let mut parts = line.splitn(2, char::is_whitespace);
+    let keyword = parts.next().unwrap_or("");
+    let argument = parts.next().unwrap_or("").trim();
+    let command = match keyword {
+        "goto" => TourCommand::Goto(parse_target(argument)?),
+        "fov" => TourCommand::Fov(argument.parse::<f32>()?.to_radians()),
+        "time" => TourCommand::Time(parse_time(argument)?),
+        "rate" => TourCommand::Rate(argument.parse()?),
+        "observer" if !argument.is_empty() => TourCommand::Observer(argument.to_string()),
+        "show" if !argument.is_empty() => TourCommand::Layer(argument.to_string(), true),
+        "hide" if !argument.is_empty() => TourCommand::Layer(argument.to_string(), false),
+        "caption" => TourCommand::Caption(argument.to_string()),
+        "wait" => TourCommand::Wait(argument.parse()?),
+        _ => return Err(format!("cannot read '{}'", line).into()),
+    };
+    Ok(command)
+}
+
+/// An object name, or right ascension `hh:mm:ss` and declination `dd:mm:ss`
+fn parse_target(argument: &str) -> Result<TourTarget, Box<dyn error::Error>> {
+    if argument.is_empty() {
+        return Err("goto needs an object or coordinates".into());
+    }
+    let words: Vec<&str> = argument.split_whitespace().collect();
+    if let [ra, dec] = words.as_slice() {
+        if ra.split(':').count() == 3 && dec.split(':').count() == 3 {
+            let ra = HMS::from_str(ra)?.to_degrees().to_radians();
+            let dec = DMS::from_str(dec)?.to_degrees().to_radians();
+            return Ok(TourTarget::Position{ra, dec});
+        }
+    }
+    Ok(TourTarget::Object(argument.to_string()))
+}
+
+/// `now`, a julian date, or a UTC date such as `2022-01-15T21:00:00 UTC`
+fn parse_time(argument: &str) -> Result<Option<f64>, Box<dyn error::Error>> {
+    if argument == "now" {
+        return Ok(None);
+    }
+    if let Ok(jd) = argument.parse::<f64>() {
+        return Ok(Some(jd));
+    }
+    let epoch = Epoch::from_str(argument).map_err(|e| format!("cannot read time '{}': {}", argument, e))?;
+    Ok(Some(epoch.as_jde_utc_days()))
+}
+
+/// Playback of a tour script
+#[derive(Debug, Clone, PartialEq)]
+pub struct TourPlayer {
+    pub name: String,
+    pub commands: Vec<TourCommand>,
+    /// Index of the next command to run
+    pub next: usize,
+    /// Seconds left in the current wait
+    pub waiting: f32,
+    pub paused: bool,
+}
+
+impl TourPlayer {
+    pub fn new(name: &str, commands: Vec<TourCommand>) -> TourPlayer {
+        TourPlayer { name: name.to_string(), commands, next: 0, waiting: 0., paused: false }
+    }
+
+    /// Commands due after `seconds` of playback, up to the next wait
+    pub fn advance(&mut self, seconds: f32) -> Vec<TourCommand> {
+        if self.paused {
+            return vec![];
+        }
+        self.waiting -= seconds;
+        self.run()
+    }
+
+    /// Cut waits short up to the next commands, and return them
+    pub fn step(&mut self) -> Vec<TourCommand> {
+        let mut due = vec![];
+        while due.is_empty() && !self.is_finished() {
+            self.waiting = 0.;
+            due = self.run();
+        }
+        due
+    }
+
+    fn run(&mut self) -> Vec<TourCommand> {
+        let mut due = vec![];
+        while self.waiting <= 0. && self.next < self.commands.len() {
+            let command = self.commands[self.next].clone();
+            self.next += 1;
+            match command {
+                // keep the overshoot so that long tours do not drift
+                TourCommand::Wait(seconds) => self.waiting += seconds,
+                command => due.push(command),
+            }
+        }
+        due
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.commands.len() && self.waiting <= 0.
+    }
+}
+
+/// Caption currently shown
+#[derive(Default)]
+struct TourCaption(String);
+
+impl Plugin for Tour {
+    fn build(&self, app: &mut AppBuilder){
+        app
+        .add_event::<TourCommand>()
+        .init_resource::<TourCaption>()
+        .insert_resource(TourPlayer::new("", vec![]))
+        .add_system_set(
+            SystemSet::on_update(AppState::Stars)
+            .with_system(ui_tours.system())
+        )
+        .add_system_set(
+            SystemSet::on_update(AppState::Tour)
+            .with_system(play_tour.system())
+            .with_system(ui_tour.system())
+        )
+        .add_system_set(
+            SystemSet::on_exit(AppState::Tour)
+            .with_system(clear_caption.system())
+        );
+    }
+}
+
+/// Read and parse a tour script file
+pub fn load_tour<P: AsRef<Path>>(path: P) -> Result<Vec<TourCommand>, Box<dyn error::Error>> {
+    parse_script(&fs::read_to_string(path)?)
+}
+
+/// Tour scripts found in the tours folder, sorted by name
+fn list_tours<P: AsRef<Path>>(folder: P) -> Vec<String> {
+    let mut names: Vec<String> = match fs::read_dir(folder) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().map_or(false, |ext| ext == "tour"))
+            .filter_map(|path| path.file_stem().map(|stem| stem.to_string_lossy().to_string()))
+            .collect(),
+        Err(_) => vec![],
+    };
+    names.sort();
+    names
+}
+
+/// List the tour scripts and start one
+fn ui_tours(
+    egui_context: ResMut<EguiContext>,
+    mut player: ResMut<TourPlayer>,
+    mut app_state: ResMut<State<AppState>>,
+    mut tours: Local<Option<Vec<String>>>,
+    mut status: Local<String>,
+) {
+    let mut started = None;
+    egui::Window::new("Tours")
+    .resizable(false)
+    .collapsible(true)
+    .default_pos(egui::pos2(1000., 600.))
+    .show(egui_context.ctx(), |ui| {
+        if tours.is_none() || ui.button("Refresh").clicked() {
+            *tours = Some(list_tours(TOURS_PATH));
+        }
+        let names = tours.as_ref().unwrap();
+        if names.is_empty() {
+            ui.label(format!("No .tour script in {}", TOURS_PATH));
+        }
+        for name in names.iter() {
+            ui.horizontal(|ui| {
+                if ui.button("Play").clicked() {
+                    started = Some(name.clone());
+                }
+                ui.label(name);
+            });
+        }
+        ui.label(&*status);
+    });
+    if let Some(name) = started {
+        let path = Path::new(TOURS_PATH).join(format!("{}.tour", name));
+        match load_tour(&path) {
+            Ok(commands) => {
+                *player = TourPlayer::new(&name, commands);
+                status.clear();
+                app_state.push(AppState::Tour).unwrap();
+            }
+            Err(e) => *status = format!("{}: {}", name, e),
+        }
+    }
+}
+
+/// Run the commands due this frame: captions here, the rest by the sky view
+fn play_tour(
+    time: Res<FrameTime>,
+    bindings: Res<Bindings>,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    mut mouse_input: ResMut<Input<MouseButton>>,
+    mut gamepad_input: ResMut<Input<GamepadButton>>,
+    mut player: ResMut<TourPlayer>,
+    mut caption: ResMut<TourCaption>,
+    mut app_state: ResMut<State<AppState>>,
+    mut tour_commands: EventWriter<TourCommand>,
+) {
+    let input = ActionInput{keys: &keyboard_input, mouse: &mouse_input, gamepad: &gamepad_input};
+    if bindings.just_pressed(Action::TourStop, &input) {
+        // the sky view runs again this frame, it must not see the press
+        bindings.consume(Action::TourStop, &mut keyboard_input, &mut mouse_input, &mut gamepad_input);
+        stop_tour(&mut app_state);
+        return;
+    }
+    if bindings.just_pressed(Action::TourPlay, &input) {
+        player.paused = !player.paused;
+    }
+    let due = if bindings.just_pressed(Action::TourStep, &input) {
+        player.step()
+    } else {
+        player.advance(time.delta_seconds())
+    };
+    dispatch(due, &mut caption, &mut tour_commands);
+}
+
+/// Show captions, and hand the other commands to the sky view
+fn dispatch(due: Vec<TourCommand>, caption: &mut TourCaption, tour_commands: &mut EventWriter<TourCommand>) {
+    for command in due {
+        match command {
+            TourCommand::Caption(text) => caption.0 = text,
+            command => tour_commands.send(command),
+        }
+    }
+}
+
+/// Caption under the sky and playback controls
+fn ui_tour(
+    egui_context: ResMut<EguiContext>,
+    mut caption: ResMut<TourCaption>,
+    mut player: ResMut<TourPlayer>,
+    mut app_state: ResMut<State<AppState>>,
+    mut tour_commands: EventWriter<TourCommand>,
+) {
+    let ctx = egui_context.ctx();
+    if !caption.0.is_empty() {
+        egui::Area::new("tour_caption")
+        .anchor(egui::Align2::CENTER_BOTTOM, [0., -60.])
+        .show(ctx, |ui| {
+            ui.add(egui::Label::new(&caption.0).heading().text_color(egui::Color32::WHITE));
+        });
+    }
+    let mut stop = false;
+    let mut step = false;
+    egui::Window::new(format!("Tour: {}", player.name))
+    .resizable(false)
+    .default_pos(egui::pos2(10., 10.))
+    .show(ctx, |ui| {
+        ui.horizontal(|ui| {
+            let label = if player.paused { "Play" } else { "Pause" };
+            if ui.button(label).clicked() {
+                player.paused = !player.paused;
+            }
+            step = ui.button("Step").clicked();
+            stop = ui.button("Stop").clicked();
+        });
+        let progress = if player.is_finished() {
+            "End of the tour".to_string()
+        } else {
+            format!("{}/{}", player.next, player.commands.len())
+        };
+        ui.label(progress);
+    });
+    if step {
+        let due = player.step();
+        dispatch(due, &mut caption, &mut tour_commands);
+    }
+    if stop {
+        stop_tour(&mut app_state);
+    }
+}
+
+/// Go back to the sky view. The stop binding and the Stop button can both
+/// ask in the same frame, the second request is already queued.
+pub fn stop_tour(app_state: &mut State<AppState>) {
+    match app_state.pop() {
+        Ok(()) | Err(StateError::StateAlreadyQueued) => {}
+        Err(e) => panic!("could not leave the tour: {}", e),
+    }
+}
+
+fn clear_caption(mut caption: ResMut<TourCaption>) {
+    caption.0.clear();
+}
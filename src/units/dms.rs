@@ -6,21 +6,46 @@ pub struct DMS {
     pub degrees: i32,
     pub minutes: i32,
     pub seconds: f64,
+    /// Sign of the angle, kept apart so that -0d30m is representable
+    pub negative: bool,
 }
 
 impl DMS {
-    fn to_degrees(&self) -> f64 {
+    pub fn to_degrees(&self) -> f64 {
         let degrees = self.degrees as f64;
         let minutes = self.minutes as f64;
         let seconds = self.seconds;
-        let decimal = f64::signum(degrees) * (f64::abs(degrees) + minutes/60. + seconds/3600.);
+        let sign = if self.negative { -1. } else { 1. };
+        let decimal = sign * (f64::abs(degrees) + minutes/60. + seconds/3600.);
         decimal
     }
+
+    pub fn from_degrees(degrees: f64) -> DMS {
+        let negative = degrees < 0.;
+        let value = f64::abs(degrees);
+        let whole = value.floor();
+        let minutes = ((value - whole) * 60.).floor();
+        let seconds = (value * 3600.) % 60.;
+        DMS {
+            degrees: if negative { -(whole as i32) } else { whole as i32 },
+            minutes: minutes as i32,
+            seconds,
+            negative,
+        }
+    }
+
+    /// Short signed label rounded to the arcminute, e.g. `-16°43'`
+    pub fn label(&self) -> String {
+        let total = (f64::abs(self.to_degrees()) * 60.).round() as i64;
+        let sign = if self.negative && total != 0 { '-' } else { '+' };
+        format!("{}{:02}°{:02}'", sign, total / 60, total % 60)
+    }
 }
 
 impl fmt::Display for DMS {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}d{}m{}s", self.degrees, self.minutes, self.seconds)
+        let sign = if self.negative && self.degrees == 0 { "-" } else { "" };
+        write!(f, "{}{}d{}m{}s", sign, self.degrees, self.minutes, self.seconds)
     }
 }
 
@@ -36,8 +61,8 @@ impl FromStr for DMS {
             degrees,
             minutes,
             seconds,
+            negative: vec[0].trim_start().starts_with('-'),
         };
         Ok(dms)
     }
 }
-
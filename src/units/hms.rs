@@ -9,7 +9,7 @@ pub struct HMS {
 }
 
 impl HMS {
-    fn to_degrees(&self) -> f64 {
+    pub fn to_degrees(&self) -> f64 {
         let hours = self.hours as f64;
         let minutes = self.minutes as f64;
         let seconds = self.seconds;
@@ -17,8 +17,10 @@ impl HMS {
         decimal * 15.
     }
 
-    fn from_degrees(&self, degrees: f64) -> HMS {
-        let time = (degrees % 360.) * 24. / 360.;
+    /// Hour angle of an angle in degrees, wrapped into 0h..24h so that
+    /// negative angles count back from 24h
+    pub fn from_degrees(degrees: f64) -> HMS {
+        let time = degrees.rem_euclid(360.) * 24. / 360.;
         let hours = time.floor();
         let minutes = (time*60.) % 60.;
         let seconds = (time*3600.) % 60.;
@@ -28,6 +30,12 @@ impl HMS {
             seconds,
         }
     }
+
+    /// Short label rounded to the minute, e.g. `06h45m`
+    pub fn label(&self) -> String {
+        let total = (self.to_degrees() * 4.).round() as i64 % (24 * 60);
+        format!("{:02}h{:02}m", total / 60, total % 60)
+    }
 }
 
 impl fmt::Display for HMS {
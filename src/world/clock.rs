@@ -0,0 +1,24 @@
+use crate::world::lst::{jd, utc_str};
+
+/// Simulated time, as a julian date advancing at `rate` times real time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SimulationClock {
+    pub jd: f64,
+    pub rate: f64,
+}
+
+impl SimulationClock {
+    pub fn new(jd: f64) -> SimulationClock {
+        SimulationClock { jd, rate: 1. }
+    }
+
+    /// Clock set to the current UTC time
+    pub fn now() -> SimulationClock {
+        SimulationClock::new(jd(&utc_str()))
+    }
+
+    /// Advance by `seconds` of real time
+    pub fn advance(&mut self, seconds: f64) {
+        self.jd += seconds * self.rate / 86400.;
+    }
+}
@@ -0,0 +1,78 @@
+/// Blackbody colours as (temperature in K, sRGB), from Mitchell Charity's
+/// "Blackbody color datafile" (CIE 1964 10 degree observer, D65 white point).
+const BLACKBODY: [(f32, [u8; 3]); 30] = [
+    (1000., [255, 56, 0]),
+    (1500., [255, 109, 0]),
+    (2000., [255, 137, 18]),
+    (2500., [255, 161, 72]),
+    (3000., [255, 180, 107]),
+    (3500., [255, 196, 137]),
+    (4000., [255, 209, 163]),
+    (4500., [255, 219, 186]),
+    (5000., [255, 228, 206]),
+    (5500., [255, 236, 224]),
+    (6000., [255, 243, 239]),
+    (6500., [255, 249, 253]),
+    (7000., [245, 243, 255]),
+    (7500., [235, 238, 255]),
+    (8000., [227, 233, 255]),
+    (8500., [220, 229, 255]),
+    (9000., [214, 225, 255]),
+    (9500., [208, 222, 255]),
+    (10000., [204, 219, 255]),
+    (11000., [196, 215, 255]),
+    (12000., [191, 211, 255]),
+    (13000., [186, 208, 255]),
+    (14000., [182, 206, 255]),
+    (15000., [179, 204, 255]),
+    (16000., [176, 202, 255]),
+    (18000., [172, 199, 255]),
+    (20000., [168, 197, 255]),
+    (25000., [163, 193, 255]),
+    (30000., [159, 191, 255]),
+    (40000., [155, 188, 255]),
+];
+
+/// Effective temperature in kelvins from a B-V colour index, using
+/// Ballesteros' formula (2012).
+pub fn bv_to_temperature(bv: f32) -> f32 {
+    4600. * (1. / (0.92 * bv + 1.7) + 1. / (0.92 * bv + 0.62))
+}
+
+/// sRGB colour, components in [0, 1], of a blackbody at `temperature` kelvins.
+pub fn temperature_to_rgb(temperature: f32) -> [f32; 3] {
+    let to_rgb = |c: [u8; 3]| [c[0] as f32 / 255., c[1] as f32 / 255., c[2] as f32 / 255.];
+    let (first_t, first_c) = BLACKBODY[0];
+    if temperature.is_nan() || temperature <= first_t {
+        return to_rgb(first_c);
+    }
+    for pair in BLACKBODY.windows(2) {
+        let (t0, c0) = pair[0];
+        let (t1, c1) = pair[1];
+        if temperature <= t1 {
+            let a = (temperature - t0) / (t1 - t0);
+            let (c0, c1) = (to_rgb(c0), to_rgb(c1));
+            return [
+                c0[0] + a * (c1[0] - c0[0]),
+                c0[1] + a * (c1[1] - c0[1]),
+                c0[2] + a * (c1[2] - c0[2]),
+            ];
+        }
+    }
+    to_rgb(BLACKBODY[BLACKBODY.len() - 1].1)
+}
+
+/// sRGB colour of a star from its B-V colour index, blended towards white
+/// as `saturation` goes from 1 to 0. Unknown (NaN) indices render white.
+pub fn bv_to_rgb(bv: f32, saturation: f32) -> [f32; 3] {
+    if bv.is_nan() {
+        return [1., 1., 1.];
+    }
+    let c = temperature_to_rgb(bv_to_temperature(bv.max(-0.4).min(2.0)));
+    let s = saturation.max(0.).min(1.);
+    [
+        1. + s * (c[0] - 1.),
+        1. + s * (c[1] - 1.),
+        1. + s * (c[2] - 1.),
+    ]
+}
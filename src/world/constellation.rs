@@ -0,0 +1,45 @@
+//! Constellation stick figures, joining catalogue stars by name.
+use std::error;
+
+use crate::catalog::reader::Catalogue;
+
+/// One polyline of a constellation figure
+#[derive(Debug, Clone, PartialEq)]
+pub struct StickFigure {
+    /// IAU abbreviation, e.g. `Ori`
+    pub constellation: String,
+    /// Proper names or designations of the stars joined in turn
+    pub stars: Vec<String>,
+}
+
+impl StickFigure {
+    /// J2000 right ascension and declination of the stars in radians, or
+    /// the name of the first star missing from the catalogue
+    pub fn resolve(&self, catalogue: &Catalogue) -> Result<Vec<(f64, f64)>, String> {
+        self.stars.iter()
+            .map(|name| catalogue.find(name)
+                .map(|(star, _)| (star.ra as f64, star.dec as f64))
+                .ok_or_else(|| name.clone()))
+            .collect()
+    }
+}
+
+/// Parse stick figures, one per line as `Ori: Alp Ori, Gam Ori, ...`.
+/// Empty lines and lines starting with `#` are skipped.
+pub fn parse_figures(text: &str) -> Result<Vec<StickFigure>, Box<dyn error::Error>> {
+    let mut figures = vec![];
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (constellation, stars) = line.split_once(':')
+            .ok_or_else(|| format!("line {}: expected '<constellation>: <star>, <star>, ...'", number + 1))?;
+        let stars: Vec<String> = stars.split(',').map(|s| s.trim().to_string()).collect();
+        if stars.len() < 2 || stars.iter().any(|s| s.is_empty()) {
+            return Err(format!("line {}: a figure joins at least two named stars", number + 1).into());
+        }
+        figures.push(StickFigure{constellation: constellation.trim().to_string(), stars});
+    }
+    Ok(figures)
+}
@@ -0,0 +1,129 @@
+//! Deep sky objects: galaxies, clusters and nebulae drawn as symbols on
+//! printed charts.
+use std::error;
+use std::io::Read;
+use std::str::FromStr;
+
+use crate::units::{dms::DMS, hms::HMS};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DeepSkyKind {
+    Galaxy,
+    OpenCluster,
+    GlobularCluster,
+    Nebula,
+    PlanetaryNebula,
+}
+
+impl DeepSkyKind {
+    pub const ALL: [DeepSkyKind; 5] = [
+        DeepSkyKind::Galaxy,
+        DeepSkyKind::OpenCluster,
+        DeepSkyKind::GlobularCluster,
+        DeepSkyKind::Nebula,
+        DeepSkyKind::PlanetaryNebula,
+    ];
+
+    /// Type code used in deep sky files, as in the NGC catalogues
+    pub fn code(&self) -> &'static str {
+        match self {
+            DeepSkyKind::Galaxy => "Gx",
+            DeepSkyKind::OpenCluster => "OC",
+            DeepSkyKind::GlobularCluster => "GC",
+            DeepSkyKind::Nebula => "Nb",
+            DeepSkyKind::PlanetaryNebula => "PN",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DeepSkyKind::Galaxy => "Galaxy",
+            DeepSkyKind::OpenCluster => "Open cluster",
+            DeepSkyKind::GlobularCluster => "Globular cluster",
+            DeepSkyKind::Nebula => "Nebula",
+            DeepSkyKind::PlanetaryNebula => "Planetary nebula",
+        }
+    }
+}
+
+impl FromStr for DeepSkyKind {
+    type Err = Box<dyn error::Error>;
+    fn from_str(s: &str) -> Result<Self, Box<dyn error::Error>> {
+        DeepSkyKind::ALL.iter()
+            .find(|kind| kind.code() == s)
+            .copied()
+            .ok_or_else(|| format!("unknown deep sky object type '{}'", s).into())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeepSkyObject {
+    pub name: String,
+    pub kind: DeepSkyKind,
+    /// J2000 right ascension and declination in radians
+    pub ra: f64,
+    pub dec: f64,
+    /// Integrated magnitude, NaN when unknown
+    pub mag: f32,
+    /// Largest apparent diameter in radians, zero when unknown
+    pub size: f64,
+}
+
+impl DeepSkyObject {
+    /// Mean surface brightness (mag/arcsec²) over a disc of the object's
+    /// size, `None` when its magnitude or size is unknown
+    pub fn surface_brightness(&self) -> Option<f32> {
+        if self.mag.is_nan() || self.size <= 0. {
+            return None;
+        }
+        let radius = self.size.to_degrees() * 3600. / 2.;
+        let area = std::f64::consts::PI * radius * radius;
+        Some(self.mag + 2.5 * area.log10() as f32)
+    }
+}
+
+/// Parse a deep sky CSV file with a `name,type,ra,dec,mag,size` header.
+///
+/// Right ascension is `h:m:s`, declination `d:m:s` and size in arcminutes;
+/// magnitude and size may be left empty.
+pub fn read_deep_sky<R: Read>(reader: R) -> Result<Vec<DeepSkyObject>, Box<dyn error::Error>> {
+    let mut rdr = csv::ReaderBuilder::new()
+        .has_headers(true)
+        .trim(csv::Trim::All)
+        .comment(Some(b'#'))
+        .from_reader(reader);
+    let mut objects = vec![];
+    for (line, result) in rdr.records().enumerate() {
+        let record = result?;
+        let parse = || -> Result<DeepSkyObject, Box<dyn error::Error>> {
+            let get = |i: usize| record.get(i).ok_or("missing column");
+            let mag = match get(4)? {
+                "" => f32::NAN,
+                value => value.parse()?,
+            };
+            let size: f64 = match get(5)? {
+                "" => 0.,
+                value => value.parse()?,
+            };
+            Ok(DeepSkyObject {
+                name: get(0)?.to_string(),
+                kind: get(1)?.parse()?,
+                ra: HMS::from_str(get(2)?)?.to_degrees().to_radians(),
+                dec: DMS::from_str(get(3)?)?.to_degrees().to_radians(),
+                mag,
+                size: (size / 60.).to_radians(),
+            })
+        };
+        let object = parse().map_err(|e| format!("deep sky row {}: {}", line + 2, e))?;
+        objects.push(object);
+    }
+    Ok(objects)
+}
+
+/// Look an object up by name, ignoring case and spaces, so that `m 42`
+/// finds `M42`
+pub fn find<'a>(objects: &'a [DeepSkyObject], name: &str) -> Option<&'a DeepSkyObject> {
+    let key = |s: &str| s.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_lowercase();
+    let name = key(name);
+    objects.iter().find(|object| key(&object.name) == name)
+}
@@ -0,0 +1,48 @@
+//! Low precision Sun and Moon positions, good to a fraction of a degree
+//! (Astronomical Almanac formulas), which is plenty to tell day from night.
+use crate::world::frames::{Frame, J2000};
+
+/// Geocentric ecliptic longitude of the Sun (radians, ecliptic of date)
+pub fn sun_longitude(jd: f64) -> f64 {
+    let n = jd - J2000;
+    let l = 280.460 + 0.9856474 * n;
+    let g = (357.528 + 0.9856003 * n).to_radians();
+    (l + 1.915 * g.sin() + 0.020 * (2. * g).sin()).rem_euclid(360.).to_radians()
+}
+
+/// Geocentric ecliptic longitude and latitude of the Moon (radians, ecliptic
+/// of date)
+pub fn moon_ecliptic(jd: f64) -> (f64, f64) {
+    let t = (jd - J2000) / 36525.;
+    let s = |a: f64, b: f64| (a + b * t).to_radians().sin();
+    let lon = 218.32 + 481267.881 * t
+        + 6.29 * s(135.0, 477198.87)
+        - 1.27 * s(259.3, -413335.36)
+        + 0.66 * s(235.7, 890534.22)
+        + 0.21 * s(269.9, 954397.74)
+        - 0.19 * s(357.5, 35999.05)
+        - 0.11 * s(186.5, 966404.03);
+    let lat = 5.13 * s(93.3, 483202.02)
+        + 0.28 * s(228.2, 960400.89)
+        - 0.28 * s(318.3, 6003.15)
+        - 0.17 * s(217.6, -407332.21);
+    (lon.rem_euclid(360.).to_radians(), lat.to_radians())
+}
+
+/// J2000 right ascension and declination of the Sun (radians)
+pub fn sun_equatorial(jd: f64) -> (f64, f64) {
+    Frame::Ecliptic.to_equatorial(sun_longitude(jd), 0., jd)
+}
+
+/// J2000 right ascension and declination of the Moon (radians)
+pub fn moon_equatorial(jd: f64) -> (f64, f64) {
+    let (lon, lat) = moon_ecliptic(jd);
+    Frame::Ecliptic.to_equatorial(lon, lat, jd)
+}
+
+/// Illuminated fraction of the Moon's disc, 0 at new moon and 1 at full moon
+pub fn moon_illumination(jd: f64) -> f64 {
+    let (lon, lat) = moon_ecliptic(jd);
+    let elongation = (lat.cos() * (lon - sun_longitude(jd)).cos()).acos();
+    (1. - elongation.cos()) / 2.
+}
@@ -0,0 +1,177 @@
+//! Optical train model: telescope, Barlow or reducer, and either an eyepiece
+//! or a camera sensor at the focal plane.
+
+/// Arcseconds per radian
+const ARCSEC_PER_RADIAN: f64 = 206264.806;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Telescope {
+    pub name: String,
+    /// Focal length in millimetres
+    pub focal_length: f64,
+    /// Aperture in millimetres
+    pub aperture: f64,
+}
+
+/// Barlow lens or focal reducer, a factor of 1 stands for none
+#[derive(Debug, Clone, PartialEq)]
+pub struct Corrector {
+    pub name: String,
+    pub factor: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Eyepiece {
+    pub name: String,
+    /// Focal length in millimetres
+    pub focal_length: f64,
+    /// Apparent field of view in degrees
+    pub apparent_fov: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sensor {
+    pub name: String,
+    /// Resolution in pixels
+    pub width: u32,
+    pub height: u32,
+    /// Pixel pitch in micrometres
+    pub pixel_size: f64,
+}
+
+impl Telescope {
+    pub fn new(name: &str, focal_length: f64, aperture: f64) -> Telescope {
+        Telescope { name: name.to_string(), focal_length, aperture }
+    }
+
+    pub fn focal_ratio(&self) -> f64 {
+        self.focal_length / self.aperture
+    }
+
+    /// Focal length in millimetres once a corrector is added
+    pub fn effective_focal_length(&self, corrector: &Corrector) -> f64 {
+        self.focal_length * corrector.factor
+    }
+}
+
+impl Corrector {
+    pub fn new(name: &str, factor: f64) -> Corrector {
+        Corrector { name: name.to_string(), factor }
+    }
+}
+
+impl Eyepiece {
+    pub fn new(name: &str, focal_length: f64, apparent_fov: f64) -> Eyepiece {
+        Eyepiece { name: name.to_string(), focal_length, apparent_fov }
+    }
+
+    pub fn magnification(&self, telescope: &Telescope, corrector: &Corrector) -> f64 {
+        telescope.effective_focal_length(corrector) / self.focal_length
+    }
+
+    /// Diameter of the sky seen through the eyepiece, in radians
+    pub fn true_field(&self, telescope: &Telescope, corrector: &Corrector) -> f64 {
+        self.apparent_fov.to_radians() / self.magnification(telescope, corrector)
+    }
+
+    /// Exit pupil in millimetres
+    pub fn exit_pupil(&self, telescope: &Telescope, corrector: &Corrector) -> f64 {
+        telescope.aperture / self.magnification(telescope, corrector)
+    }
+}
+
+impl Sensor {
+    pub fn new(name: &str, width: u32, height: u32, pixel_size: f64) -> Sensor {
+        Sensor { name: name.to_string(), width, height, pixel_size }
+    }
+
+    /// Width and height of the sensor in millimetres
+    pub fn size(&self) -> (f64, f64) {
+        (self.width as f64 * self.pixel_size / 1000., self.height as f64 * self.pixel_size / 1000.)
+    }
+
+    /// Width and height of the imaged sky, in radians
+    pub fn field(&self, telescope: &Telescope, corrector: &Corrector) -> (f64, f64) {
+        let focal_length = telescope.effective_focal_length(corrector);
+        let (width, height) = self.size();
+        (2. * (width / 2. / focal_length).atan(), 2. * (height / 2. / focal_length).atan())
+    }
+
+    /// Sky covered by one pixel, in arcseconds
+    pub fn image_scale(&self, telescope: &Telescope, corrector: &Corrector) -> f64 {
+        self.pixel_size / 1000. / telescope.effective_focal_length(corrector) * ARCSEC_PER_RADIAN
+    }
+}
+
+/// Equipment at hand, with the current selection
+#[derive(Debug, Clone, PartialEq)]
+pub struct Equipment {
+    pub telescopes: Vec<Telescope>,
+    pub correctors: Vec<Corrector>,
+    pub eyepieces: Vec<Eyepiece>,
+    pub sensors: Vec<Sensor>,
+    pub telescope: usize,
+    pub corrector: usize,
+    pub eyepiece: usize,
+    pub sensor: usize,
+}
+
+impl Default for Equipment {
+    fn default() -> Self {
+        Equipment {
+            telescopes: vec![
+                Telescope::new("80mm f/6 refractor", 480., 80.),
+                Telescope::new("200mm f/5 Newtonian", 1000., 200.),
+                Telescope::new("8\" f/10 SCT", 2032., 203.),
+            ],
+            correctors: vec![
+                Corrector::new("None", 1.),
+                Corrector::new("2x Barlow", 2.),
+                Corrector::new("0.63x reducer", 0.63),
+            ],
+            eyepieces: vec![
+                Eyepiece::new("32mm Plossl", 32., 50.),
+                Eyepiece::new("25mm Plossl", 25., 52.),
+                Eyepiece::new("13mm 82°", 13., 82.),
+                Eyepiece::new("6mm 60°", 6., 60.),
+            ],
+            sensors: vec![
+                Sensor::new("APS-C 24MP", 6000, 4000, 3.72),
+                Sensor::new("Full frame 24MP", 6000, 4000, 5.95),
+                Sensor::new("IMX294 4/3\"", 4144, 2822, 4.63),
+            ],
+            telescope: 0,
+            corrector: 0,
+            eyepiece: 0,
+            sensor: 0,
+        }
+    }
+}
+
+impl Equipment {
+    pub fn telescope(&self) -> &Telescope {
+        &self.telescopes[self.telescope]
+    }
+
+    pub fn corrector(&self) -> &Corrector {
+        &self.correctors[self.corrector]
+    }
+
+    pub fn eyepiece(&self) -> &Eyepiece {
+        &self.eyepieces[self.eyepiece]
+    }
+
+    pub fn sensor(&self) -> &Sensor {
+        &self.sensors[self.sensor]
+    }
+
+    /// True field of the selected eyepiece, in radians
+    pub fn eyepiece_field(&self) -> f64 {
+        self.eyepiece().true_field(self.telescope(), self.corrector())
+    }
+
+    /// Field of the selected sensor, in radians
+    pub fn sensor_field(&self) -> (f64, f64) {
+        self.sensor().field(self.telescope(), self.corrector())
+    }
+}
@@ -0,0 +1,156 @@
+use cgmath::{Matrix, Matrix3, Vector3, Vector4};
+
+/// Julian date of the J2000.0 epoch
+pub const J2000: f64 = 2451545.0;
+
+/// Celestial reference frames
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Frame {
+    /// Equatorial, J2000 equator and equinox
+    Equatorial,
+    /// Ecliptic and equinox of date
+    Ecliptic,
+    /// IAU 1958 galactic coordinates
+    Galactic,
+    /// De Vaucouleurs supergalactic coordinates
+    Supergalactic,
+}
+
+impl Frame {
+    pub const ALL: [Frame; 4] = [Frame::Equatorial, Frame::Ecliptic, Frame::Galactic, Frame::Supergalactic];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Frame::Equatorial => "Equatorial",
+            Frame::Ecliptic => "Ecliptic",
+            Frame::Galactic => "Galactic",
+            Frame::Supergalactic => "Supergalactic",
+        }
+    }
+
+    /// Short labels of the north and south poles
+    pub fn pole_labels(&self) -> (&'static str, &'static str) {
+        match self {
+            Frame::Equatorial => ("NCP", "SCP"),
+            Frame::Ecliptic => ("NEP", "SEP"),
+            Frame::Galactic => ("NGP", "SGP"),
+            Frame::Supergalactic => ("NSGP", "SSGP"),
+        }
+    }
+
+    /// Whether the frame moves with the date, and its grid with it
+    pub fn is_of_date(&self) -> bool {
+        matches!(self, Frame::Ecliptic)
+    }
+
+    /// Rotation from J2000 equatorial cartesian coordinates to this frame at
+    /// julian date `jd`
+    pub fn from_equatorial_matrix(&self, jd: f64) -> Matrix3<f64> {
+        match self {
+            Frame::Equatorial => Matrix3::from_scale(1.),
+            Frame::Ecliptic => rot_x(obliquity(jd)) * precession_matrix(jd),
+            Frame::Galactic => galactic_matrix(),
+            Frame::Supergalactic => supergalactic_matrix() * galactic_matrix(),
+        }
+    }
+
+    /// Rotation from this frame to J2000 equatorial cartesian coordinates
+    pub fn to_equatorial_matrix(&self, jd: f64) -> Matrix3<f64> {
+        self.from_equatorial_matrix(jd).transpose()
+    }
+
+    /// Convert J2000 right ascension and declination (radians) to this frame's
+    /// longitude and latitude (radians)
+    pub fn from_equatorial(&self, ra: f64, dec: f64, jd: f64) -> (f64, f64) {
+        to_spherical(self.from_equatorial_matrix(jd) * to_cartesian(ra, dec))
+    }
+
+    /// Convert this frame's longitude and latitude (radians) to J2000 right
+    /// ascension and declination (radians)
+    pub fn to_equatorial(&self, lon: f64, lat: f64, jd: f64) -> (f64, f64) {
+        to_spherical(self.to_equatorial_matrix(jd) * to_cartesian(lon, lat))
+    }
+}
+
+/// Build a matrix from its rows
+fn rows(r: [[f64; 3]; 3]) -> Matrix3<f64> {
+    Matrix3::new(
+        r[0][0], r[1][0], r[2][0],
+        r[0][1], r[1][1], r[2][1],
+        r[0][2], r[1][2], r[2][2])
+}
+
+/// Frame rotation of angle `a` around the x axis
+fn rot_x(a: f64) -> Matrix3<f64> {
+    rows([[1., 0., 0.], [0., a.cos(), a.sin()], [0., -a.sin(), a.cos()]])
+}
+
+/// Frame rotation of angle `a` around the y axis
+fn rot_y(a: f64) -> Matrix3<f64> {
+    rows([[a.cos(), 0., -a.sin()], [0., 1., 0.], [a.sin(), 0., a.cos()]])
+}
+
+/// Frame rotation of angle `a` around the z axis
+fn rot_z(a: f64) -> Matrix3<f64> {
+    rows([[a.cos(), a.sin(), 0.], [-a.sin(), a.cos(), 0.], [0., 0., 1.]])
+}
+
+fn arcsec(a: f64) -> f64 {
+    (a / 3600.).to_radians()
+}
+
+/// Julian centuries since J2000
+fn centuries(jd: f64) -> f64 {
+    (jd - J2000) / 36525.
+}
+
+/// Mean obliquity of the ecliptic at julian date `jd` (IAU 2006), radians
+pub fn obliquity(jd: f64) -> f64 {
+    let t = centuries(jd);
+    arcsec(84381.406 - 46.836769 * t - 0.0001831 * t * t + 0.00200340 * t * t * t)
+}
+
+/// Precession from J2000 to the mean equator and equinox of date (IAU 1976)
+pub fn precession_matrix(jd: f64) -> Matrix3<f64> {
+    let t = centuries(jd);
+    let zeta = arcsec(2306.2181 * t + 0.30188 * t * t + 0.017998 * t * t * t);
+    let z = arcsec(2306.2181 * t + 1.09468 * t * t + 0.018203 * t * t * t);
+    let theta = arcsec(2004.3109 * t - 0.42665 * t * t - 0.041833 * t * t * t);
+    rot_z(-z) * rot_y(theta) * rot_z(-zeta)
+}
+
+/// J2000 equatorial to galactic rotation (Hipparcos, ESA 1997)
+fn galactic_matrix() -> Matrix3<f64> {
+    rows([
+        [-0.0548755604, -0.8734370902, -0.4838350155],
+        [0.4941094279, -0.4448296300, 0.7469822445],
+        [-0.8676661490, -0.1980763734, 0.4559837762],
+    ])
+}
+
+/// Galactic to supergalactic rotation (Lahav et al. 2000)
+fn supergalactic_matrix() -> Matrix3<f64> {
+    rows([
+        [-0.7357425748, 0.6772612964, 0.0000000000],
+        [-0.0745537783, -0.0809914713, 0.9939225904],
+        [0.6731453021, 0.7312711658, 0.1100812622],
+    ])
+}
+
+/// Unit vector of a longitude and latitude, z towards the north pole
+pub fn to_cartesian(lon: f64, lat: f64) -> Vector3<f64> {
+    Vector3::new(lat.cos() * lon.cos(), lat.cos() * lon.sin(), lat.sin())
+}
+
+/// Longitude in [0, 2pi) and latitude of a vector
+pub fn to_spherical(v: Vector3<f64>) -> (f64, f64) {
+    let lon = v.y.atan2(v.x).rem_euclid(2. * std::f64::consts::PI);
+    let lat = (v.z / (v.x * v.x + v.y * v.y + v.z * v.z).sqrt()).asin();
+    (lon, lat)
+}
+
+/// Scene position of a J2000 equatorial unit vector, matching `Polar::to_cart`
+/// (y towards the north celestial pole, x towards the vernal equinox)
+pub fn to_world(v: Vector3<f64>) -> Vector4<f32> {
+    Vector4::new(v.x as f32, v.z as f32, v.y as f32, 1.)
+}
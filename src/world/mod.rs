@@ -1,2 +1,14 @@
 pub mod object;
-pub mod position;
\ No newline at end of file
+pub mod position;
+pub mod color;
+pub mod photometry;
+pub mod frames;
+pub mod lst;
+pub mod observer;
+pub mod clock;
+pub mod ephemeris;
+pub mod sky;
+pub mod equipment;
+pub mod overlay;
+pub mod deep_sky;
+pub mod constellation;
\ No newline at end of file
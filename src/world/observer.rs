@@ -0,0 +1,72 @@
+use crate::world::lst::{era, lst_at_lon};
+
+/// Location of an observer on Earth
+#[derive(Debug, Clone, PartialEq)]
+pub struct Observer {
+    pub name: String,
+    /// Geodetic latitude in degrees, north positive
+    pub latitude: f64,
+    /// Longitude in degrees, east positive
+    pub longitude: f64,
+    /// Usual Bortle class of the site's sky
+    pub bortle: u8,
+}
+
+impl Default for Observer {
+    fn default() -> Self {
+        Observer {
+            name: "Paris".to_string(),
+            latitude: 48.8566,
+            longitude: 2.3522,
+            bortle: 9,
+        }
+    }
+}
+
+impl Observer {
+    pub fn new(name: &str, latitude: f64, longitude: f64, bortle: u8) -> Observer {
+        Observer { name: name.to_string(), latitude, longitude, bortle }
+    }
+
+    /// A few observing sites, from city centre to the darkest skies
+    pub fn sites() -> Vec<Observer> {
+        vec![
+            Observer::default(),
+            Observer::new("Saint-Véran", 44.6969, 6.9072, 2),
+            Observer::new("Pic du Midi", 42.9369, 0.1411, 2),
+            Observer::new("La Palma", 28.7606, -17.8816, 1),
+            Observer::new("Mauna Kea", 19.8207, -155.4681, 1),
+            Observer::new("Paranal", -24.6272, -70.4042, 1),
+            Observer::new("Siding Spring", -31.2733, 149.0617, 2),
+        ]
+    }
+
+    /// Local sidereal time at julian date `jd`, radians in [0, 2pi)
+    pub fn local_sidereal_time(&self, jd: f64) -> f64 {
+        lst_at_lon(self.longitude, era(jd)).to_radians().rem_euclid(2. * std::f64::consts::PI)
+    }
+
+    /// Convert azimuth (from north, through east) and altitude to right
+    /// ascension and declination, all in radians
+    pub fn horizon_to_equatorial(&self, az: f64, alt: f64, jd: f64) -> (f64, f64) {
+        let lat = self.latitude.to_radians();
+        let dec = (lat.sin() * alt.sin() + lat.cos() * alt.cos() * az.cos()).asin();
+        let hour_angle = f64::atan2(
+            -alt.cos() * az.sin(),
+            lat.cos() * alt.sin() - lat.sin() * alt.cos() * az.cos());
+        let ra = (self.local_sidereal_time(jd) - hour_angle).rem_euclid(2. * std::f64::consts::PI);
+        (ra, dec)
+    }
+
+    /// Convert right ascension and declination to azimuth (from north,
+    /// through east) and altitude, all in radians
+    pub fn equatorial_to_horizon(&self, ra: f64, dec: f64, jd: f64) -> (f64, f64) {
+        let lat = self.latitude.to_radians();
+        let hour_angle = self.local_sidereal_time(jd) - ra;
+        let alt = (lat.sin() * dec.sin() + lat.cos() * dec.cos() * hour_angle.cos()).asin();
+        let az = f64::atan2(
+            -dec.cos() * hour_angle.sin(),
+            lat.cos() * dec.sin() - lat.sin() * dec.cos() * hour_angle.cos());
+        (az.rem_euclid(2. * std::f64::consts::PI), alt)
+    }
+}
@@ -0,0 +1,99 @@
+//! Shapes drawn on the sky around a centre, built in angular units on the
+//! celestial sphere so that any projection renders them correctly.
+use cgmath::{InnerSpace, Vector3};
+use crate::world::frames::to_cartesian;
+
+/// Unit vectors pointing east and north at a position on the sphere, in the
+/// cartesian frame of `to_cartesian`
+pub fn local_basis(lon: f64, lat: f64) -> (Vector3<f64>, Vector3<f64>) {
+    let east = Vector3::new(-lon.sin(), lon.cos(), 0.);
+    let north = Vector3::new(-lat.sin() * lon.cos(), -lat.sin() * lon.sin(), lat.cos());
+    (east, north)
+}
+
+/// Closed small circle of angular `radius` around (`lon`, `lat`), all in
+/// radians
+pub fn circle(lon: f64, lat: f64, radius: f64, segments: u32) -> Vec<Vector3<f64>> {
+    let center = to_cartesian(lon, lat);
+    let (east, north) = local_basis(lon, lat);
+    (0..segments+1)
+        .map(|i| {
+            let t = i as f64 * 2. * std::f64::consts::PI / segments as f64;
+            center * radius.cos() + (east * t.cos() + north * t.sin()) * radius.sin()
+        })
+        .collect()
+}
+
+/// Closed rectangle of angular `width` by `height` centred on (`lon`, `lat`),
+/// its width rotated by `rotation` from east towards north, all in radians.
+/// Sides are great circles, like the edges of a sensor imaged by a lens.
+pub fn rectangle(lon: f64, lat: f64, width: f64, height: f64, rotation: f64, subdivisions: u32) -> Vec<Vector3<f64>> {
+    let center = to_cartesian(lon, lat);
+    let (east, north) = local_basis(lon, lat);
+    let u = east * rotation.cos() + north * rotation.sin();
+    let v = north * rotation.cos() - east * rotation.sin();
+    // gnomonic coordinates of the corners
+    let (x, y) = ((width / 2.).tan(), (height / 2.).tan());
+    let corners = [(-x, -y), (x, -y), (x, y), (-x, y), (-x, -y)];
+    let mut points = vec![];
+    for pair in corners.windows(2) {
+        let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+        for i in 0..subdivisions {
+            let t = i as f64 / subdivisions as f64;
+            let (x, y) = (x0 + (x1 - x0) * t, y0 + (y1 - y0) * t);
+            points.push((center + u * x + v * y).normalize());
+        }
+    }
+    points.push(points[0]);
+    points
+}
+
+/// Two great circle arcs crossing at (`lon`, `lat`), one along the
+/// east-west direction and one along north-south, each `length` long
+pub fn crosshair(lon: f64, lat: f64, length: f64, segments: u32) -> [Vec<Vector3<f64>>; 2] {
+    let center = to_cartesian(lon, lat);
+    let (east, north) = local_basis(lon, lat);
+    let arc = |direction: Vector3<f64>| -> Vec<Vector3<f64>> {
+        (0..segments+1)
+            .map(|i| {
+                let t = (i as f64 / segments as f64 - 0.5) * length;
+                center * t.cos() + direction * t.sin()
+            })
+            .collect()
+    };
+    [arc(east), arc(north)]
+}
+
+/// Finder reticle: concentric rings and an optional crosshair
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reticle {
+    pub name: String,
+    /// Ring diameters in degrees
+    pub rings: Vec<f64>,
+    pub crosshair: bool,
+}
+
+impl Reticle {
+    /// Telrad reflex finder, 0.5°, 2° and 4° rings
+    pub fn telrad() -> Reticle {
+        Reticle { name: "Telrad".to_string(), rings: vec![0.5, 2., 4.], crosshair: false }
+    }
+
+    /// Right angle correct image 9x50 finder, 5° field with crosshair
+    pub fn raci() -> Reticle {
+        Reticle { name: "RACI 9x50".to_string(), rings: vec![5.], crosshair: true }
+    }
+
+    /// Outlines of the reticle centred on (`lon`, `lat`), in radians
+    pub fn outlines(&self, lon: f64, lat: f64) -> Vec<Vec<Vector3<f64>>> {
+        let mut outlines: Vec<Vec<Vector3<f64>>> = self.rings.iter()
+            .filter(|d| **d > 0.)
+            .map(|d| circle(lon, lat, d.to_radians() / 2., 128))
+            .collect();
+        if self.crosshair {
+            let largest = self.rings.iter().cloned().fold(1., f64::max);
+            outlines.extend_from_slice(&crosshair(lon, lat, largest.to_radians(), 32));
+        }
+        outlines
+    }
+}
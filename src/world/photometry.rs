@@ -0,0 +1,76 @@
+/// Magnitude to on-screen size and opacity model for point sources.
+///
+/// Inspired by Stellarium's sky drawer: the limiting magnitude deepens as the
+/// field of view narrows, apparent flux is compressed by `contrast` before
+/// being turned into a disc area, and stars too faint to reach
+/// `min_radius` fade out instead of shrinking.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BrightnessModel {
+    /// Faintest visible magnitude at `reference_fov`.
+    pub limiting_mag: f32,
+    /// Faintest magnitude the sky background lets through at
+    /// `reference_fov`, capping `limiting_mag` without replacing it.
+    pub sky_limit: f32,
+    /// Dynamic range compression, 1 makes disc area proportional to flux.
+    pub contrast: f32,
+    /// Radius in pixels of a star one magnitude above the limit.
+    pub min_radius: f32,
+    /// Largest radius in pixels.
+    pub max_radius: f32,
+    /// Field of view (radians) at which `limiting_mag` applies.
+    pub reference_fov: f32,
+    /// Magnitudes gained per decade of zoom.
+    pub zoom_gain: f32,
+}
+
+/// Apparent size and opacity of a star.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PointSource {
+    pub radius: f32,
+    pub alpha: f32,
+}
+
+impl Default for BrightnessModel {
+    fn default() -> Self {
+        BrightnessModel {
+            limiting_mag: 6.,
+            sky_limit: f32::INFINITY,
+            contrast: 0.5,
+            min_radius: 1.,
+            max_radius: 16.,
+            reference_fov: 1.6,
+            zoom_gain: 2.5,
+        }
+    }
+}
+
+impl BrightnessModel {
+    /// Limiting magnitude for a field of view in radians.
+    pub fn limit_at(&self, fov: f32) -> f32 {
+        self.limiting_mag.min(self.sky_limit) + self.zoom_gain * f32::max(f32::log10(self.reference_fov / fov), 0.)
+    }
+
+    /// Flux relative to a star one magnitude brighter than the limit.
+    pub fn relative_flux(&self, mag: f32, fov: f32) -> f32 {
+        f32::powf(10., -0.4 * (mag - (self.limit_at(fov) - 1.)))
+    }
+
+    pub fn point_source(&self, mag: f32, fov: f32) -> PointSource {
+        let flux = self.relative_flux(mag, fov);
+        let radius = self.min_radius * f32::powf(flux, self.contrast / 2.);
+        if radius >= self.min_radius {
+            PointSource {
+                radius: f32::min(radius, self.max_radius),
+                alpha: 1.,
+            }
+        } else {
+            // within one magnitude of the limit, fade linearly with flux
+            let limit_flux = f32::powf(10., -0.4);
+            let alpha = f32::max((flux - limit_flux) / (1. - limit_flux), 0.);
+            PointSource {
+                radius: self.min_radius,
+                alpha,
+            }
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use crate::world::ephemeris::{sun_equatorial, moon_equatorial, moon_illumination};
+use crate::world::observer::Observer;
+
+/// Typical zenith sky brightness (mag/arcsec²) of each Bortle class, 1 to 9
+const BORTLE_SQM: [f32; 9] = [21.9, 21.8, 21.5, 20.8, 19.9, 19.1, 18.5, 18.0, 17.5];
+
+/// Surface brightness (mag/arcsec²) of the bright parts of the Milky Way
+pub const MILKY_WAY_SURFACE_BRIGHTNESS: f32 = 20.5;
+
+/// How much fainter than the sky background (mag/arcsec²) an extended object
+/// can be and still be made out, the eye picking up a few percent of contrast
+/// over a large area
+pub const DETECTION_MARGIN: f32 = 4.;
+
+/// Sky brightness sources other than the stars themselves, reduced to how
+/// dark the background is for an observer at a given time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SkyConditions {
+    /// Zenith sky brightness due to light pollution, as read by a sky quality
+    /// meter (mag/arcsec²), higher is darker
+    pub sky_quality: f32,
+}
+
+/// State of the Sun and Moon as seen by the observer
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SkyState {
+    /// Sun altitude (radians)
+    pub sun_altitude: f64,
+    /// Moon altitude (radians)
+    pub moon_altitude: f64,
+    /// Illuminated fraction of the Moon
+    pub moon_illumination: f64,
+}
+
+impl SkyConditions {
+    /// Typical conditions of a Bortle class, clamped to 1..=9
+    pub fn from_bortle(class: u8) -> SkyConditions {
+        let index = class.max(1).min(9) as usize - 1;
+        SkyConditions { sky_quality: BORTLE_SQM[index] }
+    }
+
+    /// Bortle class whose typical sky brightness is the closest
+    pub fn bortle(&self) -> u8 {
+        let mut best = 0;
+        for (i, sqm) in BORTLE_SQM.iter().enumerate() {
+            if (sqm - self.sky_quality).abs() < (BORTLE_SQM[best] - self.sky_quality).abs() {
+                best = i;
+            }
+        }
+        best as u8 + 1
+    }
+
+    /// Naked eye limiting magnitude at the zenith, from the sky brightness
+    /// (empirical fit used by sky quality meter vendors)
+    pub fn limiting_magnitude(&self) -> f32 {
+        7.93 - 5. * (10_f32.powf(4.316 - self.sky_quality / 5.) + 1.).log10()
+    }
+
+    /// Linear RGB of the sky background, a faint blue at pristine sites
+    /// rising to a washed out grey under city lights
+    pub fn background_color(&self) -> [f32; 3] {
+        let level = (0.004 * 10_f32.powf(-0.4 * (self.sky_quality - 22.))).min(0.25);
+        [level * 0.9, level, level * 1.2]
+    }
+
+    /// Visibility of an extended source of given surface brightness
+    /// (mag/arcsec²) against the light polluted sky, from 0 when it is
+    /// fainter than the sky to 1 when it stands out clearly
+    pub fn extended_visibility(&self, surface_brightness: f32) -> f32 {
+        ((self.sky_quality - surface_brightness) / 1.2).max(0.).min(1.)
+    }
+
+    /// Visibility of faint diffuse light such as the Milky Way, from 0
+    /// (invisible) to 1 (dark moonless night at a pristine site)
+    pub fn diffuse_visibility(&self, sky: &SkyState) -> f32 {
+        sky_darkness(sky) * self.extended_visibility(MILKY_WAY_SURFACE_BRIGHTNESS)
+    }
+
+    /// Visibility of a deep sky object of given mean surface brightness
+    /// (mag/arcsec²), from 0 to 1. Objects of unknown brightness only fade
+    /// with twilight and moonlight.
+    pub fn object_visibility(&self, sky: &SkyState, surface_brightness: Option<f32>) -> f32 {
+        let contrast = match surface_brightness {
+            Some(brightness) => self.extended_visibility(brightness - DETECTION_MARGIN),
+            None => 1.,
+        };
+        sky_darkness(sky) * contrast
+    }
+}
+
+impl SkyState {
+    pub fn at(observer: &Observer, jd: f64) -> SkyState {
+        let (ra, dec) = sun_equatorial(jd);
+        let (_, sun_altitude) = observer.equatorial_to_horizon(ra, dec, jd);
+        let (ra, dec) = moon_equatorial(jd);
+        let (_, moon_altitude) = observer.equatorial_to_horizon(ra, dec, jd);
+        SkyState {
+            sun_altitude,
+            moon_altitude,
+            moon_illumination: moon_illumination(jd),
+        }
+    }
+}
+
+/// Fraction of the night sky darkness left by twilight and moonlight
+fn sky_darkness(sky: &SkyState) -> f32 {
+    twilight_darkness(sky.sun_altitude) * moonlight_darkness(sky.moon_altitude, sky.moon_illumination)
+}
+
+/// Fraction of the night sky darkness left by twilight: 0 while the Sun is
+/// above -6° (civil twilight), 1 once it is below -18° (astronomical night)
+pub fn twilight_darkness(sun_altitude: f64) -> f32 {
+    let t = ((-6. - sun_altitude.to_degrees()) / 12.).max(0.).min(1.) as f32;
+    t * t * (3. - 2. * t)
+}
+
+/// Fraction of the darkness left by moonlight, a full moon high in the sky
+/// washes out most of the faint diffuse light
+pub fn moonlight_darkness(moon_altitude: f64, illumination: f64) -> f32 {
+    let height = moon_altitude.sin().max(0.).sqrt();
+    1. - 0.85 * (illumination * height) as f32
+}
@@ -0,0 +1,93 @@
+use stargazer::catalog::reader::Catalogue;
+use stargazer::catalog::record::{pack_constellation, StarRecord, HEADER_SIZE, NO_NAME, RECORD_SIZE};
+use stargazer::catalog::writer::CatalogueWriter;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(id: u32, name: u32) -> StarRecord {
+        StarRecord {
+            id,
+            ra: 1.5,
+            dec: -0.25,
+            mag: 0.5,
+            abs_mag: 1.2,
+            color_index: f32::NAN,
+            pm_ra: 10.,
+            pm_dec: -3.,
+            name,
+            distance: 2.64,
+            designation: NO_NAME,
+            constellation: pack_constellation("CMa"),
+        }
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let mut writer = CatalogueWriter::new();
+        let sirius = writer.add_name("Sirius");
+        writer.push(record(1, sirius));
+        let unnamed = writer.add_name("");
+        writer.push(record(2, unnamed));
+        let sirius_again = writer.add_name("Sirius");
+        writer.push(record(3, sirius_again));
+
+        let catalogue = Catalogue::from_bytes(writer.to_bytes()).unwrap();
+        assert_eq!(catalogue.len(), 3);
+        let first = catalogue.get(0).unwrap();
+        assert_eq!(first.id, 1);
+        assert_eq!(first.ra, 1.5);
+        assert!(first.color_index.is_nan());
+        assert_eq!(catalogue.name(&first), Some("Sirius"));
+        assert_eq!(first.constellation(), Some("CMa"));
+        assert_eq!(catalogue.get(1).unwrap().name, NO_NAME);
+        assert_eq!(catalogue.name(&catalogue.get(1).unwrap()), None);
+        assert_eq!(catalogue.get(2).unwrap().name, sirius);
+        assert!(catalogue.get(3).is_none());
+    }
+
+    #[test]
+    fn test_find_by_name_or_designation() {
+        let mut writer = CatalogueWriter::new();
+        let mut sirius = record(1, writer.add_name("Sirius"));
+        sirius.designation = writer.add_name("Alp CMa");
+        writer.push(sirius);
+        let catalogue = Catalogue::from_bytes(writer.to_bytes()).unwrap();
+        assert_eq!(catalogue.find(" sirius").map(|(star, name)| (star.id, name)), Some((1, "Sirius")));
+        assert_eq!(catalogue.find("alp cma").map(|(star, name)| (star.id, name)), Some((1, "Alp CMa")));
+        assert!(catalogue.find("Vega").is_none());
+    }
+
+    #[test]
+    fn test_rejects_bad_magic() {
+        let mut bytes = CatalogueWriter::new().to_bytes();
+        bytes[0] = b'X';
+        assert!(Catalogue::from_bytes(bytes).is_err());
+    }
+
+    #[test]
+    fn test_rejects_truncated() {
+        let mut writer = CatalogueWriter::new();
+        writer.push(record(1, NO_NAME));
+        let mut bytes = writer.to_bytes();
+        bytes.truncate(20);
+        assert!(Catalogue::from_bytes(bytes).is_err());
+    }
+
+    #[test]
+    fn test_rejects_bad_name_offsets() {
+        let mut writer = CatalogueWriter::new();
+        let sirius = writer.add_name("Sirius");
+        writer.push(record(1, sirius));
+        let vega = writer.add_name("Vega");
+        writer.push(record(2, vega));
+        let bytes = writer.to_bytes();
+        let catalogue = Catalogue::from_bytes(bytes.clone()).unwrap();
+        let offsets = HEADER_SIZE + catalogue.len() * RECORD_SIZE;
+        // second name starting past the end of the blob
+        let mut corrupt = bytes;
+        corrupt[offsets + 4..offsets + 8].copy_from_slice(&100u32.to_le_bytes());
+        assert!(Catalogue::from_bytes(corrupt).is_err());
+    }
+}
@@ -0,0 +1,57 @@
+use std::fs::File;
+use stargazer::catalog::hyg::{read_hyg, to_catalogue};
+use stargazer::catalog::reader::Catalogue;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture() -> File {
+        File::open("tests/fixtures/hyg_small.csv").unwrap()
+    }
+
+    #[test]
+    fn test_read_hyg() {
+        let stars = read_hyg(fixture()).unwrap();
+        assert_eq!(stars.len(), 6);
+        let sirius = stars.iter().find(|s| s.proper == "Sirius").unwrap();
+        assert_eq!(sirius.id, 32263);
+        assert_eq!(sirius.designation(), Some("Alp CMa".to_string()));
+        assert_eq!(sirius.color_index, 0.009);
+        let flamsteed = stars.iter().find(|s| s.id == 28614).unwrap();
+        assert_eq!(flamsteed.designation(), Some("67 Ori".to_string()));
+        let anonymous = stars.iter().find(|s| s.id == 119615).unwrap();
+        assert_eq!(anonymous.designation(), None);
+        assert!(anonymous.color_index.is_nan());
+    }
+
+    #[test]
+    fn test_catalogue_sorted_without_sun() {
+        let writer = to_catalogue(read_hyg(fixture()).unwrap(), f64::INFINITY);
+        let catalogue = Catalogue::from_bytes(writer.to_bytes()).unwrap();
+        let ids: Vec<u32> = catalogue.iter().map(|s| s.id).collect();
+        assert_eq!(ids, vec![32263, 24378, 27919, 28614, 119615]);
+
+        let sirius = catalogue.get(0).unwrap();
+        assert_eq!(catalogue.name(&sirius), Some("Sirius"));
+        assert_eq!(catalogue.designation(&sirius), Some("Alp CMa"));
+        assert_eq!(sirius.constellation(), Some("CMa"));
+        assert!((sirius.ra - 1.7677943).abs() < 1e-5);
+        assert!((sirius.dec + 0.2917531).abs() < 1e-5);
+        assert!((sirius.distance - 2.6371).abs() < 1e-4);
+        assert_eq!(sirius.pm_dec, -1223.08);
+
+        let anonymous = catalogue.get(4).unwrap();
+        assert_eq!(catalogue.name(&anonymous), None);
+        assert_eq!(anonymous.constellation(), None);
+        assert!(anonymous.distance.is_nan());
+    }
+
+    #[test]
+    fn test_magnitude_filter() {
+        let writer = to_catalogue(read_hyg(fixture()).unwrap(), 1.);
+        let catalogue = Catalogue::from_bytes(writer.to_bytes()).unwrap();
+        assert_eq!(catalogue.len(), 3);
+        assert!(catalogue.iter().all(|s| s.mag <= 1.));
+    }
+}
@@ -0,0 +1,97 @@
+use bevy::prelude::*;
+use stargazer::controls::bindings::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults_have_no_conflicts() {
+        assert!(Bindings::default().conflicts().is_empty());
+    }
+
+    #[test]
+    fn test_config_roundtrip() {
+        let mut bindings = Bindings::default();
+        bindings.add(Action::Pause, Binding::Mouse(MouseButton::Other(4)));
+        bindings.remove(Action::Search, Binding::Key(KeyCode::F));
+        let parsed = Bindings::from_config(&bindings.to_config()).unwrap();
+        assert_eq!(parsed, bindings);
+    }
+
+    #[test]
+    fn test_config_overrides_defaults() {
+        let bindings = Bindings::from_config("# comment\npause = P, Gamepad:Select\n\nsearch =\n").unwrap();
+        assert_eq!(bindings.get(Action::Pause), &[Binding::Key(KeyCode::P), Binding::Gamepad(GamepadButtonType::Select)]);
+        assert!(bindings.get(Action::Search).is_empty());
+        assert_eq!(bindings.get(Action::NightVision), &[Binding::Key(KeyCode::N)]);
+    }
+
+    #[test]
+    fn test_config_errors() {
+        assert!(Bindings::from_config("jump = Space").is_err());
+        assert!(Bindings::from_config("pause = Escap").is_err());
+        assert!(Bindings::from_config("pause").is_err());
+    }
+
+    #[test]
+    fn test_conflicts_respect_scopes() {
+        let mut bindings = Bindings::default();
+        // tours and the sky view never read the same press
+        bindings.add(Action::TourPlay, Binding::Key(KeyCode::G));
+        assert!(bindings.conflicts().is_empty());
+        bindings.add(Action::NightVision, Binding::Key(KeyCode::G));
+        bindings.add(Action::SnapPole, Binding::Key(KeyCode::G));
+        let conflicts = bindings.conflicts();
+        assert_eq!(conflicts.len(), 2);
+        assert_eq!(conflicts[0], Conflict{
+            binding: Binding::Key(KeyCode::G),
+            actions: vec![Action::NightVision, Action::ToggleGrid, Action::SnapPole],
+        });
+        assert_eq!(conflicts[1].actions, vec![Action::NightVision, Action::TourPlay]);
+    }
+
+    #[test]
+    fn test_start_conflicts_with_sky_actions() {
+        // the sky view runs in the frame the menu is left
+        let mut bindings = Bindings::default();
+        bindings.add(Action::Pause, Binding::Gamepad(GamepadButtonType::Start));
+        assert_eq!(bindings.conflicts(), vec![Conflict{
+            binding: Binding::Gamepad(GamepadButtonType::Start),
+            actions: vec![Action::Start, Action::Pause],
+        }]);
+    }
+
+    #[test]
+    fn test_cancel_capture_only_conflicts_with_global_actions() {
+        // Escape also pauses and leaves tours, captures consume it first
+        let mut bindings = Bindings::default();
+        bindings.add(Action::NightVision, Binding::Key(KeyCode::Escape));
+        let conflicts = bindings.conflicts();
+        assert_eq!(conflicts.len(), 3);
+        assert_eq!(conflicts[2].actions, vec![Action::NightVision, Action::CancelCapture]);
+    }
+
+    #[test]
+    fn test_hint() {
+        let mut bindings = Bindings::default();
+        assert_eq!(bindings.hint(Action::RollLeft), Some("Q".to_string()));
+        bindings.remove(Action::RollLeft, Binding::Key(KeyCode::Q));
+        assert_eq!(bindings.hint(Action::RollLeft), Some("Gamepad:LeftTrigger".to_string()));
+        bindings.remove(Action::RollLeft, Binding::Gamepad(GamepadButtonType::LeftTrigger));
+        assert_eq!(bindings.hint(Action::RollLeft), None);
+    }
+
+    #[test]
+    fn test_pressed_on_any_device() {
+        let bindings = Bindings::default();
+        let keys = Input::<KeyCode>::default();
+        let mouse = Input::<MouseButton>::default();
+        let mut gamepad = Input::<GamepadButton>::default();
+        gamepad.press(GamepadButton(Gamepad(1), GamepadButtonType::DPadUp));
+        let input = ActionInput{keys: &keys, mouse: &mouse, gamepad: &gamepad};
+        assert!(bindings.pressed(Action::PanUp, &input));
+        assert!(bindings.just_pressed(Action::PanUp, &input));
+        assert!(!bindings.pressed(Action::PanDown, &input));
+    }
+}
@@ -0,0 +1,64 @@
+use cgmath::{Quaternion, Rotation3, Rad};
+use stargazer::controls::bookmarks::*;
+use stargazer::render::camera::{CameraFrame, CameraMode};
+use stargazer::render::projection::ProjectionKind;
+use stargazer::world::observer::Observer;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn orion() -> Bookmark {
+        Bookmark {
+            name: "Orion at 22:00 from the club site".to_string(),
+            orientation: Quaternion::from_angle_y(Rad(2.1)) * Quaternion::from_angle_x(Rad(0.3)),
+            frame: CameraFrame::Horizon,
+            mode: CameraMode::KeepLevel,
+            fov: 0.7,
+            projection: ProjectionKind::Stereographic,
+            observer: Observer::new("Club, north field", 44.6969, 6.9072, 3),
+            sky_quality: 21.3,
+            jd: 2459580.375,
+            rate: 1.,
+            layers: vec!["Alt-azimuth grid".to_string(), "Labels".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let mut pole = orion();
+        pole.name = "Pole".to_string();
+        pole.frame = CameraFrame::Equatorial;
+        pole.mode = CameraMode::Free;
+        pole.projection = ProjectionKind::Gnomonic;
+        pole.layers = vec![];
+        let bookmarks = Bookmarks(vec![orion(), pole]);
+        assert_eq!(Bookmarks::from_config(&bookmarks.to_config()).unwrap(), bookmarks);
+    }
+
+    #[test]
+    fn test_observer_name_with_separator() {
+        let mut bookmark = orion();
+        bookmark.observer = Observer::new("Club; north field", 44.6969, 6.9072, 3);
+        let bookmarks = Bookmarks(vec![bookmark]);
+        assert_eq!(Bookmarks::from_config(&bookmarks.to_config()).unwrap(), bookmarks);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(Bookmarks::from_config("fov = 1").is_err());
+        assert!(Bookmarks::from_config("[a]\nfov = wide").is_err());
+        assert!(Bookmarks::from_config("[a]\norientation = 1 0 0").is_err());
+        assert!(Bookmarks::from_config("[a]\nzoom = 2").is_err());
+        assert!(Bookmarks::from_config("[a]\nprojection = mercator").is_err());
+    }
+
+    #[test]
+    fn test_missing_settings_default() {
+        let bookmarks = Bookmarks::from_config("# views\n[Empty]\n").unwrap();
+        assert_eq!(bookmarks.0.len(), 1);
+        assert_eq!(bookmarks.0[0].name, "Empty");
+        assert_eq!(bookmarks.0[0].frame, CameraFrame::Equatorial);
+        assert_eq!(bookmarks.0[0].projection, ProjectionKind::Gnomonic);
+    }
+}
@@ -0,0 +1,88 @@
+use bevy::prelude::*;
+use stargazer::controls::dynamics::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Position every half second over 2 s, thrown at the start and
+    /// accelerated during the first half second, stepping through `steps`
+    /// (seconds) within each half second
+    fn trajectory(dynamics: &CameraDynamics, steps: &[f32]) -> Vec<Vec3> {
+        let mut inertia = CameraInertia::default();
+        inertia.push(Vec3::new(0.3, -0.2, 0.1));
+        let mut position = Vec3::ZERO;
+        let mut samples = vec![];
+        for half_second in 0..4 {
+            for step in steps {
+                if half_second == 0 {
+                    inertia.accelerate(Vec3::new(1.44, 0., 3.6));
+                }
+                position += inertia.step(dynamics, *step);
+            }
+            samples.push(position);
+        }
+        samples
+    }
+
+    fn frames(fps: usize) -> Vec<f32> {
+        vec![1. / fps as f32; fps / 2]
+    }
+
+    fn assert_close(a: &[Vec3], b: &[Vec3]) {
+        for (a, b) in a.iter().zip(b) {
+            assert!((*a - *b).abs().max_element() < 1e-4, "{:?} != {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_same_trajectory_at_any_frame_rate() {
+        let dynamics = CameraDynamics::default();
+        let reference = trajectory(&dynamics, &frames(60));
+        assert_close(&trajectory(&dynamics, &frames(30)), &reference);
+        assert_close(&trajectory(&dynamics, &frames(144)), &reference);
+        assert_close(&trajectory(&dynamics, &frames(240)), &reference);
+        // stuttering frames
+        assert_close(&trajectory(&dynamics, &[0.1, 0.05, 0.2, 0.15]), &reference);
+        // the whole half second at once
+        assert_close(&trajectory(&dynamics, &[0.5]), &reference);
+    }
+
+    #[test]
+    fn test_same_trajectory_without_damping() {
+        let dynamics = CameraDynamics{damping: 0., ..CameraDynamics::default()};
+        assert_close(&trajectory(&dynamics, &frames(30)), &trajectory(&dynamics, &frames(144)));
+    }
+
+    #[test]
+    fn test_motion_dies_out() {
+        let dynamics = CameraDynamics::default();
+        let mut inertia = CameraInertia::default();
+        inertia.push(Vec3::new(1., 0., 0.));
+        let mut distance = 0.;
+        for _ in 0..60 {
+            distance += inertia.step(&dynamics, 1. / 60.).x;
+        }
+        let k = dynamics.damping;
+        assert!((inertia.velocity.x - (-k).exp()).abs() < 1e-5);
+        assert!((distance - (1. - (-k).exp()) / k).abs() < 1e-5);
+        // a throw never goes further than velocity / damping
+        for _ in 0..6000 {
+            distance += inertia.step(&dynamics, 1. / 60.).x;
+        }
+        assert!((distance - 1. / k).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_held_controls_reach_terminal_velocity() {
+        let dynamics = CameraDynamics::default();
+        let mut inertia = CameraInertia::default();
+        for _ in 0..1200 {
+            inertia.accelerate(Vec3::new(0., 0., 3.));
+            inertia.step(&dynamics, 1. / 60.);
+        }
+        assert!((inertia.velocity.z - 3. / dynamics.damping).abs() < 1e-4);
+        // acceleration only lasts the step it was given for
+        assert_eq!(inertia.acceleration, Vec3::ZERO);
+    }
+}
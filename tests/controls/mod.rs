@@ -0,0 +1,5 @@
+mod navigation;
+mod bindings;
+mod bookmarks;
+mod replay;
+mod dynamics;
@@ -0,0 +1,83 @@
+use bevy::app::Events;
+use bevy::prelude::*;
+use stargazer::controls::bindings::{ActionInput, Bindings};
+use stargazer::controls::navigation::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn acceleration(keys: &Input<KeyCode>) -> Vec3 {
+        let mouse = Input::<MouseButton>::default();
+        let gamepad = Input::<GamepadButton>::default();
+        key_acceleration(&Bindings::default(), &ActionInput{keys, mouse: &mouse, gamepad: &gamepad})
+    }
+
+    #[test]
+    fn test_arrows_and_wasd_pan_alike() {
+        let mut arrows = Input::<KeyCode>::default();
+        arrows.press(KeyCode::Up);
+        arrows.press(KeyCode::Left);
+        let mut wasd = Input::<KeyCode>::default();
+        wasd.press(KeyCode::W);
+        wasd.press(KeyCode::A);
+        assert_eq!(acceleration(&arrows), acceleration(&wasd));
+        assert_eq!(acceleration(&arrows), Vec3::new(KEY_PAN_ACCELERATION, KEY_PAN_ACCELERATION, 0.));
+    }
+
+    #[test]
+    fn test_opposite_keys_cancel() {
+        let mut keys = Input::<KeyCode>::default();
+        keys.press(KeyCode::Equals);
+        keys.press(KeyCode::Minus);
+        assert_eq!(acceleration(&keys), Vec3::ZERO);
+    }
+
+    #[test]
+    fn test_snap_on_press_only() {
+        let bindings = Bindings::default();
+        let mouse = Input::<MouseButton>::default();
+        let gamepad = Input::<GamepadButton>::default();
+        let mut keys = Input::<KeyCode>::default();
+        keys.press(KeyCode::P);
+        assert_eq!(key_snap(&bindings, &ActionInput{keys: &keys, mouse: &mouse, gamepad: &gamepad}), Some(SnapTarget::Pole));
+        keys.update();
+        assert_eq!(key_snap(&bindings, &ActionInput{keys: &keys, mouse: &mouse, gamepad: &gamepad}), None);
+    }
+
+    #[test]
+    fn test_stick_matches_keys_at_full_deflection() {
+        let mut keys = Input::<KeyCode>::default();
+        keys.press(KeyCode::Right);
+        let stick = stick_acceleration(&StickSettings::default(), Vec2::new(1., 0.), 0.);
+        assert_eq!(stick, acceleration(&keys));
+    }
+
+    #[test]
+    fn test_stick_dead_zone() {
+        let stick = stick_acceleration(&StickSettings::default(), Vec2::new(0.1, -0.05), 0.1);
+        assert_eq!(stick, Vec3::ZERO);
+    }
+
+    #[test]
+    fn test_gamepads_follow_connections() {
+        let mut gamepads = Gamepads::default();
+        gamepads.update(&GamepadEvent(Gamepad(0), GamepadEventType::Connected));
+        gamepads.update(&GamepadEvent(Gamepad(1), GamepadEventType::Connected));
+        gamepads.update(&GamepadEvent(Gamepad(0), GamepadEventType::Connected));
+        assert_eq!(gamepads.0, vec![Gamepad(0), Gamepad(1)]);
+        gamepads.update(&GamepadEvent(Gamepad(0), GamepadEventType::Disconnected));
+        assert_eq!(gamepads.0, vec![Gamepad(1)]);
+    }
+
+    #[test]
+    fn test_gamepads_tracked_outside_any_state() {
+        let mut app = App::build();
+        app.add_event::<GamepadEvent>().add_plugin(GamepadsPlugin);
+        let mut app = app.app;
+        app.world.get_resource_mut::<Events<GamepadEvent>>().unwrap()
+            .send(GamepadEvent(Gamepad(2), GamepadEventType::Connected));
+        app.update();
+        assert_eq!(app.world.get_resource::<Gamepads>().unwrap().0, vec![Gamepad(2)]);
+    }
+}
@@ -0,0 +1,154 @@
+use bevy::app::Events;
+use bevy::core::CorePlugin;
+use bevy::input::InputPlugin;
+use bevy::input::gamepad::GamepadEventRaw;
+use bevy::input::mouse::MouseMotion;
+use bevy::prelude::*;
+use bevy::window::WindowPlugin;
+use stargazer::controls::bindings::Binding;
+use stargazer::controls::dynamics::{CameraDynamics, CameraInertia};
+use stargazer::controls::replay::*;
+use stargazer::render::camera::SkyCamera;
+use stargazer::states::stars::{fov_adjust, orbit_camera, Fov, MouseButtonPressed};
+use stargazer::world::clock::SimulationClock;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recording() -> InputRecording {
+        InputRecording(vec![
+            InputFrame{jd: 2459580.375, delta: 0., events: vec![]},
+            InputFrame{jd: 2459580.3750002, delta: 0.016, events: vec![
+                InputEvent::Cursor(Vec2::new(600., 350.5)),
+                InputEvent::Button(Binding::Mouse(MouseButton::Left), true),
+                InputEvent::Motion(Vec2::new(3., -2.25)),
+                InputEvent::Wheel{lines: true, x: 0., y: -1.},
+            ]},
+            InputFrame{jd: 2459580.3750004, delta: 0.0215, events: vec![
+                InputEvent::Button(Binding::Mouse(MouseButton::Left), false),
+                InputEvent::Button(Binding::Key(KeyCode::Left), true),
+                InputEvent::Wheel{lines: false, x: 0., y: 12.5},
+                InputEvent::Character(' '),
+                InputEvent::CursorLeft,
+            ]},
+            InputFrame{jd: 2459580.3750006, delta: 0.0166, events: vec![
+                InputEvent::Gamepad(GamepadEventRaw(Gamepad(1), GamepadEventType::Connected)),
+                InputEvent::Gamepad(GamepadEventRaw(Gamepad(1), GamepadEventType::ButtonChanged(GamepadButtonType::South, 1.))),
+                InputEvent::Gamepad(GamepadEventRaw(Gamepad(1), GamepadEventType::AxisChanged(GamepadAxisType::LeftStickY, -0.75))),
+            ]},
+        ])
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let recording = recording();
+        let text = recording.to_text();
+        assert!(text.contains("press Mouse:Left\n"));
+        assert!(text.contains("wheel pixel 0 12.5\n"));
+        assert!(text.contains("frame 2459580.3750004 0.0215\n"));
+        assert!(text.contains("char 32\n"));
+        assert!(text.contains("gamepad 1 button Gamepad:South 1\n"));
+        assert!(text.contains("gamepad 1 axis LeftStickY -0.75\n"));
+        assert_eq!(InputRecording::from_text(&text).unwrap(), recording);
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = InputRecording::from_text("motion 1 2").unwrap_err();
+        assert!(error.to_string().starts_with("line 1:"));
+        let error = InputRecording::from_text("frame 2459580.5\n\npress Nope").unwrap_err();
+        assert!(error.to_string().starts_with("line 3:"));
+        assert!(InputRecording::from_text("frame 2459580.5\nwheel page 0 1").is_err());
+        assert!(InputRecording::from_text("frame soon").is_err());
+        assert!(InputRecording::from_text("frame 2459580.5 0.016 0.016").is_err());
+        assert!(InputRecording::from_text("frame 2459580.5\ngamepad 0 axis Throttle 1").is_err());
+        assert!(InputRecording::from_text("frame 2459580.5\ngamepad 0 button Key:A 1").is_err());
+    }
+
+    #[test]
+    fn test_frame_without_delta() {
+        let recording = InputRecording::from_text("frame 2459580.5\npress A").unwrap();
+        assert_eq!(recording.0[0].delta, REPLAY_TIMESTEP);
+    }
+
+    #[test]
+    fn test_replay_order() {
+        let mut replay = Replay::new(recording(), None);
+        let jds: Vec<f64> = std::iter::from_fn(|| replay.next_frame().map(|frame| frame.jd)).collect();
+        assert_eq!(jds, vec![2459580.375, 2459580.3750002, 2459580.3750004, 2459580.3750006]);
+        assert!(replay.is_finished());
+        assert!(replay.next_frame().is_none());
+    }
+
+    /// Camera after replaying `recording` through the camera systems
+    fn replayed_camera(recording: &InputRecording, name: &str) -> (SkyCamera, f32) {
+        let path = std::env::temp_dir().join(format!("stargazer-replay-{}.rec", name));
+        recording.save(&path).unwrap();
+        let mut app = App::build();
+        app.add_plugin(CorePlugin)
+            .add_plugin(InputPlugin)
+            .add_plugin(WindowPlugin{add_primary_window: false, exit_on_close: false})
+            .add_plugin(ReplayPlugin{mode: ReplayMode::Replay{path: path.to_string_lossy().into_owned(), timestep: None}})
+            .insert_resource(SimulationClock::new(2459580.5))
+            .insert_resource(CameraDynamics::default())
+            .insert_resource(CameraInertia::default())
+            .insert_resource(SkyCamera::default())
+            .insert_resource(Fov(1.))
+            .insert_resource(MouseButtonPressed(false))
+            .add_system(fov_adjust.system().before("camera"))
+            .add_system(orbit_camera.system().label("camera"));
+        let mut app = app.app;
+        // live input, dropped by the replay
+        app.world.get_resource_mut::<Events<MouseMotion>>().unwrap()
+            .send(MouseMotion{delta: Vec2::new(500., 500.)});
+        for _ in 0..recording.0.len() {
+            app.update();
+        }
+        let _ = std::fs::remove_file(&path);
+        let camera = *app.world.get_resource::<SkyCamera>().unwrap();
+        let fov = app.world.get_resource::<Fov>().unwrap().0;
+        (camera, fov)
+    }
+
+    #[test]
+    fn test_replay_through_camera() {
+        let drag = InputRecording(vec![
+            InputFrame{jd: 2459580.5, delta: 0.1, events: vec![
+                InputEvent::Button(Binding::Mouse(MouseButton::Left), true),
+                InputEvent::Motion(Vec2::new(30., 0.)),
+            ]},
+            InputFrame{jd: 2459580.5, delta: 0.25, events: vec![
+                InputEvent::Button(Binding::Mouse(MouseButton::Left), false),
+            ]},
+            InputFrame{jd: 2459580.5, delta: 0.5, events: vec![
+                InputEvent::Wheel{lines: true, x: 0., y: 1.},
+            ]},
+        ]);
+        // the same motion stepped by hand with the recorded frame times
+        let dynamics = CameraDynamics::default();
+        let mut inertia = CameraInertia::default();
+        let mut expected = SkyCamera::default();
+        let mut fov = 1_f32;
+        inertia.push(Vec3::new(0., 30., 0.) * dynamics.drag_sensitivity);
+        expected.yaw((fov / 3.14 * 30. / 300.) as f64);
+        inertia.step(&dynamics, 0.1);
+        for (delta, scroll) in [(0.25, 0.), (0.5, dynamics.scroll_sensitivity)].iter() {
+            inertia.push(Vec3::new(0., 0., *scroll));
+            let distance = inertia.step(&dynamics, *delta);
+            expected.pitch((fov / 3.14 * distance.x) as f64);
+            expected.yaw((fov / 3.14 * distance.y) as f64);
+            fov -= distance.z;
+        }
+
+        let (camera, replayed_fov) = replayed_camera(&drag, "camera");
+        let (q, e) = (camera.orientation, expected.orientation);
+        for (a, b) in [(q.s, e.s), (q.v.x, e.v.x), (q.v.y, e.v.y), (q.v.z, e.v.z)].iter() {
+            assert!((a - b).abs() < 1e-9, "{:?} != {:?}", q, e);
+        }
+        assert!((replayed_fov - fov).abs() < 1e-6);
+        assert!(fov < 1.);
+        // the sky was thrown after the release
+        assert!(expected.forward() != SkyCamera::default().forward());
+    }
+}
@@ -1 +1,6 @@
-mod units;
\ No newline at end of file
+mod units;
+mod catalog;
+mod world;
+mod render;
+mod controls;
+mod states;
@@ -0,0 +1,115 @@
+use bevy::math::Vec2;
+use stargazer::catalog::reader::Catalogue;
+use stargazer::catalog::record::{StarRecord, NO_NAME};
+use stargazer::catalog::writer::CatalogueWriter;
+use stargazer::render::atlas::*;
+use stargazer::render::projection::ProjectionKind;
+use stargazer::world::deep_sky::{DeepSkyKind, DeepSkyObject};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn star(id: u32, ra: f64, dec: f64, mag: f32) -> StarRecord {
+        StarRecord {
+            id,
+            ra: ra.to_radians() as f32,
+            dec: dec.to_radians() as f32,
+            mag,
+            abs_mag: f32::NAN,
+            color_index: f32::NAN,
+            pm_ra: f32::NAN,
+            pm_dec: f32::NAN,
+            name: NO_NAME,
+            distance: f32::NAN,
+            designation: NO_NAME,
+            constellation: [0; 4],
+        }
+    }
+
+    fn catalogue() -> Catalogue {
+        let mut writer = CatalogueWriter::new();
+        writer.push(star(1, 83.8, -5.4, 1.));
+        writer.push(star(2, 85., -2., 5.));
+        // too faint
+        writer.push(star(3, 84., -6., 9.));
+        // out of the field
+        writer.push(star(4, 200., 40., 0.));
+        Catalogue::from_bytes(writer.to_bytes()).unwrap()
+    }
+
+    fn request() -> ChartRequest {
+        ChartRequest {
+            ra: 83.8_f64.to_radians(),
+            dec: -5.4_f64.to_radians(),
+            field: 20_f32.to_radians(),
+            projection: ProjectionKind::Stereographic,
+            max_mag: 6.,
+            layers: ChartLayers::default(),
+            size: Vec2::new(600., 800.),
+        }
+    }
+
+    #[test]
+    fn test_layers_from_str() {
+        let layers: ChartLayers = "stars, grid".parse().unwrap();
+        assert!(layers.stars && layers.grid);
+        assert!(!layers.constellations && !layers.labels && !layers.deep_sky && !layers.legend);
+        assert!("stars,planets".parse::<ChartLayers>().is_err());
+    }
+
+    #[test]
+    fn test_grid_steps() {
+        assert_eq!(grid_steps(50_f32.to_radians(), 0.), (15., 10.));
+        assert_eq!(grid_steps(2_f32.to_radians(), 0.), (0.5, 0.5));
+        // meridians are spaced wider near the pole
+        assert!(grid_steps(20_f32.to_radians(), 70_f64.to_radians()).0 > grid_steps(20_f32.to_radians(), 0.).0);
+    }
+
+    #[test]
+    fn test_chart_layers() {
+        let objects = vec![
+            DeepSkyObject {
+                name: "M42".to_string(),
+                kind: DeepSkyKind::Nebula,
+                ra: 83.82_f64.to_radians(),
+                dec: -5.39_f64.to_radians(),
+                mag: 4.,
+                size: 85_f64.to_radians() / 60.,
+            },
+            DeepSkyObject {
+                name: "M31".to_string(),
+                kind: DeepSkyKind::Galaxy,
+                ra: 10.68_f64.to_radians(),
+                dec: 41.27_f64.to_radians(),
+                mag: 3.4,
+                size: 178_f64.to_radians() / 60.,
+            },
+        ];
+        let figures = vec![vec![(83.8_f64.to_radians(), -5.4_f64.to_radians()), (85_f64.to_radians(), -2_f64.to_radians())]];
+        let chart = build_chart(&request(), &catalogue(), &figures, &objects);
+        assert_eq!(chart.size(), (600, 800));
+        assert_eq!(chart.stars.len(), 2);
+        assert!((chart.stars[0].position - Vec2::new(300., 400.)).length() < 0.5);
+        // the brighter star is larger
+        assert!(chart.stars[0].radius > chart.stars[1].radius);
+        assert!(chart.labels.iter().any(|label| label.text == "M42"));
+        assert!(!chart.labels.iter().any(|label| label.text == "M31"));
+        // grid labelled in hours and degrees
+        assert!(chart.labels.iter().any(|label| label.text == "05h30m"));
+        assert!(chart.labels.iter().any(|label| label.text == "+00°00'"));
+        let legend: Vec<&str> = chart.legend.iter().map(|(text, _)| text.as_str()).collect();
+        assert!(legend.contains(&"Centre 05h35m -05°24', field 20.0°"));
+        assert!(legend.contains(&"Stereographic projection, stars to mag 6.0"));
+        assert!(legend.contains(&"Grid every 15m of RA, 5° of Dec"));
+        assert!(legend.contains(&"Constellation lines"));
+        assert!(legend.contains(&"Nebula"));
+        assert!(!legend.contains(&"Galaxy"));
+
+        let mut bare = request();
+        bare.layers = "stars".parse().unwrap();
+        let chart = build_chart(&bare, &catalogue(), &figures, &objects);
+        assert_eq!(chart.stars.len(), 2);
+        assert!(chart.lines.is_empty() && chart.labels.is_empty() && chart.legend.is_empty());
+    }
+}
@@ -0,0 +1,89 @@
+use cgmath::{InnerSpace, Quaternion, Rotation3, Rad, Vector3};
+use stargazer::render::camera::{SkyCamera, CameraMode, CameraFrame, CameraTransition};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: Vector3<f64>, b: Vector3<f64>) -> bool {
+        (a - b).magnitude() < 1e-9
+    }
+
+    #[test]
+    fn test_default_looks_down_z() {
+        let camera = SkyCamera::default();
+        assert!(close(camera.forward(), -Vector3::unit_z()));
+        assert!(close(camera.up(), Vector3::unit_y()));
+    }
+
+    #[test]
+    fn test_keep_level_stays_level() {
+        let mut camera = SkyCamera::default();
+        for _ in 0..50 {
+            camera.pitch(0.013);
+            camera.yaw(0.027);
+        }
+        // screen right stays parallel to the equator
+        let right = camera.forward().cross(camera.up());
+        assert!(right.y.abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_pitch_stops_at_pole() {
+        let mut camera = SkyCamera::default();
+        for _ in 0..100 {
+            camera.pitch(0.1);
+        }
+        assert!(camera.forward().y < 1.);
+        assert!(camera.up().y >= 0.);
+    }
+
+    #[test]
+    fn test_roll_only_when_free() {
+        let mut camera = SkyCamera::default();
+        camera.roll(0.5);
+        assert!(close(camera.up(), Vector3::unit_y()));
+        camera.set_mode(CameraMode::Free);
+        camera.roll(0.5);
+        assert!((camera.up().y - 0.5f64.cos()).abs() < 1e-9);
+        camera.set_mode(CameraMode::KeepLevel);
+        assert!(close(camera.up(), Vector3::unit_y()));
+    }
+
+    #[test]
+    fn test_set_frame_keeps_view() {
+        let mut camera = SkyCamera::default();
+        camera.yaw(0.4);
+        camera.pitch(0.2);
+        let forward = camera.forward();
+        camera.set_frame(CameraFrame::Horizon, Quaternion::from_angle_x(Rad(0.7)));
+        assert!(close(camera.forward(), forward));
+    }
+
+    #[test]
+    fn test_view_matrix_inverts_rotation() {
+        let mut camera = SkyCamera::default();
+        camera.set_mode(CameraMode::Free);
+        camera.yaw(1.1);
+        camera.pitch(-0.3);
+        camera.roll(0.2);
+        let view = camera.view_matrix() * camera.forward();
+        assert!(close(view, -Vector3::unit_z()));
+    }
+
+    #[test]
+    fn test_transition_ends_on_target() {
+        let from = Quaternion::from_angle_y(Rad(0.2));
+        let to = Quaternion::from_angle_y(Rad(1.4)) * Quaternion::from_angle_x(Rad(0.5));
+        let mut transition = CameraTransition::new(from, to, 1.6, 0.2, 1.5);
+        let (start, start_fov) = transition.advance(0.);
+        assert!((start.s - from.s).abs() < 1e-9 && (start_fov - 1.6).abs() < 1e-6);
+        let (_, mid_fov) = transition.advance(0.75);
+        assert!(mid_fov < 1.6 && mid_fov > 0.2);
+        assert!(!transition.is_finished());
+        let (end, end_fov) = transition.advance(1.);
+        assert!(transition.is_finished());
+        assert!((end.s - to.s).abs() < 1e-9 && (end.v - to.v).magnitude() < 1e-9);
+        assert!((end_fov - 0.2).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,83 @@
+use ab_glyph::FontVec;
+use bevy::math::Vec2;
+use bevy::render::color::Color;
+use cgmath::{Vector3, Vector4};
+use stargazer::render::camera::SkyCamera;
+use stargazer::render::chart::*;
+use stargazer::render::paths::PathStyle;
+use stargazer::render::projection::ViewProjection;
+use stargazer::world::photometry::BrightnessModel;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chart(width: f32, height: f32, scale: f32) -> Chart {
+        let projection = ViewProjection::new(1.2, Vec2::new(width, height), SkyCamera::default().view_matrix());
+        Chart::new(projection, scale, Color::BLACK)
+    }
+
+    fn world_at(chart: &Chart, screen: Vec2) -> Vector4<f32> {
+        chart.projection.screen_to_world(screen).extend(1.)
+    }
+
+    #[test]
+    fn test_stars_shown_and_scaled() {
+        let model = BrightnessModel::default();
+        let mut small = chart(80., 60., 1.);
+        let mut large = chart(800., 600., 10.);
+        for chart in [&mut small, &mut large].iter_mut() {
+            let centre = world_at(chart, chart.projection.viewport / 2.);
+            chart.add_star(centre, 0., Color::WHITE, &model);
+            // fainter than the limit
+            chart.add_star(centre, 12., Color::WHITE, &model);
+            // behind the camera
+            chart.add_star(Vector4::new(-centre.x, -centre.y, -centre.z, 1.), 0., Color::WHITE, &model);
+        }
+        assert_eq!(small.stars.len(), 1);
+        assert_eq!(large.stars.len(), 1);
+        assert!((small.stars[0].position - Vec2::new(40., 30.)).length() < 1e-2);
+        assert!((large.stars[0].radius - small.stars[0].radius * 10.).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_paths_split_out_of_view() {
+        let mut chart = chart(800., 600., 2.);
+        // a full circle around the camera, starting behind it
+        let circle: Vec<Vector4<f32>> = (0..=64)
+            .map(|i| {
+                let angle = std::f32::consts::PI + i as f32 / 64. * std::f32::consts::TAU;
+                Vector3::new(angle.sin(), 0., -angle.cos()).extend(1.)
+            })
+            .collect();
+        chart.add_path(&circle, &PathStyle::dashed(Color::RED, 1.5, 4., 2.));
+        assert_eq!(chart.lines.len(), 1);
+        let line = &chart.lines[0];
+        assert_eq!(line.style.width, 3.);
+        assert_eq!(line.style.dash, 8.);
+        assert!(line.points.iter().all(|p| (p.y - 300.).abs() < 1e-2));
+    }
+
+    #[test]
+    fn test_raster() {
+        let font = FontVec::try_from_vec(std::fs::read("assets/fonts/ShareTechMono-Regular.ttf").unwrap()).unwrap();
+        let mut chart = chart(64., 48., 1.);
+        let model = BrightnessModel::default();
+        let star = world_at(&chart, Vec2::new(16.5, 24.5));
+        chart.add_star(star, 0., Color::WHITE, &model);
+        let from = world_at(&chart, Vec2::new(40.5, 2.));
+        let to = world_at(&chart, Vec2::new(40.5, 46.));
+        chart.add_path(&[from, to], &PathStyle::solid(Color::rgb(0., 1., 0.), 1.));
+        chart.add_label(Vec2::new(2., 2.), "N", Color::WHITE, 10., Vec2::ZERO);
+        let image = chart.to_image(&font);
+        assert_eq!(image.dimensions(), (64, 48));
+        // rows count from the top
+        assert_eq!(image.get_pixel(16, 23).0, [255, 255, 255, 255]);
+        assert!(image.get_pixel(40, 20).0[1] > 200);
+        assert_eq!(image.get_pixel(60, 5).0, [0, 0, 0, 255]);
+        let lit = (0..10).flat_map(|x| (38..46).map(move |y| (x, y)))
+            .filter(|(x, y)| image.get_pixel(*x, *y).0[0] > 0)
+            .count();
+        assert!(lit > 0);
+    }
+}
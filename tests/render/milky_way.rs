@@ -0,0 +1,28 @@
+use stargazer::render::milky_way::{procedural_brightness, procedural_texture};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_procedural_band() {
+        let centre = procedural_brightness(0., 0.);
+        let anticentre = procedural_brightness(std::f64::consts::PI, 0.);
+        let pole = procedural_brightness(0., std::f64::consts::FRAC_PI_2);
+        assert!(centre > anticentre);
+        assert!(anticentre > 0.1);
+        assert!(pole < 0.01);
+        // symmetric about the galactic plane and the centre
+        assert!((procedural_brightness(0.3, 0.1) - procedural_brightness(-0.3, -0.1)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_procedural_texture_layout() {
+        let texture = procedural_texture(64, 32);
+        assert_eq!(texture.data.len(), 64 * 32 * 4);
+        // the galactic centre is in the middle, the poles on the top row
+        let pixel = |x: usize, y: usize| texture.data[(y * 64 + x) * 4];
+        assert!(pixel(32, 16) > pixel(0, 16));
+        assert!(pixel(32, 16) > pixel(32, 0));
+    }
+}
@@ -0,0 +1,9 @@
+mod paths;
+mod night_vision;
+mod camera;
+mod projection;
+mod chart;
+mod vector;
+mod atlas;
+mod screenshot;
+mod milky_way;
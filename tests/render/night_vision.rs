@@ -0,0 +1,92 @@
+use bevy::prelude::*;
+use bevy::render::{
+    pass::{LoadOp, Operations, PassDescriptor, RenderPassDepthStencilAttachmentDescriptor, TextureAttachment},
+    render_graph::{base::{self, MainPass}, Edge, PassNode, RenderGraph, WindowSwapChainNode, WindowTextureNode},
+    texture::{Extent3d, TextureDescriptor, TextureFormat, TextureUsage},
+};
+use bevy::window::WindowId;
+use bevy_egui::{node::EGUI_PASS, RenderGraphConfig};
+use stargazer::render::night_vision::{self, night_color, node, NIGHT_BRIGHTNESS};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Window passes of a single sampled window: the sky pass and egui
+    fn window_graph() -> RenderGraph {
+        let mut graph = RenderGraph::default();
+        let msaa = Msaa{samples: 1};
+        graph.add_node(base::node::PRIMARY_SWAP_CHAIN, WindowSwapChainNode::new(WindowId::primary()));
+        graph.add_node(base::node::MAIN_DEPTH_TEXTURE, WindowTextureNode::new(WindowId::primary(), TextureDescriptor {
+            size: Extent3d{width: 1, height: 1, depth: 1},
+            format: TextureFormat::Depth32Float,
+            usage: TextureUsage::OUTPUT_ATTACHMENT,
+            ..Default::default()
+        }));
+        graph.add_node(base::node::MAIN_PASS, PassNode::<&MainPass>::new(PassDescriptor {
+            color_attachments: vec![msaa.color_attachment_descriptor(
+                TextureAttachment::Input("color_attachment".to_string()),
+                TextureAttachment::Input("color_resolve_target".to_string()),
+                Operations{load: LoadOp::Load, store: true},
+            )],
+            depth_stencil_attachment: Some(RenderPassDepthStencilAttachmentDescriptor {
+                attachment: TextureAttachment::Input("depth".to_string()),
+                depth_ops: Some(Operations{load: LoadOp::Clear(1.0), store: true}),
+                stencil_ops: None,
+            }),
+            sample_count: 1,
+        }));
+        graph.add_slot_edge(base::node::PRIMARY_SWAP_CHAIN, WindowSwapChainNode::OUT_TEXTURE, base::node::MAIN_PASS, "color_attachment").unwrap();
+        graph.add_slot_edge(base::node::MAIN_DEPTH_TEXTURE, WindowTextureNode::OUT_TEXTURE, base::node::MAIN_PASS, "depth").unwrap();
+        bevy_egui::setup_pipeline(&mut graph, &msaa, RenderGraphConfig::default());
+        night_vision::setup_graph(&mut graph, Handle::default());
+        graph
+    }
+
+    /// Node the color attachment of a pass comes from
+    fn color_source(graph: &RenderGraph, pass: &'static str) -> &'static str {
+        let state = graph.get_node_state(pass).unwrap();
+        let index = state.input_slots.get_slot_index("color_attachment").unwrap();
+        let output_node = state.edges.get_input_slot_edge(index).unwrap().get_output_node();
+        let sources = [base::node::PRIMARY_SWAP_CHAIN, node::NIGHT_VISION_TEXTURE];
+        sources.iter().find(|source| graph.get_node_id(**source).unwrap() == output_node).unwrap()
+    }
+
+    #[test]
+    fn test_night_vision_reroutes_egui() {
+        let mut graph = window_graph();
+        assert_eq!(color_source(&graph, EGUI_PASS), base::node::PRIMARY_SWAP_CHAIN);
+        night_vision::route_to_night_vision(&mut graph, true).unwrap();
+        assert_eq!(color_source(&graph, EGUI_PASS), node::NIGHT_VISION_TEXTURE);
+        assert_eq!(color_source(&graph, base::node::MAIN_PASS), node::NIGHT_VISION_TEXTURE);
+        // the night pass still draws to the window, after egui
+        let night_pass = graph.get_node_state(node::NIGHT_VISION_PASS).unwrap();
+        let swap_chain = graph.get_node_id(base::node::PRIMARY_SWAP_CHAIN).unwrap();
+        assert!(night_pass.edges.input_edges.iter().any(|edge| edge.get_output_node() == swap_chain));
+        let egui_pass = graph.get_node_id(EGUI_PASS).unwrap();
+        assert!(night_pass.edges.input_edges.contains(&Edge::NodeEdge{input_node: night_pass.id, output_node: egui_pass}));
+
+        night_vision::route_to_night_vision(&mut graph, false).unwrap();
+        assert_eq!(color_source(&graph, EGUI_PASS), base::node::PRIMARY_SWAP_CHAIN);
+        assert_eq!(color_source(&graph, base::node::MAIN_PASS), base::node::PRIMARY_SWAP_CHAIN);
+        let texture = graph.get_node_state(node::NIGHT_VISION_TEXTURE).unwrap();
+        assert_eq!(texture.edges.output_edges.len(), 1);
+    }
+
+    #[test]
+    fn test_routing_twice_changes_nothing() {
+        let mut graph = window_graph();
+        night_vision::route_to_night_vision(&mut graph, true).unwrap();
+        night_vision::route_to_night_vision(&mut graph, true).unwrap();
+        assert_eq!(color_source(&graph, EGUI_PASS), node::NIGHT_VISION_TEXTURE);
+        let swap_chain = graph.get_node_state(base::node::PRIMARY_SWAP_CHAIN).unwrap();
+        assert_eq!(swap_chain.edges.output_edges.len(), 1);
+    }
+
+    #[test]
+    fn test_night_color_is_red() {
+        let [r, g, b, a] = night_color(Color::rgba_linear(0.2, 1., 0.5, 0.5)).as_linear_rgba_f32();
+        assert!((r - NIGHT_BRIGHTNESS).abs() < 1e-6);
+        assert_eq!((g, b, a), (0., 0., 0.5));
+    }
+}
@@ -0,0 +1,70 @@
+use cgmath::Vector4;
+use bevy::math::Vec2;
+use stargazer::render::paths::{clip_segment, border_crossings, Border};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inside_segment_kept() {
+        let a = Vector4::new(-0.5, 0., 0., 1.);
+        let b = Vector4::new(0.5, 0., 0., 1.);
+        assert_eq!(clip_segment(a, b), Some((0., 1.)));
+    }
+
+    #[test]
+    fn test_clipped_at_screen_edge() {
+        let a = Vector4::new(0., 0., 0., 1.);
+        let b = Vector4::new(2., 0., 0., 1.);
+        assert_eq!(clip_segment(a, b), Some((0., 0.25)));
+    }
+
+    #[test]
+    fn test_clipped_to_visible_area() {
+        // runs from the centre to twice past the top right corner
+        let a = Vector4::new(0., 0., 0., 2.);
+        let b = Vector4::new(2., 2., 0., 2.);
+        let (_, t1) = clip_segment(a, b).unwrap();
+        assert!((t1 - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_clipped_behind_camera() {
+        // crosses the near plane: the part with w <= |z| is dropped
+        let a = Vector4::new(0., 0., 0., 1.);
+        let b = Vector4::new(0., 0., 2., -1.);
+        let (t0, t1) = clip_segment(a, b).unwrap();
+        assert_eq!(t0, 0.);
+        assert!(t1 > 0. && t1 < 0.5);
+    }
+
+    #[test]
+    fn test_outside_segment_dropped() {
+        let a = Vector4::new(2., 0., 0., 1.);
+        let b = Vector4::new(3., 1., 0., 1.);
+        assert_eq!(clip_segment(a, b), None);
+    }
+
+    #[test]
+    fn test_border_crossings() {
+        let half = Vec2::new(0.5, 0.5);
+        let path = vec![
+            Vector4::new(-0.8, 0.1, 0., 1.),
+            Vector4::new(0., 0.1, 0., 1.),
+            Vector4::new(0.8, 0.1, 0., 1.),
+        ];
+        let crossings = border_crossings(&path, half);
+        assert_eq!(crossings.len(), 2);
+        assert_eq!(crossings[0].border, Border::Left);
+        assert_eq!(crossings[1].border, Border::Right);
+        assert!((crossings[0].position.y - 0.1).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_crossing_outside_viewport_ignored() {
+        // crosses the line x = 0.5 above the top right corner
+        let path = vec![Vector4::new(0.4, 0.6, 0., 1.), Vector4::new(0.6, 0.7, 0., 1.)];
+        assert!(border_crossings(&path, Vec2::new(0.5, 0.5)).is_empty());
+    }
+}
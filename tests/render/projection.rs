@@ -0,0 +1,106 @@
+use bevy::math::Vec2;
+use cgmath::{InnerSpace, Quaternion, Rad, Rotation, Rotation3};
+use stargazer::render::camera::SkyCamera;
+use stargazer::render::projection::{ProjectionKind, ViewProjection};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn projection() -> ViewProjection {
+        let mut camera = SkyCamera::default();
+        camera.yaw(0.3);
+        camera.pitch(0.2);
+        ViewProjection::new(1.2, Vec2::new(800., 600.), camera.view_matrix())
+    }
+
+    #[test]
+    fn test_centre_of_view() {
+        let mut camera = SkyCamera::default();
+        camera.yaw(0.3);
+        camera.pitch(0.2);
+        let projection = projection();
+        let forward = camera.forward().cast::<f32>().unwrap();
+        let screen = projection.world_to_screen(forward.extend(1.)).unwrap();
+        assert!((screen - Vec2::new(400., 300.)).length() < 1e-2);
+        assert!(projection.is_visible(forward.extend(1.)));
+        assert!(!projection.is_visible((-forward).extend(1.)));
+    }
+
+    #[test]
+    fn test_screen_roundtrip() {
+        let projection = projection();
+        let screen = Vec2::new(130., 420.);
+        let direction = projection.screen_to_world(screen);
+        assert!((direction.magnitude() - 1.).abs() < 1e-5);
+        let back = projection.world_to_screen(direction.extend(1.)).unwrap();
+        assert!((back - screen).length() < 1e-2);
+    }
+
+    #[test]
+    fn test_pixels_per_radian() {
+        let projection = projection();
+        let centre = projection.screen_to_world(Vec2::new(400., 300.));
+        let step = 0.01;
+        let offset = projection.screen_to_world(Vec2::new(400., 300. + step * projection.pixels_per_radian()));
+        let angle = centre.angle(offset).0;
+        assert!((angle - step).abs() < 1e-4);
+    }
+
+    fn stereographic() -> ViewProjection {
+        let mut camera = SkyCamera::default();
+        camera.yaw(0.3);
+        camera.pitch(0.2);
+        ViewProjection::with_kind(ProjectionKind::Stereographic, 1.2, Vec2::new(800., 600.), camera.view_matrix())
+    }
+
+    #[test]
+    fn test_stereographic_roundtrip() {
+        let projection = stereographic();
+        for screen in [Vec2::new(400., 300.), Vec2::new(130., 420.), Vec2::new(790., 10.)].iter() {
+            let direction = projection.screen_to_world(*screen);
+            assert!((direction.magnitude() - 1.).abs() < 1e-5);
+            let back = projection.world_to_screen(direction.extend(1.)).unwrap();
+            assert!((back - *screen).length() < 1e-2);
+        }
+        let centre = projection.screen_to_world(Vec2::new(400., 300.));
+        let step = 0.01;
+        let offset = projection.screen_to_world(Vec2::new(400. + step * projection.pixels_per_radian(), 300.));
+        assert!((centre.angle(offset).0 - step).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_stereographic_keeps_wide_fields() {
+        // a point 100° away is still in front of a stereographic camera
+        let projection = stereographic();
+        let centre = projection.screen_to_world(Vec2::new(400., 300.));
+        let side = projection.screen_to_world(Vec2::new(800., 300.));
+        let axis = centre.cross(side).normalize();
+        let far = Quaternion::from_axis_angle(axis, Rad(100_f32.to_radians())).rotate_vector(centre);
+        let screen = projection.world_to_screen(far.extend(1.)).unwrap();
+        assert!(screen.x > 800.);
+        assert!((screen.y - 300.).abs() < 1.);
+    }
+
+    #[test]
+    fn test_fov_for_field() {
+        let field = 0.5;
+        for kind in ProjectionKind::ALL.iter() {
+            let fov = kind.fov_for_field(field);
+            let projection = ViewProjection::with_kind(*kind, fov, Vec2::new(800., 600.), SkyCamera::default().view_matrix());
+            let top = projection.screen_to_world(Vec2::new(400., 600.));
+            let bottom = projection.screen_to_world(Vec2::new(400., 0.));
+            assert!((top.angle(bottom).0 - field).abs() < 1e-4);
+        }
+        assert_eq!("stereographic".parse::<ProjectionKind>().unwrap(), ProjectionKind::Stereographic);
+        assert!("mercator".parse::<ProjectionKind>().is_err());
+    }
+
+    #[test]
+    fn test_visible_within_margin() {
+        let projection = projection();
+        let outside = projection.screen_to_world(Vec2::new(805., 300.));
+        assert!(!projection.is_visible(outside.extend(1.)));
+        assert!(projection.is_visible_within(outside.extend(1.), 10.));
+    }
+}
@@ -0,0 +1,19 @@
+use stargazer::render::screenshot::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_padded_rows() {
+        // two pixels wide, rows padded to 12 bytes
+        let data = [
+            1, 2, 3, 128, 4, 5, 6, 0, 9, 9, 9, 9,
+            7, 8, 9, 255, 10, 11, 12, 64, 9, 9, 9, 9,
+        ];
+        assert_eq!(bgra_rows_to_rgba(&data, 2, 2, 12), vec![
+            3, 2, 1, 255, 6, 5, 4, 255,
+            9, 8, 7, 255, 12, 11, 10, 255,
+        ]);
+    }
+}
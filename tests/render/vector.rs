@@ -0,0 +1,83 @@
+use bevy::math::Vec2;
+use bevy::render::color::Color;
+use stargazer::render::camera::SkyCamera;
+use stargazer::render::chart::*;
+use stargazer::render::paths::PathStyle;
+use stargazer::render::projection::ViewProjection;
+use stargazer::render::vector::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chart() -> Chart {
+        let projection = ViewProjection::new(1.2, Vec2::new(200., 100.), SkyCamera::default().view_matrix());
+        let mut chart = Chart::new(projection, 1., Color::WHITE);
+        chart.stars.push(ChartStar{position: Vec2::new(50., 25.), radius: 2., color: Color::rgba(0., 0., 0., 0.5)});
+        chart.lines.push(ChartLine {
+            points: vec![Vec2::new(0., 10.), Vec2::new(100., 10.), Vec2::new(100., 90.)],
+            style: PathStyle::dashed(Color::RED, 1.5, 4., 2.),
+        });
+        chart.add_label(Vec2::new(10., 10.), "M42 <Orion>", Color::BLACK, 10., Vec2::ZERO);
+        chart.add_legend("Centre 05h35m -05°23'", None);
+        chart
+    }
+
+    #[test]
+    fn test_svg() {
+        let svg = to_svg(&chart());
+        assert!(svg.contains("viewBox=\"0 0 200 100\""));
+        // rows count from the top
+        assert!(svg.contains("<circle cx=\"50.00\" cy=\"75.00\" r=\"2.00\" fill=\"#000000\" fill-opacity=\"0.500\"/>"));
+        assert!(svg.contains("points=\"0.00,90.00 100.00,90.00 100.00,10.00\""));
+        assert!(svg.contains("stroke-dasharray=\"4.00 2.00\""));
+        assert!(svg.contains(">M42 &lt;Orion&gt;</text>"));
+        assert!(svg.contains(">Centre 05h35m -05°23'</text>"));
+        assert!(svg.trim_end().ends_with("</svg>"));
+    }
+
+    #[test]
+    fn test_pdf() {
+        let pdf = to_pdf(&chart());
+        let text = String::from_utf8(pdf).unwrap();
+        assert!(text.starts_with("%PDF-1.4\n"));
+        assert!(text.ends_with("%%EOF\n"));
+        assert!(text.contains("/MediaBox [0 0 200 100]"));
+        assert!(text.contains("0.00 10.00 m 100.00 10.00 l 100.00 90.00 l S"));
+        assert!(text.contains("(M42 <Orion>) Tj"));
+        // latin-1 characters are escaped in octal
+        assert!(text.contains("(Centre 05h35m -05\\26023') Tj"));
+        // the stream length leaves out the end of line before endstream
+        let start = text.find("stream\n").unwrap() + "stream\n".len();
+        let end = text.find("\nendstream").unwrap();
+        let length: usize = text[text.find("/Length ").unwrap() + 8..].split(' ').next().unwrap().parse().unwrap();
+        assert_eq!(length, end - start);
+        // every cross reference points at its object
+        let xref = text.find("\nxref\n").unwrap() + 1;
+        let startxref: usize = text.lines().rev().nth(1).unwrap().parse().unwrap();
+        assert_eq!(startxref, xref);
+        let offsets: Vec<usize> = text[xref..].lines().skip(3).take(5)
+            .map(|line| line[..10].parse().unwrap())
+            .collect();
+        for (index, offset) in offsets.iter().enumerate() {
+            assert!(text[*offset..].starts_with(&format!("{} 0 obj", index + 1)));
+        }
+    }
+
+    #[test]
+    fn test_save_by_extension() {
+        let chart = chart();
+        let dir = std::env::temp_dir().join("stargazer-vector-test");
+        save_chart(&chart, dir.join("chart.svg")).unwrap();
+        save_chart(&chart, dir.join("chart.PDF")).unwrap();
+        assert!(std::fs::read_to_string(dir.join("chart.svg")).unwrap().contains("<svg"));
+        assert!(std::fs::read(dir.join("chart.PDF")).unwrap().starts_with(b"%PDF"));
+        assert!(save_chart(&chart, dir.join("chart.png")).is_err());
+    }
+
+    #[test]
+    fn test_monospace_width() {
+        assert_eq!(monospace_width("05h35m", 10.), 36.);
+        assert_eq!(monospace_width("-05°", 10.), 24.);
+    }
+}
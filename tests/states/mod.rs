@@ -0,0 +1,2 @@
+mod tour;
+mod stars;
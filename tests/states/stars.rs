@@ -0,0 +1,75 @@
+use bevy::math::Vec2;
+use cgmath::{InnerSpace, Vector3, Vector4};
+use stargazer::render::camera::{CameraFrame, SkyCamera};
+use stargazer::render::paths::Border;
+use stargazer::render::projection::ViewProjection;
+use stargazer::states::stars::{aim_camera, grid_marker_spots, horizon_rotation, MarkerAnchor, Path2DKind};
+use stargazer::states::tour::{parse_script, TourCommand, TourPlayer, TourTarget};
+use stargazer::world::frames::to_cartesian;
+use stargazer::world::observer::Observer;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn across() -> Vec<Vector4<f32>> {
+        vec![
+            Vector4::new(-0.8, 0.1, 0., 1.),
+            Vector4::new(0., 0.1, 0., 1.),
+            Vector4::new(0.8, 0.1, 0., 1.),
+        ]
+    }
+
+    #[test]
+    fn test_equator_named_at_borders() {
+        let projection = ViewProjection { viewport: Vec2::new(800., 600.), ..Default::default() };
+        let spots = grid_marker_spots(&Path2DKind::Equator, &across(), &projection);
+        let anchors: Vec<MarkerAnchor> = spots.iter().map(|(anchor, _)| *anchor).collect();
+        assert_eq!(anchors, vec![MarkerAnchor::Name(Border::Left, 0), MarkerAnchor::Name(Border::Right, 0)]);
+        assert!((spots[0].1 - Vec2::new(0., 360.)).length() < 1e-3);
+    }
+
+    #[test]
+    fn test_grid_lines_labelled_on_their_sides() {
+        let projection = ViewProjection { viewport: Vec2::new(800., 600.), ..Default::default() };
+        let theta = grid_marker_spots(&Path2DKind::ThetaCircle, &across(), &projection);
+        assert_eq!(theta.len(), 2);
+        assert!(matches!(theta[0].0, MarkerAnchor::Border(Border::Left, 0)));
+        // meridian-like lines are only labelled on the top and bottom
+        assert!(grid_marker_spots(&Path2DKind::PhiCircle, &across(), &projection).is_empty());
+    }
+
+    #[test]
+    fn test_goto_after_observer_and_time_in_one_batch() {
+        let script = "observer Saint-Véran\ntime 2459595.3333\ngoto 05:55:10 07:24:25\nwait 4";
+        let mut player = TourPlayer::new("orion", parse_script(script).unwrap());
+        let batch = player.advance(0.);
+        assert_eq!(batch.len(), 3);
+        let (mut observer, mut jd) = (Observer::default(), 2451545.);
+        let mut camera = SkyCamera {
+            frame: CameraFrame::Horizon,
+            frame_rotation: horizon_rotation(&observer, jd),
+            ..Default::default()
+        };
+        // applied in order, as the sky view does within one frame
+        let mut target = None;
+        for command in batch {
+            match command {
+                TourCommand::Observer(name) => {
+                    observer = Observer::sites().into_iter().find(|site| site.name == name).unwrap();
+                }
+                TourCommand::Time(Some(time)) => jd = time,
+                TourCommand::Goto(TourTarget::Position{ra, dec}) => {
+                    target = Some((ra, dec, aim_camera(&camera, &observer, jd, ra, dec)));
+                }
+                command => panic!("unexpected {:?}", command),
+            }
+        }
+        let (ra, dec, orientation) = target.unwrap();
+        // the frame follows the new site and time before the flight lands
+        camera.frame_rotation = horizon_rotation(&observer, jd);
+        camera.orientation = orientation;
+        let v = to_cartesian(ra, dec);
+        assert!((camera.forward() - Vector3::new(v.x, v.z, v.y)).magnitude() < 1e-9);
+    }
+}
@@ -0,0 +1,84 @@
+use bevy::prelude::*;
+use stargazer::consts::AppState;
+use stargazer::states::tour::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_script() {
+        let script = "# test\ngoto Betelgeuse\nfov 30\n\nwait 2.5\nshow Equatorial grid\ncaption Orion\ntime 2451545\nrate 60\n";
+        let commands = parse_script(script).unwrap();
+        assert_eq!(commands, vec![
+            TourCommand::Goto(TourTarget::Object("Betelgeuse".to_string())),
+            TourCommand::Fov(30f32.to_radians()),
+            TourCommand::Wait(2.5),
+            TourCommand::Layer("Equatorial grid".to_string(), true),
+            TourCommand::Caption("Orion".to_string()),
+            TourCommand::Time(Some(2451545.)),
+            TourCommand::Rate(60.),
+        ]);
+    }
+
+    #[test]
+    fn test_parse_coordinates() {
+        let commands = parse_script("goto 06:00:00 -30:00:00").unwrap();
+        match &commands[0] {
+            TourCommand::Goto(TourTarget::Position{ra, dec}) => {
+                assert!((ra - std::f64::consts::FRAC_PI_2).abs() < 1e-12);
+                assert!((dec + 30f64.to_radians()).abs() < 1e-12);
+            }
+            command => panic!("unexpected {:?}", command),
+        }
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse_script("jump 3").is_err());
+        assert!(parse_script("wait soon").is_err());
+        assert!(parse_script("goto").is_err());
+        assert!(parse_script("show").is_err());
+    }
+
+    #[test]
+    fn test_player_waits() {
+        let commands = parse_script("fov 10\nwait 1\nrate 2\nrate 3\nwait 1\ncaption end").unwrap();
+        let mut player = TourPlayer::new("test", commands);
+        assert_eq!(player.advance(0.), vec![TourCommand::Fov(10f32.to_radians())]);
+        assert!(player.advance(0.5).is_empty());
+        assert_eq!(player.advance(0.6), vec![TourCommand::Rate(2.), TourCommand::Rate(3.)]);
+        // the 0.1 s overshoot counts towards the next wait
+        assert!(player.advance(0.85).is_empty());
+        assert_eq!(player.advance(0.1), vec![TourCommand::Caption("end".to_string())]);
+        assert!(player.is_finished());
+    }
+
+    #[test]
+    fn test_player_pause_and_step() {
+        let commands = parse_script("wait 10\nrate 2\nwait 10\nrate 3").unwrap();
+        let mut player = TourPlayer::new("test", commands);
+        player.paused = true;
+        assert!(player.advance(20.).is_empty());
+        assert_eq!(player.step(), vec![TourCommand::Rate(2.)]);
+        assert_eq!(player.step(), vec![TourCommand::Rate(3.)]);
+        assert!(player.is_finished());
+    }
+
+    #[test]
+    fn test_stop_twice_in_a_frame() {
+        let mut app = App::build();
+        app.add_state(AppState::Stars);
+        let mut app = app.app;
+        app.world.get_resource_mut::<State<AppState>>().unwrap().push(AppState::Tour).unwrap();
+        app.update();
+        assert_eq!(app.world.get_resource::<State<AppState>>().unwrap().current(), &AppState::Tour);
+        {
+            let mut app_state = app.world.get_resource_mut::<State<AppState>>().unwrap();
+            stop_tour(&mut app_state);
+            stop_tour(&mut app_state);
+        }
+        app.update();
+        assert_eq!(app.world.get_resource::<State<AppState>>().unwrap().current(), &AppState::Stars);
+    }
+}
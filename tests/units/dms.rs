@@ -0,0 +1,30 @@
+use std::str::FromStr;
+use stargazer::units::dms::DMS;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create() {
+        let dms = DMS::from_str("-16:42:58.0").unwrap();
+        assert_eq!(dms.degrees, -16);
+        assert_eq!(dms.minutes, 42);
+        assert!(dms.negative);
+        assert!((dms.to_degrees() + 16.716111).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_negative_below_one_degree() {
+        let dms = DMS::from_str("-0:30:00").unwrap();
+        assert_eq!(dms.to_degrees(), -0.5);
+        assert_eq!(DMS::from_degrees(-0.5).to_degrees(), -0.5);
+    }
+
+    #[test]
+    fn test_label() {
+        assert_eq!(DMS::from_degrees(18.5).label(), "+18°30'");
+        assert_eq!(DMS::from_degrees(-16.716111).label(), "-16°43'");
+        assert_eq!(DMS::from_degrees(0.).label(), "+00°00'");
+    }
+}
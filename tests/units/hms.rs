@@ -13,4 +13,19 @@ mod tests {
         assert_eq!(hms.minutes, 04);
         assert_eq!(hms.seconds, 20.99);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_from_degrees_wraps() {
+        let hms = HMS::from_degrees(-22.5);
+        assert_eq!((hms.hours, hms.minutes), (22, 30));
+        let hms = HMS::from_degrees(382.5);
+        assert_eq!((hms.hours, hms.minutes), (1, 30));
+    }
+
+    #[test]
+    fn test_label() {
+        assert_eq!(HMS::from_degrees(101.25).label(), "06h45m");
+        assert_eq!(HMS::from_degrees(-15.).label(), "23h00m");
+        assert_eq!(HMS::from_degrees(359.999).label(), "00h00m");
+    }
+}
@@ -1 +1,2 @@
-pub mod hms;
\ No newline at end of file
+pub mod hms;
+mod dms;
\ No newline at end of file
@@ -0,0 +1,26 @@
+use stargazer::world::color::{bv_to_temperature, bv_to_rgb};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sun_temperature() {
+        let t = bv_to_temperature(0.656);
+        assert!((t - 5800.).abs() < 150.);
+    }
+
+    #[test]
+    fn test_betelgeuse_redder_than_rigel() {
+        let betelgeuse = bv_to_rgb(1.5, 1.);
+        let rigel = bv_to_rgb(-0.03, 1.);
+        assert!(betelgeuse[0] > betelgeuse[2]);
+        assert!(rigel[2] > rigel[0]);
+    }
+
+    #[test]
+    fn test_saturation() {
+        assert_eq!(bv_to_rgb(1.5, 0.), [1., 1., 1.]);
+        assert_eq!(bv_to_rgb(f32::NAN, 1.), [1., 1., 1.]);
+    }
+}
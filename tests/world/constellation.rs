@@ -0,0 +1,52 @@
+use stargazer::catalog::reader::Catalogue;
+use stargazer::catalog::record::StarRecord;
+use stargazer::catalog::writer::CatalogueWriter;
+use stargazer::world::constellation::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn catalogue() -> Catalogue {
+        let mut writer = CatalogueWriter::new();
+        for (id, (name, designation)) in [("Betelgeuse", "Alp Ori"), ("Bellatrix", "Gam Ori")].iter().enumerate() {
+            let name = writer.add_name(name);
+            let designation = writer.add_name(designation);
+            writer.push(StarRecord {
+                id: id as u32,
+                ra: id as f32 * 0.1,
+                dec: 0.1,
+                mag: 0.5,
+                abs_mag: f32::NAN,
+                color_index: f32::NAN,
+                pm_ra: f32::NAN,
+                pm_dec: f32::NAN,
+                name,
+                distance: f32::NAN,
+                designation,
+                constellation: [0; 4],
+            });
+        }
+        Catalogue::from_bytes(writer.to_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_resolve() {
+        let figures = parse_figures("# Orion\n\nOri: Betelgeuse, Gam Ori\nOri: Alp Ori, Bet Ori\n").unwrap();
+        assert_eq!(figures.len(), 2);
+        assert_eq!(figures[0].constellation, "Ori");
+        assert_eq!(figures[0].stars, vec!["Betelgeuse".to_string(), "Gam Ori".to_string()]);
+        let catalogue = catalogue();
+        let points = figures[0].resolve(&catalogue).unwrap();
+        assert_eq!(points.len(), 2);
+        assert!((points[1].0 - 0.1).abs() < 1e-6);
+        assert_eq!(figures[1].resolve(&catalogue), Err("Bet Ori".to_string()));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse_figures("Ori Betelgeuse, Bellatrix").unwrap_err().to_string().contains("line 1"));
+        assert!(parse_figures("\nOri: Betelgeuse").unwrap_err().to_string().contains("line 2"));
+        assert!(parse_figures("Ori: Betelgeuse, , Bellatrix").is_err());
+    }
+}
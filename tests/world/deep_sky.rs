@@ -0,0 +1,43 @@
+use stargazer::world::deep_sky::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OBJECTS: &str = "# bright objects
+name,type,ra,dec,mag,size
+M42,Nb,05:35:17.3,-05:23:28,4.0,85
+M57, PN, 18:53:35.1, +33:01:45, , 
+";
+
+    #[test]
+    fn test_read_deep_sky() {
+        let objects = read_deep_sky(OBJECTS.as_bytes()).unwrap();
+        assert_eq!(objects.len(), 2);
+        let m42 = &objects[0];
+        assert_eq!(m42.kind, DeepSkyKind::Nebula);
+        assert!((m42.ra.to_degrees() - 83.822).abs() < 1e-3);
+        assert!((m42.dec.to_degrees() + 5.391).abs() < 1e-3);
+        assert!((m42.size.to_degrees() * 60. - 85.).abs() < 1e-9);
+        let m57 = &objects[1];
+        assert_eq!(m57.kind, DeepSkyKind::PlanetaryNebula);
+        assert!(m57.mag.is_nan());
+        assert_eq!(m57.size, 0.);
+        assert_eq!(find(&objects, "m 57").map(|o| o.name.as_str()), Some("M57"));
+        assert!(find(&objects, "M1").is_none());
+    }
+
+    #[test]
+    fn test_rejects_unknown_type() {
+        let text = "name,type,ra,dec,mag,size\nM42,Nb,05:35:17.3,-05:23:28,4.0,85\nM99,Qs,12:18:49.6,+14:24:59,9.9,5\n";
+        let error = read_deep_sky(text.as_bytes()).unwrap_err().to_string();
+        assert!(error.contains("row 3"));
+        assert!(error.contains("Qs"));
+    }
+
+    #[test]
+    fn test_bundled_objects() {
+        let objects = read_deep_sky(std::fs::File::open("assets/data/deep_sky.csv").unwrap()).unwrap();
+        assert!(objects.iter().any(|o| o.name == "M31" && o.kind == DeepSkyKind::Galaxy));
+    }
+}
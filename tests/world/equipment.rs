@@ -0,0 +1,72 @@
+use cgmath::InnerSpace;
+use stargazer::world::equipment::{Telescope, Corrector, Eyepiece, Sensor};
+use stargazer::world::overlay::{circle, rectangle, crosshair, Reticle};
+use stargazer::world::frames::to_cartesian;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eyepiece_field() {
+        let telescope = Telescope::new("200/1000", 1000., 200.);
+        let eyepiece = Eyepiece::new("25mm", 25., 52.);
+        let none = Corrector::new("None", 1.);
+        assert_eq!(eyepiece.magnification(&telescope, &none), 40.);
+        assert!((eyepiece.true_field(&telescope, &none).to_degrees() - 1.3).abs() < 1e-9);
+        assert_eq!(eyepiece.exit_pupil(&telescope, &none), 5.);
+        let barlow = Corrector::new("2x Barlow", 2.);
+        assert_eq!(eyepiece.magnification(&telescope, &barlow), 80.);
+    }
+
+    #[test]
+    fn test_sensor_field() {
+        let telescope = Telescope::new("80/480", 480., 80.);
+        let sensor = Sensor::new("APS-C", 6000, 4000, 3.72);
+        let none = Corrector::new("None", 1.);
+        let (width, height) = sensor.field(&telescope, &none);
+        assert!((width.to_degrees() - 2.66).abs() < 0.01);
+        assert!((height.to_degrees() - 1.78).abs() < 0.01);
+        assert!((sensor.image_scale(&telescope, &none) - 1.60).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_circle_radius() {
+        let (lon, lat) = (1.2, -0.4);
+        let center = to_cartesian(lon, lat);
+        for p in circle(lon, lat, 0.01, 16) {
+            assert!((p.dot(center).acos() - 0.01).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_rectangle_is_closed_and_centred() {
+        let (lon, lat) = (0.3, 0.8);
+        let points = rectangle(lon, lat, 0.02, 0.01, 0.5, 4);
+        assert_eq!(points.len(), 17);
+        assert_eq!(points[0], points[16]);
+        // opposite corners are symmetric about the centre
+        let center = to_cartesian(lon, lat);
+        assert!((points[0].dot(center) - points[8].dot(center)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_crosshair_crosses_at_center() {
+        let (lon, lat) = (2., 0.1);
+        let [a, b] = crosshair(lon, lat, 0.1, 8);
+        let center = to_cartesian(lon, lat);
+        assert!((a[4] - center).magnitude() < 1e-12);
+        assert!((b[4] - center).magnitude() < 1e-12);
+        assert!(a[0].dot(b[0]).acos() > 0.05);
+    }
+
+    #[test]
+    fn test_telrad_rings() {
+        let outlines = Reticle::telrad().outlines(0., 0.);
+        assert_eq!(outlines.len(), 3);
+        let center = to_cartesian(0., 0.);
+        let radius = outlines[2][0].dot(center).acos().to_degrees();
+        assert!((radius - 2.).abs() < 1e-9);
+        assert_eq!(Reticle::raci().outlines(0., 0.).len(), 3);
+    }
+}
@@ -0,0 +1,60 @@
+use stargazer::world::frames::{Frame, J2000, obliquity};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64, tolerance_deg: f64) -> bool {
+        (a - b).abs() < tolerance_deg.to_radians()
+    }
+
+    #[test]
+    fn test_galactic_center() {
+        let (ra, dec) = Frame::Galactic.to_equatorial(0., 0., J2000);
+        assert!(close(ra, 266.405_f64.to_radians(), 0.01));
+        assert!(close(dec, (-28.936_f64).to_radians(), 0.01));
+    }
+
+    #[test]
+    fn test_north_galactic_pole() {
+        let (_, b) = Frame::Galactic.from_equatorial(192.859_f64.to_radians(), 27.128_f64.to_radians(), J2000);
+        assert!(close(b, 90_f64.to_radians(), 0.01));
+    }
+
+    #[test]
+    fn test_supergalactic_pole() {
+        // north supergalactic pole at galactic (47.37, 6.32)
+        let (ra, dec) = Frame::Galactic.to_equatorial(47.37_f64.to_radians(), 6.32_f64.to_radians(), J2000);
+        let (_, sgb) = Frame::Supergalactic.from_equatorial(ra, dec, J2000);
+        assert!(close(sgb, 90_f64.to_radians(), 0.01));
+    }
+
+    #[test]
+    fn test_ecliptic_at_j2000() {
+        assert!(close(obliquity(J2000), 23.4393_f64.to_radians(), 0.0001));
+        let (lon, lat) = Frame::Ecliptic.from_equatorial(0., 90_f64.to_radians(), J2000);
+        assert!(close(lat, 90_f64.to_radians() - obliquity(J2000), 1e-6));
+        assert!(close(lon, 90_f64.to_radians(), 1e-6));
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let jd = J2000 + 9000.;
+        // the published galactic rotation is only orthogonal to ~1e-10
+        for frame in Frame::ALL.iter() {
+            let (lon, lat) = frame.from_equatorial(1.2, -0.4, jd);
+            let (ra, dec) = frame.to_equatorial(lon, lat, jd);
+            assert!(close(ra, 1.2, 1e-7));
+            assert!(close(dec, -0.4, 1e-7));
+        }
+    }
+
+    #[test]
+    fn test_frames_of_date() {
+        let century = J2000 + 36525.;
+        for frame in Frame::ALL.iter() {
+            let moved = frame.from_equatorial_matrix(J2000) != frame.from_equatorial_matrix(century);
+            assert_eq!(moved, frame.is_of_date(), "{}", frame.name());
+        }
+    }
+}
@@ -0,0 +1,8 @@
+mod color;
+mod photometry;
+mod frames;
+mod observer;
+mod sky;
+mod equipment;
+mod deep_sky;
+mod constellation;
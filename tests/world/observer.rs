@@ -0,0 +1,33 @@
+use stargazer::world::observer::Observer;
+use stargazer::world::frames::J2000;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zenith_declination_is_latitude() {
+        let observer = Observer::default();
+        let (ra, dec) = observer.horizon_to_equatorial(0., std::f64::consts::FRAC_PI_2, J2000);
+        assert!((dec - observer.latitude.to_radians()).abs() < 1e-9);
+        assert!((ra - observer.local_sidereal_time(J2000)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let observer = Observer::default();
+        let (ra, dec) = observer.horizon_to_equatorial(1.2, 0.4, J2000 + 0.3);
+        let (az, alt) = observer.equatorial_to_horizon(ra, dec, J2000 + 0.3);
+        assert!((az - 1.2).abs() < 1e-9);
+        assert!((alt - 0.4).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_sky_turns_westward() {
+        // an object on the eastern horizon rises as sidereal time passes
+        let observer = Observer::default();
+        let (ra, dec) = observer.horizon_to_equatorial(std::f64::consts::FRAC_PI_2, 0., J2000);
+        let (_, alt) = observer.equatorial_to_horizon(ra, dec, J2000 + 1. / 24.);
+        assert!(alt > 0.);
+    }
+}
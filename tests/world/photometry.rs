@@ -0,0 +1,42 @@
+use stargazer::world::photometry::BrightnessModel;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_brighter_is_bigger() {
+        let model = BrightnessModel::default();
+        let sirius = model.point_source(-1.46, 1.6);
+        let vega = model.point_source(0.03, 1.6);
+        assert!(sirius.radius > vega.radius);
+        assert_eq!(sirius.alpha, 1.);
+    }
+
+    #[test]
+    fn test_fades_at_limit() {
+        let model = BrightnessModel::default();
+        let faint = model.point_source(5.5, 1.6);
+        assert_eq!(faint.radius, model.min_radius);
+        assert!(faint.alpha > 0. && faint.alpha < 1.);
+        assert_eq!(model.point_source(6.5, 1.6).alpha, 0.);
+    }
+
+    #[test]
+    fn test_zoom_reveals_fainter_stars() {
+        let model = BrightnessModel::default();
+        assert_eq!(model.point_source(6.5, 1.6).alpha, 0.);
+        assert!(model.point_source(6.5, 0.16).alpha > 0.);
+        assert!(model.limit_at(0.16) > model.limit_at(1.6));
+    }
+
+    #[test]
+    fn test_sky_limit_caps_user_limit() {
+        let mut model = BrightnessModel { limiting_mag: 5., ..Default::default() };
+        model.sky_limit = 3.5;
+        assert_eq!(model.limit_at(1.6), 3.5);
+        // a darker sky gives the user's setting back
+        model.sky_limit = 7.;
+        assert_eq!(model.limit_at(1.6), 5.);
+    }
+}
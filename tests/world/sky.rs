@@ -0,0 +1,77 @@
+use stargazer::world::ephemeris::{sun_longitude, moon_illumination};
+use stargazer::world::sky::{SkyConditions, SkyState, MILKY_WAY_SURFACE_BRIGHTNESS, twilight_darkness};
+use stargazer::world::deep_sky::read_deep_sky;
+use stargazer::world::frames::J2000;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sun_longitude() {
+        assert!((sun_longitude(J2000).to_degrees() - 280.38).abs() < 0.05);
+        // June solstice, 2021-06-21 03:32 UTC
+        assert!((sun_longitude(2459386.647).to_degrees() - 90.).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_moon_phases() {
+        // full moon 2021-09-20 23:55 UTC, new moon 2021-10-06 11:05 UTC
+        assert!(moon_illumination(2459478.4965) > 0.99);
+        assert!(moon_illumination(2459493.962) < 0.01);
+    }
+
+    #[test]
+    fn test_twilight() {
+        assert_eq!(twilight_darkness(0.), 0.);
+        assert_eq!(twilight_darkness((-20_f64).to_radians()), 1.);
+        let halfway = twilight_darkness((-12_f64).to_radians());
+        assert!((halfway - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_daylight_hides_diffuse_light() {
+        let sky = SkyState{sun_altitude: 0.5, moon_altitude: -0.5, moon_illumination: 0.};
+        assert_eq!(SkyConditions::from_bortle(1).diffuse_visibility(&sky), 0.);
+        let night = SkyState{sun_altitude: -1., ..sky};
+        assert_eq!(SkyConditions::from_bortle(1).diffuse_visibility(&night), 1.);
+    }
+
+    #[test]
+    fn test_bortle_roundtrip() {
+        for class in 1..=9 {
+            assert_eq!(SkyConditions::from_bortle(class).bortle(), class);
+        }
+        assert_eq!(SkyConditions::from_bortle(12).bortle(), 9);
+    }
+
+    #[test]
+    fn test_light_pollution_hides_stars_and_milky_way() {
+        let dark = SkyConditions::from_bortle(1);
+        let city = SkyConditions::from_bortle(9);
+        assert!(dark.limiting_magnitude() > 6.5);
+        assert!(city.limiting_magnitude() < 4.);
+        assert!(dark.extended_visibility(MILKY_WAY_SURFACE_BRIGHTNESS) > 0.99);
+        assert_eq!(city.extended_visibility(MILKY_WAY_SURFACE_BRIGHTNESS), 0.);
+        assert!(city.background_color()[1] > dark.background_color()[1]);
+    }
+
+    #[test]
+    fn test_light_pollution_fades_faint_objects() {
+        let text = "name,type,ra,dec,mag,size\nM31,Gx,00:42:44.3,+41:16:09,3.4,190\nM57,PN,18:53:35.1,+33:01:45,8.8,1.4\nM1,Nb,05:34:31.9,+22:00:52,,\n";
+        let objects = read_deep_sky(text.as_bytes()).unwrap();
+        let sb: Vec<Option<f32>> = objects.iter().map(|o| o.surface_brightness()).collect();
+        // a large galaxy spreads its light much thinner than a small nebula
+        assert!(sb[0].unwrap() > sb[1].unwrap() + 4.);
+        assert!(sb[2].is_none());
+        let night = SkyState{sun_altitude: -1., moon_altitude: -0.5, moon_illumination: 0.};
+        let dark = SkyConditions::from_bortle(2);
+        let suburb = SkyConditions::from_bortle(6);
+        assert!(dark.object_visibility(&night, sb[0]) > 0.99);
+        assert_eq!(suburb.object_visibility(&night, sb[0]), 0.);
+        assert!(suburb.object_visibility(&night, sb[1]) > 0.99);
+        assert_eq!(suburb.object_visibility(&night, sb[2]), 1.);
+        let day = SkyState{sun_altitude: 0.5, ..night};
+        assert_eq!(dark.object_visibility(&day, sb[1]), 0.);
+    }
+}